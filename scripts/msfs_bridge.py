@@ -4,34 +4,207 @@ MSFS SimConnect Bridge
 连接 MSFS 并通过 UDP 发送飞行数据到 Virtual ATC
 """
 
+import re
 import sys
 import time
 import json
 import socket
+from ctypes import POINTER, addressof, c_char, c_double, cast
 from SimConnect import SimConnect, AircraftRequests, AircraftEvents
+from SimConnect.Enum import (
+    SIMCONNECT_DATATYPE,
+    SIMCONNECT_RECV_ID,
+    SIMCONNECT_RECV_SIMOBJECT_DATA_BYTYPE,
+    SIMCONNECT_SIMOBJECT_TYPE,
+    SIMCONNECT_UNUSED,
+)
+
+# 桥接协议版本，必须与 src-tauri/src/modules/msfs_bridge.rs 中的 PROTOCOL_VERSION 一致
+PROTOCOL_VERSION = 2
+BRIDGE_VERSION = "1.1.0"
+HEARTBEAT_INTERVAL = 1.0  # 秒
+
+# AI/联机飞机：每 2 秒请求一次 50 公里内的全部飞机
+TRAFFIC_INTERVAL = 2.0
+TRAFFIC_RADIUS_M = 50000
+TRAFFIC_MAX_TARGETS = 40
+TRAFFIC_VARS = [
+    ("PLANE LATITUDE", "degrees", "latitude"),
+    ("PLANE LONGITUDE", "degrees", "longitude"),
+    ("PLANE ALTITUDE", "feet", "altitude"),
+    ("PLANE HEADING DEGREES TRUE", "degrees", "heading"),
+    ("GROUND VELOCITY", "knots", "ground_speed"),
+    ("VERTICAL SPEED", "feet per minute", "vertical_speed"),
+    ("SIM ON GROUND", "bool", "on_ground"),
+]
+TRAFFIC_STRINGS = ["ATC ID", "ATC MODEL"]  # 各 32 字节
+
+def bcd16_to_squawk(value):
+    """SimConnect 的应答机编码为 BCD16 格式，转换为 4 位八进制数字（如 0x7000 -> 7000）"""
+    value = int(value)
+    return int(f"{value:04x}") if value else 0
+
+def text(value):
+    """SimConnect 的字符串变量以 bytes 返回，转换为 str"""
+    if isinstance(value, bytes):
+        return value.decode('utf-8', errors='ignore').rstrip('\x00').strip()
+    return str(value or "").strip()
+
+def icao_model(value):
+    """ATC MODEL 形如 "ATCCOM.AC_MODEL A320.0.text"，部分机型直接为 "C172"，提取 ICAO 机型代码"""
+    model = text(value)
+    match = re.search(r"AC_MODEL[ _]?([A-Z0-9]{2,4})", model)
+    if match:
+        return match.group(1)
+    return model.upper() if re.fullmatch(r"[A-Za-z0-9]{2,4}", model) else ""
+
+class TrafficSimConnect(SimConnect):
+    """在 SimConnect 消息分发中截获按类型请求的飞机数据（Python-SimConnect 只处理本机数据）"""
+    
+    def __init__(self, *args, **kwargs):
+        self.traffic_request = None
+        self.traffic_buffer = {}
+        super().__init__(*args, **kwargs)
+    
+    def my_dispatch_proc(self, pData, cbData, pContext):
+        if self.traffic_request is not None \
+                and pData.contents.dwID == SIMCONNECT_RECV_ID.SIMCONNECT_RECV_ID_SIMOBJECT_DATA_BYTYPE:
+            obj = cast(pData, POINTER(SIMCONNECT_RECV_SIMOBJECT_DATA_BYTYPE)).contents
+            if obj.dwRequestID == int(self.traffic_request.value):
+                self.traffic_buffer[obj.dwObjectID] = decode_traffic(obj.dwObjectID, addressof(obj.dwData))
+                return
+        super().my_dispatch_proc(pData, cbData, pContext)
+
+class TrafficReader:
+    """周期性请求周围飞机，返回上一次请求的完整快照"""
+    
+    def __init__(self, sm):
+        self.sm = sm
+        self.definition = sm.new_def_id()
+        for name, units, _ in TRAFFIC_VARS:
+            sm.dll.AddToDataDefinition(sm.hSimConnect, self.definition.value, name.encode(), units.encode(),
+                                       SIMCONNECT_DATATYPE.SIMCONNECT_DATATYPE_FLOAT64, 0, SIMCONNECT_UNUSED)
+        for name in TRAFFIC_STRINGS:
+            sm.dll.AddToDataDefinition(sm.hSimConnect, self.definition.value, name.encode(), None,
+                                       SIMCONNECT_DATATYPE.SIMCONNECT_DATATYPE_STRING32, 0, SIMCONNECT_UNUSED)
+        sm.traffic_request = sm.new_request_id()
+        self.last_request = 0.0
+    
+    def poll(self, own):
+        """到达请求间隔时返回快照（不含本机），否则返回 None"""
+        if time.time() - self.last_request < TRAFFIC_INTERVAL:
+            return None
+        
+        snapshot = [
+            target for target in self.sm.traffic_buffer.values()
+            if abs(target["latitude"] - own["latitude"]) > 1e-5 or abs(target["longitude"] - own["longitude"]) > 1e-5
+        ]
+        # 只发送最近的若干架，保证单个 UDP 数据报不会过大
+        snapshot.sort(key=lambda t: (t["latitude"] - own["latitude"]) ** 2 + (t["longitude"] - own["longitude"]) ** 2)
+        snapshot = snapshot[:TRAFFIC_MAX_TARGETS]
+        self.sm.traffic_buffer = {}
+        self.sm.dll.RequestDataOnSimObjectType(self.sm.hSimConnect, self.sm.traffic_request.value, self.definition.value,
+                                               TRAFFIC_RADIUS_M, SIMCONNECT_SIMOBJECT_TYPE.SIMCONNECT_SIMOBJECT_TYPE_AIRCRAFT)
+        
+        first = self.last_request == 0.0
+        self.last_request = time.time()
+        return None if first else snapshot
+
+def decode_traffic(object_id, address):
+    """按数据定义的顺序解析：若干 FLOAT64，之后是 32 字节字符串"""
+    values = (c_double * len(TRAFFIC_VARS)).from_address(address)
+    raw = (c_char * (32 * len(TRAFFIC_STRINGS))).from_address(address + 8 * len(TRAFFIC_VARS)).raw
+    strings = [text(raw[i * 32:(i + 1) * 32]) for i in range(len(TRAFFIC_STRINGS))]
+    
+    target = {field: value for (_, _, field), value in zip(TRAFFIC_VARS, values)}
+    target["on_ground"] = bool(target["on_ground"])
+    target["id"] = f"msfs-{object_id}"
+    target["callsign"] = strings[0]
+    target["aircraft_type"] = icao_model(strings[1])
+    return target
+
+def send(sock, target, message):
+    sock.sendto(json.dumps(message).encode('utf-8'), target)
+
+def handshake(sock, target):
+    """每秒发送一次 hello，直到 Virtual ATC 回复 welcome 或 reject"""
+    sock.settimeout(1.0)
+    while True:
+        send(sock, target, {
+            "type": "hello",
+            "protocol_version": PROTOCOL_VERSION,
+            "bridge_version": BRIDGE_VERSION,
+        })
+        try:
+            reply = json.loads(sock.recv(4096))
+        except socket.timeout:
+            continue
+        except (OSError, ValueError):
+            # Virtual ATC 尚未开始监听时 Windows 会报告连接被重置
+            time.sleep(1)
+            continue
+        
+        if reply.get("type") == "welcome":
+            return True
+        if reply.get("type") == "reject":
+            print(f"Rejected by Virtual ATC: {reply.get('reason')}", file=sys.stderr)
+            return False
+
+def check_rejected(sock):
+    """非阻塞读取 Virtual ATC 的回复，收到 reject 时返回 True"""
+    sock.setblocking(False)
+    try:
+        while True:
+            reply = json.loads(sock.recv(4096))
+            if reply.get("type") == "reject":
+                print(f"Rejected by Virtual ATC: {reply.get('reason')}", file=sys.stderr)
+                return True
+    except (BlockingIOError, OSError, ValueError):
+        return False
 
 def main():
     print("Starting MSFS SimConnect bridge...")
     
+    # 创建 UDP socket
+    sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+    target = ("127.0.0.1", 49001)
+    
+    print("UDP socket created, sending to", target)
+    
+    if not handshake(sock, target):
+        sys.exit(2)
+    
+    print(f"Handshake complete (protocol v{PROTOCOL_VERSION})")
+    seq = 0
+    
     try:
         # 连接到 MSFS
-        sm = SimConnect()
+        sm = TrafficSimConnect()
         aq = AircraftRequests(sm, _time=200)
         
         print("Connected to MSFS")
         
-        # 创建 UDP socket
-        sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
-        target = ("127.0.0.1", 49001)
+        # 交通数据为可选功能，失败时只发送本机数据
+        try:
+            traffic = TrafficReader(sm)
+        except Exception as e:
+            print(f"Traffic requests unavailable: {e}", file=sys.stderr)
+            traffic = None
         
-        print("UDP socket created, sending to", target)
+        last_sent = time.time()
         
         # 主循环
         while True:
+            if check_rejected(sock):
+                sys.exit(2)
+            
             try:
                 # 读取飞行数据
                 data = {
-                    "callsign": aq.get("ATC_ID") or "",
+                    "callsign": text(aq.get("ATC_ID")),
+                    "atc_airline": text(aq.get("ATC_AIRLINE")),
+                    "atc_flight_number": text(aq.get("ATC_FLIGHT_NUMBER")),
+                    "aircraft_type": icao_model(aq.get("ATC_MODEL")),
                     "altitude": aq.get("INDICATED_ALTITUDE") or 0.0,
                     "speed": aq.get("AIRSPEED_INDICATED") or 0.0,
                     "heading": aq.get("PLANE_HEADING_DEGREES_MAGNETIC") or 0.0,
@@ -39,26 +212,55 @@ def main():
                     "latitude": aq.get("PLANE_LATITUDE") or 0.0,
                     "longitude": aq.get("PLANE_LONGITUDE") or 0.0,
                     "on_ground": bool(aq.get("SIM_ON_GROUND") or 0),
+                    "altitude_agl": aq.get("PLANE_ALT_ABOVE_GROUND") or 0.0,
+                    "ground_speed": aq.get("GROUND_VELOCITY") or 0.0,
+                    "gear_ratio": aq.get("GEAR_HANDLE_POSITION") or 0.0,
+                    "flap_ratio": (aq.get("FLAPS_HANDLE_PERCENT") or 0.0) / 100.0,
+                    "com1_active": aq.get("COM_ACTIVE_FREQUENCY:1") or 0.0,
+                    "com1_standby": aq.get("COM_STANDBY_FREQUENCY:1") or 0.0,
+                    "com2_active": aq.get("COM_ACTIVE_FREQUENCY:2") or 0.0,
+                    "com2_standby": aq.get("COM_STANDBY_FREQUENCY:2") or 0.0,
+                    "nav1_active": aq.get("NAV_ACTIVE_FREQUENCY:1") or 0.0,
+                    "nav1_standby": aq.get("NAV_STANDBY_FREQUENCY:1") or 0.0,
+                    "nav2_active": aq.get("NAV_ACTIVE_FREQUENCY:2") or 0.0,
+                    "nav2_standby": aq.get("NAV_STANDBY_FREQUENCY:2") or 0.0,
+                    "transponder_code": bcd16_to_squawk(aq.get("TRANSPONDER_CODE:1") or 0),
+                    "transponder_mode": int(aq.get("TRANSPONDER_STATE:1") or 0),
+                    "parking_brake": aq.get("BRAKE_PARKING_POSITION") or 0.0,
                 }
                 
-                # 发送 JSON 数据
-                json_data = json.dumps(data).encode('utf-8')
-                sock.sendto(json_data, target)
+                # 发送飞行数据
+                seq += 1
+                send(sock, target, {"type": "telemetry", "seq": seq, "data": data})
+                last_sent = time.time()
+                
+                # 发送周围飞机快照
+                targets = traffic.poll(data) if traffic else None
+                if targets is not None:
+                    seq += 1
+                    send(sock, target, {"type": "traffic", "seq": seq, "targets": targets})
                 
                 # 每秒更新 5 次
                 time.sleep(0.2)
                 
             except Exception as e:
                 print(f"Error reading data: {e}", file=sys.stderr)
+                send(sock, target, {"type": "error", "message": f"Error reading data: {e}"})
+                
+                # 读取失败时保持心跳，避免 Virtual ATC 误判桥接已退出
+                if time.time() - last_sent >= HEARTBEAT_INTERVAL:
+                    seq += 1
+                    send(sock, target, {"type": "heartbeat", "seq": seq})
+                    last_sent = time.time()
                 time.sleep(1)
                 
     except Exception as e:
         print(f"Failed to connect to MSFS: {e}", file=sys.stderr)
         print("Make sure MSFS is running and SimConnect is enabled", file=sys.stderr)
+        send(sock, target, {"type": "error", "message": f"Failed to connect to MSFS: {e}"})
         sys.exit(1)
     finally:
-        if 'sock' in locals():
-            sock.close()
+        sock.close()
         if 'sm' in locals():
             sm.exit()
 
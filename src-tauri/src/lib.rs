@@ -3,166 +3,335 @@
 
 mod modules;
 
-use modules::simulator::SimulatorConnection;
+use modules::simulator::{SimulatorConnection, XPlaneConfig};
 use modules::whisper::WhisperEngine;
 use modules::llm::LLMClient;
 use modules::tts::TTSEngine;
-use modules::msfs::MSFSConnection;
-use modules::flight_phase::{FlightPhaseDetector, FlightPhase};
-use modules::atc_database::ATCDatabase;
-use modules::little_navmap::LittleNavmapDB;
+use modules::msfs::{MSFSConfig, MSFSConnection};
+use modules::replay::ReplayConnection;
+use modules::recorder::{FlightRecorder, SharedRecorder};
+use modules::traffic::{SharedTraffic, TrafficInfo, TrafficTable};
+use modules::data_source::{ConnectionStatus, FlightDataSource};
+use modules::callsign::{self, Callsign, CallsignProfile};
+use modules::aircraft::{self, Aircraft, AircraftProfile};
+use modules::flight_phase::{FlightPhaseDetector, FlightPhase, PhaseRecord};
+use modules::atc_database::{ATCDatabase, TaxiRoute};
+use modules::taxi_network::{RouteOptions, TaxiStart, TaxiTarget};
+use modules::{apt_dat, our_airports};
+use modules::little_navmap::{DatabaseInfo, DatabaseType, LittleNavmapDB, NavmapConfig, ProcedureKind};
 use modules::model_manager::{ModelManager, WhisperModel};
-use std::sync::Mutex;
-use tauri::State;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, State};
+
+/// 附近机场的搜索半径（公里）
+const NEARBY_AIRPORT_RADIUS_KM: f64 = 50.0;
+
+/// 上下文中列出的附近导航台和航路点数量
+const NEARBY_FIX_LIMIT: usize = 10;
+
+/// 移动超过该距离（公里）后重新确定附近机场和标高
+const NEARBY_FIELD_REFRESH_KM: f64 = 5.0;
+
+/// 最近一次确定的附近机场及标高（飞行数据每秒轮询，避免每次都查询数据库）
+struct NearbyField {
+    latitude: f64,
+    longitude: f64,
+    icao: Option<String>,
+    elevation: Option<f64>,
+}
 
 struct AppState {
-    simulator: Mutex<Option<SimulatorConnection>>,
-    msfs: Mutex<Option<MSFSConnection>>,
+    simulator: Mutex<Option<Box<dyn FlightDataSource>>>,
+    recorder: SharedRecorder,
+    traffic: SharedTraffic,
+    callsign_profile: Mutex<CallsignProfile>,
+    aircraft_profile: Mutex<AircraftProfile>,
     whisper: Mutex<Option<WhisperEngine>>,
     llm: Mutex<LLMClient>,
     tts: Mutex<TTSEngine>,
-    current_sim: Mutex<String>, // "xplane" or "msfs"
     phase_detector: Mutex<FlightPhaseDetector>,
     atc_database: Mutex<ATCDatabase>,
     little_navmap: Mutex<Option<LittleNavmapDB>>,
+    navmap_airport: Mutex<Option<String>>,   // 最近一次从 Little Navmap 合并的机场
+    nearby_field: Mutex<Option<NearbyField>>, // 缓存的附近机场标高，换库、导入或机场变化时清除
+    closed_taxiways: Mutex<Vec<String>>,     // 规划滑行路线时避开的滑行道
     model_manager: Mutex<ModelManager>,
 }
 
 #[tauri::command]
-async fn connect_simulator(sim_type: String, state: State<'_, AppState>) -> Result<String, String> {
-    let mut current_sim = state.current_sim.lock().unwrap();
+async fn connect_simulator(
+    sim_type: String,
+    xplane_config: Option<XPlaneConfig>,
+    msfs_config: Option<MSFSConfig>,
+    replay_file: Option<String>,
+    replay_speed: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    // 先断开已有连接，disconnect 返回时端口已释放
+    if let Some(mut previous) = state.simulator.lock().unwrap().take() {
+        previous.disconnect();
+    }
+    state.traffic.lock().unwrap().clear();
+    state.phase_detector.lock().unwrap().reset();
+    *state.nearby_field.lock().unwrap() = None;
     
-    match sim_type.as_str() {
+    let source: Box<dyn FlightDataSource> = match sim_type.as_str() {
         "xplane" => {
-            let mut sim = state.simulator.lock().unwrap();
-            match SimulatorConnection::new() {
-                Ok(connection) => {
-                    *sim = Some(connection);
-                    *current_sim = "xplane".to_string();
-                    Ok("✓ 已连接到 X-Plane\n正在接收飞行数据...".to_string())
+            // 使用传入的配置并保存，否则使用上次保存的配置
+            let config = match xplane_config {
+                Some(config) => {
+                    config.validate().map_err(|e| format!("❌ X-Plane 连接配置无效\n\n{}", e))?;
+                    if let Err(e) = config.save() {
+                        eprintln!("Failed to save X-Plane config: {}", e);
+                    }
+                    config
                 }
+                None => XPlaneConfig::load(),
+            };
+            
+            match SimulatorConnection::new(&config, state.recorder.clone(), state.traffic.clone()) {
+                Ok(connection) => Box::new(connection),
                 Err(e) => {
-                    Err(format!("❌ 无法连接到 X-Plane\n\n可能的原因：\n• X-Plane 未运行\n• 飞机未加载\n• 防火墙阻止 UDP 端口 49000\n\n详细错误：{}", e))
+                    return Err(format!("❌ 无法连接到 X-Plane（{}:{}）\n\n可能的原因：\n• X-Plane 未运行\n• 飞机未加载\n• 防火墙阻止 UDP 端口 {} / {}\n\n详细错误：{}", config.sim_host, config.sim_port, config.sim_port, config.listen_port, e));
                 }
             }
         }
         "msfs" => {
-            let mut msfs = state.msfs.lock().unwrap();
-            match MSFSConnection::new() {
+            // 使用传入的配置并保存，否则使用上次保存的配置
+            let config = match msfs_config {
+                Some(config) => {
+                    config.validate().map_err(|e| format!("❌ MSFS 桥接配置无效\n\n{}", e))?;
+                    if let Err(e) = config.save() {
+                        eprintln!("Failed to save MSFS config: {}", e);
+                    }
+                    config
+                }
+                None => MSFSConfig::load(),
+            };
+            
+            match MSFSConnection::new(state.recorder.clone(), state.traffic.clone()) {
                 Ok(mut connection) => {
-                    match connection.connect() {
-                        Ok(_) => {
-                            *msfs = Some(connection);
-                            *current_sim = "msfs".to_string();
-                            Ok("✓ 已连接到 MSFS\n正在接收飞行数据...".to_string())
-                        }
+                    match connection.connect(&config) {
+                        Ok(_) => Box::new(connection),
                         Err(e) => {
-                            Err(format!("❌ 无法连接到 MSFS\n\n可能的原因：\n• MSFS 未运行\n• SimConnect 未安装\n• Python 桥接脚本启动失败\n\n详细错误：{}", e))
+                            return Err(format!("❌ 无法连接到 MSFS\n\n可能的原因：\n• Python 解释器路径错误（当前：{}）\n• 桥接脚本不存在（当前：{}）\n• UDP 端口 49001 被占用\n\n详细错误：{}", config.python_path, config.script_path, e));
                         }
                     }
                 }
                 Err(e) => {
-                    Err(format!("❌ 初始化 MSFS 连接失败\n\n请确保：\n• 已安装 Python\n• 已安装 SimConnect-Python (pip install SimConnect-Python)\n\n详细错误：{}", e))
+                    return Err(format!("❌ 初始化 MSFS 连接失败\n\n请确保：\n• 已安装 Python\n• 已安装 SimConnect-Python (pip install SimConnect-Python)\n\n详细错误：{}", e));
+                }
+            }
+        }
+        "replay" => {
+            // 未指定文件时从环境变量读取
+            let path = replay_file
+                .or_else(|| std::env::var("VIRTUAL_ATC_REPLAY_FILE").ok())
+                .ok_or("❌ 未指定飞行记录文件\n\n请选择 JSONL/CSV 飞行记录，或设置环境变量 VIRTUAL_ATC_REPLAY_FILE")?;
+            
+            match ReplayConnection::from_file(&path, replay_speed.unwrap_or(1.0)) {
+                Ok(connection) => Box::new(connection),
+                Err(e) => {
+                    return Err(format!("❌ 无法加载飞行记录\n\n文件：{}\n\n详细错误：{}", path, e));
                 }
             }
         }
-        _ => Err("❌ 无效的模拟器类型\n\n请使用 'xplane' 或 'msfs'".to_string())
+        _ => return Err("❌ 无效的模拟器类型\n\n请使用 'xplane'、'msfs' 或 'replay'".to_string())
+    };
+    
+    let message = format!("✓ 已连接到 {}\n正在接收飞行数据...", source.display_name());
+    let preferred_db = DatabaseType::for_simulator(source.sim_type());
+    
+    *state.simulator.lock().unwrap() = Some(source);
+    
+    // 自动选择时换用与模拟器对应的 Little Navmap 数据库
+    let navmap_config = NavmapConfig::load();
+    let current_db = state.little_navmap.lock().unwrap().as_ref().map(|db| db.db_type());
+    if navmap_config.is_auto() && preferred_db.is_some() && current_db != preferred_db {
+        match switch_navmap(&state, &navmap_config) {
+            Ok(info) => println!("✓ Little Navmap 数据库: {:?} ({})", info.db_type, info.path),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    
+    Ok(message)
+}
+
+#[tauri::command]
+fn get_connection_status(state: State<'_, AppState>) -> ConnectionStatus {
+    match &*state.simulator.lock().unwrap() {
+        Some(source) => source.status(),
+        None => ConnectionStatus::disconnected(),
+    }
+}
+
+#[tauri::command]
+fn get_xplane_config() -> XPlaneConfig {
+    XPlaneConfig::load()
+}
+
+#[tauri::command]
+fn get_msfs_config() -> MSFSConfig {
+    MSFSConfig::load()
+}
+
+/// 其他航空器相对本机的位置（按距离排序）
+#[tauri::command]
+fn get_traffic(state: State<'_, AppState>) -> Result<Vec<TrafficInfo>, String> {
+    let data = {
+        let sim = state.simulator.lock().unwrap();
+        match &*sim {
+            Some(source) => source.get_flight_data().map_err(|e| e.to_string())?,
+            None => return Err("No simulator connected".to_string()),
+        }
+    };
+    
+    Ok(state.traffic.lock().unwrap().nearby(&data))
+}
+
+#[tauri::command]
+fn get_callsign_profile(state: State<'_, AppState>) -> CallsignProfile {
+    state.callsign_profile.lock().unwrap().clone()
+}
+
+/// 设置呼号（航空公司和航班号均为空时恢复使用模拟器呼号），返回解析后的呼号
+#[tauri::command]
+fn set_callsign_profile(profile: CallsignProfile, state: State<'_, AppState>) -> Result<Callsign, String> {
+    profile.validate().map_err(|e| format!("❌ 呼号设置无效\n\n{}", e))?;
+    
+    if let Err(e) = profile.save() {
+        eprintln!("Failed to save callsign profile: {}", e);
     }
+    
+    let data = state.simulator.lock().unwrap()
+        .as_ref()
+        .and_then(|source| source.get_flight_data().ok())
+        .unwrap_or_default();
+    let resolved = callsign::resolve(&profile, &data);
+    
+    *state.callsign_profile.lock().unwrap() = profile;
+    Ok(resolved)
+}
+
+#[tauri::command]
+fn get_aircraft_profile(state: State<'_, AppState>) -> AircraftProfile {
+    state.aircraft_profile.lock().unwrap().clone()
+}
+
+/// 设置机型（机型代码为空且未指定类别时恢复使用模拟器机型），返回解析后的机型
+#[tauri::command]
+fn set_aircraft_profile(profile: AircraftProfile, state: State<'_, AppState>) -> Result<Aircraft, String> {
+    profile.validate().map_err(|e| format!("❌ 机型设置无效\n\n{}", e))?;
+    
+    if let Err(e) = profile.save() {
+        eprintln!("Failed to save aircraft profile: {}", e);
+    }
+    
+    let data = state.simulator.lock().unwrap()
+        .as_ref()
+        .and_then(|source| source.get_flight_data().ok())
+        .unwrap_or_default();
+    let resolved = aircraft::resolve(&profile, &data);
+    
+    state.phase_detector.lock().unwrap().set_category(resolved.category);
+    *state.aircraft_profile.lock().unwrap() = profile;
+    Ok(resolved)
 }
 
 #[tauri::command]
 async fn disconnect_simulator(state: State<'_, AppState>) -> Result<String, String> {
-    let mut current_sim = state.current_sim.lock().unwrap();
+    let mut sim = state.simulator.lock().unwrap();
     
-    match current_sim.as_str() {
-        "xplane" => {
-            let mut sim = state.simulator.lock().unwrap();
-            *sim = None;
-        }
-        "msfs" => {
-            let mut msfs = state.msfs.lock().unwrap();
-            if let Some(connection) = msfs.as_mut() {
-                connection.disconnect();
-            }
-            *msfs = None;
-        }
-        _ => {}
+    if let Some(mut source) = sim.take() {
+        source.disconnect();
     }
+    state.traffic.lock().unwrap().clear();
     
-    *current_sim = String::new();
     Ok("Disconnected".to_string())
 }
 
 #[tauri::command]
 async fn get_flight_data(state: State<'_, AppState>) -> Result<FlightDataResponse, String> {
-    let current_sim = state.current_sim.lock().unwrap();
-    
     // 获取飞行数据
-    let (callsign, altitude, speed, heading, vertical_speed, latitude, longitude, on_ground) = match current_sim.as_str() {
-        "xplane" => {
-            let sim = state.simulator.lock().unwrap();
-            match &*sim {
-                Some(connection) => {
-                    let data = connection.get_flight_data().map_err(|e| e.to_string())?;
-                    (
-                        data.callsign,
-                        data.altitude,
-                        data.speed,
-                        data.heading,
-                        data.vertical_speed,
-                        data.latitude,
-                        data.longitude,
-                        data.altitude < 10.0 && data.vertical_speed.abs() < 100.0,
-                    )
-                }
-                None => return Err("Not connected to X-Plane".to_string()),
-            }
-        }
-        "msfs" => {
-            let msfs = state.msfs.lock().unwrap();
-            match &*msfs {
-                Some(connection) => {
-                    let data = connection.get_flight_data().map_err(|e| e.to_string())?;
-                    (
-                        data.callsign,
-                        data.altitude,
-                        data.speed,
-                        data.heading,
-                        data.vertical_speed,
-                        data.latitude,
-                        data.longitude,
-                        data.on_ground,
-                    )
-                }
-                None => return Err("Not connected to MSFS".to_string()),
-            }
+    let data = {
+        let sim = state.simulator.lock().unwrap();
+        match &*sim {
+            Some(source) => source.get_flight_data().map_err(|e| e.to_string())?,
+            None => return Err("No simulator connected".to_string()),
         }
-        _ => return Err("No simulator connected".to_string())
     };
     
-    // 更新飞行阶段
+    let aircraft = aircraft::resolve(&state.aircraft_profile.lock().unwrap(), &data);
+    
+    // 更新飞行阶段（阈值按机型类别，高度相对附近机场标高）
+    let field_elevation = nearby_field_elevation(&state, &data);
     let mut detector = state.phase_detector.lock().unwrap();
-    let phase = detector.update(&modules::flight_phase::FlightData {
-        altitude,
-        speed,
-        heading,
-        vertical_speed,
-        on_ground,
-    });
+    detector.set_category(aircraft.category);
+    detector.set_field_elevation(field_elevation);
+    let phase = detector.update(&modules::flight_phase::FlightData::from(&data));
+    
+    let callsign = callsign::resolve(&state.callsign_profile.lock().unwrap(), &data)
+        .with_wake(aircraft.wake);
     
     Ok(FlightDataResponse {
-        callsign,
-        altitude,
-        speed,
-        heading,
-        vertical_speed,
-        latitude,
-        longitude,
+        callsign: callsign.code,
+        callsign_en: callsign.spoken_en,
+        callsign_zh: callsign.spoken_zh,
+        tail_number: data.callsign,
+        aircraft_type: aircraft.aircraft_type,
+        aircraft_category: aircraft.category.as_str().to_string(),
+        altitude: data.altitude,
+        speed: data.speed,
+        heading: data.heading,
+        vertical_speed: data.vertical_speed,
+        latitude: data.latitude,
+        longitude: data.longitude,
+        on_ground: data.on_ground,
+        altitude_agl: data.altitude_agl,
+        ground_speed: data.ground_speed,
+        gear_ratio: data.gear_ratio,
+        flap_ratio: data.flap_ratio,
+        com1_active: data.com1_active,
+        com1_standby: data.com1_standby,
+        com2_active: data.com2_active,
+        com2_standby: data.com2_standby,
+        nav1_active: data.nav1_active,
+        nav1_standby: data.nav1_standby,
+        nav2_active: data.nav2_active,
+        nav2_standby: data.nav2_standby,
+        transponder_code: data.transponder_code,
+        transponder_mode: data.transponder_mode,
+        parking_brake: data.parking_brake,
         phase: phase.as_str().to_string(),
         phase_display: phase.display_name().to_string(),
     })
 }
 
+#[tauri::command]
+fn start_flight_recording(path: Option<String>, state: State<'_, AppState>) -> Result<String, String> {
+    let path = path.map(std::path::PathBuf::from)
+        .unwrap_or_else(FlightRecorder::default_path);
+    
+    let recorder = FlightRecorder::create(&path).map_err(|e| {
+        format!("❌ 无法创建飞行记录文件\n\n文件：{:?}\n\n详细错误：{}", path, e)
+    })?;
+    
+    *state.recorder.lock().unwrap() = Some(recorder);
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn stop_flight_recording(state: State<'_, AppState>) -> Result<String, String> {
+    match state.recorder.lock().unwrap().take() {
+        Some(recorder) => Ok(format!(
+            "Flight log saved to {:?} ({} samples)",
+            recorder.path(),
+            recorder.sample_count()
+        )),
+        None => Err("Flight recording is not active".to_string()),
+    }
+}
+
 #[tauri::command]
 async fn start_recording(state: State<'_, AppState>) -> Result<(), String> {
     let mut whisper = state.whisper.lock().unwrap();
@@ -226,49 +395,60 @@ async fn get_atc_response(
     let llm = state.llm.lock().unwrap();
     
     // 获取当前飞行数据
-    let current_sim = state.current_sim.lock().unwrap();
-    let flight_data = match current_sim.as_str() {
-        "xplane" => {
-            let sim = state.simulator.lock().unwrap();
-            sim.as_ref().and_then(|c| c.get_flight_data().ok())
-        }
-        "msfs" => {
-            let msfs = state.msfs.lock().unwrap();
-            msfs.as_ref().and_then(|c| c.get_flight_data().ok()).map(|data| {
-                modules::simulator::FlightData {
-                    callsign: data.callsign,
-                    altitude: data.altitude,
-                    speed: data.speed,
-                    heading: data.heading,
-                    vertical_speed: data.vertical_speed,
-                    latitude: data.latitude,
-                    longitude: data.longitude,
-                }
-            })
-        }
-        _ => None,
+    let mut flight_data = {
+        let sim = state.simulator.lock().unwrap();
+        sim.as_ref().and_then(|source| source.get_flight_data().ok())
     };
     
-    // 自动检测机场（如果��飞行数据）
+    // 交通情况（活动通报、进近排序、跑道占用）
+    let phase = state.phase_detector.lock().unwrap().get_current_phase();
+    let traffic_context = flight_data.as_ref()
+        .map(|data| state.traffic.lock().unwrap().picture(data, phase).get_atc_context(&language))
+        .unwrap_or_default();
+    
+    // 使用解析后的无线电呼号，保证每次通话呼号一致
+    if let Some(ref mut data) = flight_data {
+        let profile = state.callsign_profile.lock().unwrap();
+        let wake = aircraft::resolve(&state.aircraft_profile.lock().unwrap(), data).wake;
+        data.callsign = callsign::resolve(&profile, data).with_wake(wake).spoken(&language).to_string();
+    }
+    
+    // 自动检测机场（如果有飞行数据）
     if let Some(ref data) = flight_data {
-        let mut atc_db = state.atc_database.lock().unwrap();
-        atc_db.detect_nearest_airport(data.latitude, data.longitude);
+        locate_airport(&state, data.latitude, data.longitude);
     }
     
+    // 附近导航设施（需要 Little Navmap）
+    let navaid_context = flight_data.as_ref()
+        .and_then(|data| navaid_context(&state, data, &language))
+        .unwrap_or_default();
+    
     // 获取机场上下文
+    let options = flight_data.as_ref().map(|data| taxi_route_options(&state, data));
     let atc_db = state.atc_database.lock().unwrap();
     let airport_context = atc_db.get_atc_context(&language);
     
+    // 在地面时附带到各跑道等待点的滑行路线
+    let taxi_context = flight_data.as_ref()
+        .zip(options.as_ref())
+        .filter(|(data, _)| data.on_ground)
+        .and_then(|(data, options)| atc_db.get_current_airport()?
+            .format_taxi_options(data.latitude, data.longitude, options, &language))
+        .unwrap_or_default();
+    
     // 获取飞行阶段上下文
     let detector = state.phase_detector.lock().unwrap();
     let phase_context = detector.get_atc_context(&language);
     
     // 构建完整的上下文
     let full_context = format!(
-        "{}\n\n当前飞行阶段：{}\n\n{}\n\n飞行员消息：{}",
+        "{}\n\n{}\n\n{}\n\n当前飞行阶段：{}\n\n{}\n\n{}\n\n飞行员消息：{}",
         airport_context,
+        taxi_context,
+        navaid_context,
         detector.get_current_phase().display_name(),
         phase_context,
+        traffic_context,
         message
     );
     
@@ -285,6 +465,118 @@ async fn get_atc_response(
     Ok(response)
 }
 
+/// 确定附近机场：先用 Little Navmap 补全数据库中缺失的机场数据，再查询最近机场
+fn locate_airport(state: &AppState, lat: f64, lon: f64) -> Option<String> {
+    let navmap = state.little_navmap.lock().unwrap();
+    let mut atc_db = state.atc_database.lock().unwrap();
+    
+    if let Some(db) = navmap.as_ref() {
+        match db.find_nearest_airport(lat, lon, NEARBY_AIRPORT_RADIUS_KM) {
+            Ok(Some(icao)) => {
+                // 同一机场只需合并一次
+                let mut merged = state.navmap_airport.lock().unwrap();
+                if merged.as_deref() != Some(icao.as_str()) {
+                    match db.get_airport_full(&icao) {
+                        Ok(airport) => {
+                            atc_db.import_airports(vec![airport.to_atc_airport()]);
+                        }
+                        Err(e) => eprintln!("读取 Little Navmap 机场 {} 失败: {}", icao, e),
+                    }
+                    *merged = Some(icao);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Little Navmap 附近机场查询失败: {}", e),
+        }
+    }
+    
+    let icao = atc_db.detect_nearest_airport(lat, lon);
+    
+    // 附近机场变化时下次轮询重新确定标高
+    let mut field = state.nearby_field.lock().unwrap();
+    if field.as_ref().is_some_and(|field| field.icao != icao) {
+        *field = None;
+    }
+    
+    icao
+}
+
+/// 当前机场的 ILS、离场/进场程序和附近导航台、航路点（用于 LLM 上下文）
+fn navaid_context(state: &AppState, data: &modules::simulator::FlightData, language: &str) -> Option<String> {
+    let navmap = state.little_navmap.lock().unwrap();
+    let db = navmap.as_ref()?;
+    
+    let icao = state.atc_database.lock().unwrap()
+        .get_current_airport()
+        .map(|airport| airport.icao.clone());
+    let (ils, sids, stars) = match icao {
+        Some(icao) => (
+            db.get_airport_ils(&icao).unwrap_or_default(),
+            db.get_procedures(&icao, ProcedureKind::Sid).unwrap_or_default(),
+            db.get_procedures(&icao, ProcedureKind::Star).unwrap_or_default(),
+        ),
+        None => Default::default(),
+    };
+    let fixes = db.find_nearby_fixes(data.latitude, data.longitude, NEARBY_AIRPORT_RADIUS_KM, NEARBY_FIX_LIMIT)
+        .unwrap_or_else(|e| {
+            eprintln!("Little Navmap 导航台查询失败: {}", e);
+            Vec::new()
+        });
+    
+    Some(format!(
+        "{}\n\n{}",
+        LittleNavmapDB::format_procedures_for_llm(&sids, &stars, language),
+        LittleNavmapDB::format_navaids_for_llm(&ils, &fixes, language),
+    ))
+}
+
+/// 附近机场标高（英尺），移动不远且机场未变化时使用缓存
+fn nearby_field_elevation(state: &AppState, data: &modules::simulator::FlightData) -> Option<f64> {
+    if let Some(field) = state.nearby_field.lock().unwrap().as_ref() {
+        let moved = ATCDatabase::calculate_distance(field.latitude, field.longitude, data.latitude, data.longitude);
+        if moved < NEARBY_FIELD_REFRESH_KM {
+            return field.elevation;
+        }
+    }
+    
+    let icao = locate_airport(state, data.latitude, data.longitude);
+    let elevation = icao.as_deref().and_then(|icao| field_elevation(state, icao));
+    *state.nearby_field.lock().unwrap() = Some(NearbyField {
+        latitude: data.latitude,
+        longitude: data.longitude,
+        icao,
+        elevation,
+    });
+    
+    elevation
+}
+
+/// 机场标高（英尺）。优先使用 Little Navmap 数据库，其次使用机场数据库
+fn field_elevation(state: &AppState, icao: &str) -> Option<f64> {
+    if let Some(db) = state.little_navmap.lock().unwrap().as_ref() {
+        if let Ok(airport) = db.get_airport(icao) {
+            return Some(airport.elevation as f64);
+        }
+    }
+    
+    state.atc_database.lock().unwrap()
+        .get_current_airport()
+        .map(|airport| airport.elevation as f64)
+}
+
+/// 设置计划巡航高度（英尺），用于判断巡航阶段；None 表示清除
+#[tauri::command]
+fn set_cruise_altitude(altitude: Option<f64>, state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(altitude) = altitude {
+        if !(1000.0..=60000.0).contains(&altitude) {
+            return Err(format!("❌ 巡航高度无效: {} 英尺（应在 1000-60000 之间）", altitude));
+        }
+    }
+    
+    state.phase_detector.lock().unwrap().set_cruise_altitude(altitude);
+    Ok(())
+}
+
 #[tauri::command]
 async fn get_current_phase(state: State<'_, AppState>) -> Result<PhaseInfo, String> {
     let detector = state.phase_detector.lock().unwrap();
@@ -295,9 +587,150 @@ async fn get_current_phase(state: State<'_, AppState>) -> Result<PhaseInfo, Stri
         phase: phase.as_str().to_string(),
         display_name: phase.display_name().to_string(),
         duration_seconds: duration.as_secs(),
+        cruise_altitude: detector.get_cruise_altitude(),
     })
 }
 
+/// 本次连接的阶段历史（用于飞行总结）
+#[tauri::command]
+fn get_phase_history(state: State<'_, AppState>) -> Vec<PhaseRecord> {
+    state.phase_detector.lock().unwrap().get_history()
+}
+
+// ========== 机场数据命令 ==========
+
+/// 从 X-Plane 安装目录（或单个 apt.dat 文件）导入机场数据
+#[tauri::command]
+async fn load_xplane_airports(path: String, state: State<'_, AppState>) -> Result<String, String> {
+    // 全球机场文件较大，解析时不持有数据库锁
+    let airports = apt_dat::load_all(std::path::Path::new(path.trim()))
+        .map_err(|e| format!("❌ 导入 X-Plane 机场数据失败\n\n{}", e))?;
+    
+    let mut atc_db = state.atc_database.lock().unwrap();
+    let count = atc_db.import_airports(airports);
+    *state.nearby_field.lock().unwrap() = None;
+    
+    Ok(format!("✓ 已导入 {} 个机场（共 {} 个）", count, atc_db.airport_count()))
+}
+
+/// 从 OurAirports CSV 目录导入机场数据（未指定目录时使用默认数据目录）
+#[tauri::command]
+async fn load_ourairports(path: Option<String>, state: State<'_, AppState>) -> Result<String, String> {
+    let dir = path
+        .map(|p| std::path::PathBuf::from(p.trim()))
+        .unwrap_or_else(our_airports::default_dir);
+    let airports = our_airports::load_dir(&dir)
+        .map_err(|e| format!("❌ 导入 OurAirports 数据失败\n\n{}", e))?;
+    
+    let mut atc_db = state.atc_database.lock().unwrap();
+    let count = atc_db.import_airports(airports);
+    atc_db.record_source(&dir.join("airports.csv"));
+    *state.nearby_field.lock().unwrap() = None;
+    
+    Ok(format!("✓ 已导入 {} 个机场（共 {} 个）", count, atc_db.airport_count()))
+}
+
+// ========== 滑行路线命令 ==========
+
+/// 滑行路线限制：本机翼展等级和关闭的滑行道
+fn taxi_route_options(state: &AppState, data: &modules::simulator::FlightData) -> RouteOptions {
+    RouteOptions {
+        size: Some(aircraft::resolve(&state.aircraft_profile.lock().unwrap(), data).size_code()),
+        closed: state.closed_taxiways.lock().unwrap().clone(),
+    }
+}
+
+/// 设置关闭的滑行道（规划路线时避开）
+#[tauri::command]
+fn set_closed_taxiways(taxiways: Vec<String>, state: State<'_, AppState>) {
+    *state.closed_taxiways.lock().unwrap() = taxiways
+        .into_iter()
+        .map(|name| name.trim().to_uppercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+}
+
+/// 规划当前机场的滑行路线：未指定停机位时从飞机当前位置出发
+#[tauri::command]
+fn plan_taxi_route(
+    target: TaxiTarget,
+    from_gate: Option<String>,
+    language: String,
+    state: State<'_, AppState>,
+) -> Result<TaxiRouteResponse, String> {
+    let data = state.simulator.lock().unwrap()
+        .as_ref()
+        .and_then(|source| source.get_flight_data().ok());
+    
+    let start = match (from_gate, &data) {
+        (Some(gate), _) => TaxiStart::Gate(gate),
+        (None, Some(data)) => {
+            locate_airport(&state, data.latitude, data.longitude);
+            TaxiStart::Position { latitude: data.latitude, longitude: data.longitude }
+        }
+        (None, None) => return Err("❌ 未连接模拟器\n\n请连接模拟器，或指定出发停机位".to_string()),
+    };
+    let options = match &data {
+        Some(data) => taxi_route_options(&state, data),
+        None => RouteOptions { size: None, closed: state.closed_taxiways.lock().unwrap().clone() },
+    };
+    
+    let atc_db = state.atc_database.lock().unwrap();
+    let airport = atc_db.get_current_airport().ok_or("❌ 未确定当前机场")?;
+    let route = airport.plan_taxi_route(&start, &target, &options).ok_or_else(|| {
+        format!("❌ {} 没有可用的滑行路线\n\n可能的原因：\n• 机场缺少滑行网络数据（导入 X-Plane apt.dat 或安装 Little Navmap）\n• 滑行道关闭或翼展超出限制\n• 停机位或跑道名称不存在", airport.icao)
+    })?;
+    let instruction = airport.format_taxi_instruction(&route, &language);
+    
+    Ok(TaxiRouteResponse { route, instruction })
+}
+
+// ========== Little Navmap 命令 ==========
+
+/// 当前模拟器对应的数据库类型
+fn simulator_database_type(state: &AppState) -> Option<DatabaseType> {
+    state.simulator.lock().unwrap()
+        .as_ref()
+        .and_then(|source| DatabaseType::for_simulator(source.sim_type()))
+}
+
+/// 按配置重新打开 Little Navmap 数据库
+fn switch_navmap(state: &AppState, config: &NavmapConfig) -> Result<DatabaseInfo, String> {
+    let db = LittleNavmapDB::select(config, simulator_database_type(state))
+        .map_err(|e| format!("❌ 无法打开 Little Navmap 数据库\n\n{}", e))?;
+    let info = db.info();
+    
+    *state.little_navmap.lock().unwrap() = Some(db);
+    *state.nearby_field.lock().unwrap() = None;
+    
+    Ok(info)
+}
+
+#[tauri::command]
+fn get_navmap_config() -> NavmapConfig {
+    NavmapConfig::load()
+}
+
+/// 已安装的 Little Navmap 数据库及其 AIRAC 周期
+#[tauri::command]
+fn list_navmap_databases(state: State<'_, AppState>) -> Vec<DatabaseInfo> {
+    let navmap = state.little_navmap.lock().unwrap();
+    LittleNavmapDB::list_databases(&NavmapConfig::load(), navmap.as_ref().map(|db| db.path()))
+}
+
+/// 切换 Little Navmap 数据库：指定文件或类型，都为空时自动选择
+#[tauri::command]
+fn set_navmap_database(config: NavmapConfig, state: State<'_, AppState>) -> Result<DatabaseInfo, String> {
+    config.validate().map_err(|e| format!("❌ Little Navmap 设置无效\n\n{}", e))?;
+    
+    let info = switch_navmap(&state, &config)?;
+    if let Err(e) = config.save() {
+        eprintln!("Failed to save Little Navmap config: {}", e);
+    }
+    
+    Ok(info)
+}
+
 // ========== 模型管理命令 ==========
 
 #[tauri::command]
@@ -388,17 +821,45 @@ struct PhaseInfo {
     phase: String,
     display_name: String,
     duration_seconds: u64,
+    cruise_altitude: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+struct TaxiRouteResponse {
+    route: TaxiRoute,
+    instruction: String,
 }
 
 #[derive(serde::Serialize)]
 struct FlightDataResponse {
     callsign: String,
+    callsign_en: String,    // 英文无线电呼号
+    callsign_zh: String,    // 中文无线电呼号
+    tail_number: String,    // 模拟器报告的机尾号/ATC ID
+    aircraft_type: String,  // ICAO 机型代码
+    aircraft_category: String,
     altitude: f64,
     speed: f64,
     heading: f64,
     vertical_speed: f64,
     latitude: f64,
     longitude: f64,
+    on_ground: bool,
+    altitude_agl: f64,
+    ground_speed: f64,
+    gear_ratio: f64,
+    flap_ratio: f64,
+    com1_active: f64,
+    com1_standby: f64,
+    com2_active: f64,
+    com2_standby: f64,
+    nav1_active: f64,
+    nav1_standby: f64,
+    nav2_active: f64,
+    nav2_standby: f64,
+    transponder_code: u16,
+    transponder_mode: u8,
+    parking_brake: f64,
     phase: String,
     phase_display: String,
 }
@@ -407,13 +868,28 @@ struct FlightDataResponse {
 pub fn run() {
     let llm_client = LLMClient::new();
     let tts_engine = TTSEngine::new();
-    let atc_db = ATCDatabase::new();
+    let mut atc_db = ATCDatabase::new();
     let model_manager = ModelManager::new();
     
+    // 全球机场数据（OurAirports），只补全内置机场缺失的部分；已导入且未更新时直接使用缓存
+    let our_airports_dir = our_airports::default_dir();
+    let our_airports_csv = our_airports_dir.join("airports.csv");
+    if our_airports_csv.exists() && !atc_db.is_source_current(&our_airports_csv) {
+        match our_airports::load_dir(&our_airports_dir) {
+            Ok(airports) => {
+                atc_db.import_airports(airports);
+                atc_db.record_source(&our_airports_csv);
+            }
+            Err(e) => println!("⚠ OurAirports 数据加载失败: {}", e),
+        }
+    }
+    println!("✓ 机场数据库：{} 个机场", atc_db.airport_count());
+    
     // 尝试加载 Little Navmap 数据库
     let little_navmap = match LittleNavmapDB::new() {
         Ok(db) => {
-            println!("✓ Little Navmap 数据库加载成功");
+            let info = db.info();
+            println!("✓ Little Navmap 数据库加载成功: {:?}，AIRAC {}", info.db_type, info.airac_cycle.as_deref().unwrap_or("未知"));
             Some(db)
         }
         Err(e) => {
@@ -427,24 +903,56 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(AppState {
             simulator: Mutex::new(None),
-            msfs: Mutex::new(None),
+            recorder: Arc::new(Mutex::new(None)),
+            traffic: Arc::new(Mutex::new(TrafficTable::new())),
+            callsign_profile: Mutex::new(CallsignProfile::load()),
+            aircraft_profile: Mutex::new(AircraftProfile::load()),
             whisper: Mutex::new(None),
             llm: Mutex::new(llm_client),
             tts: Mutex::new(tts_engine),
-            current_sim: Mutex::new(String::new()),
             phase_detector: Mutex::new(FlightPhaseDetector::new()),
             atc_database: Mutex::new(atc_db),
             little_navmap: Mutex::new(little_navmap),
+            navmap_airport: Mutex::new(None),
+            nearby_field: Mutex::new(None),
+            closed_taxiways: Mutex::new(Vec::new()),
             model_manager: Mutex::new(model_manager),
         })
+        .setup(|app| {
+            // 阶段切换时通知前端
+            let handle = app.handle().clone();
+            app.state::<AppState>().phase_detector.lock().unwrap().set_listener(move |change| {
+                let _ = handle.emit("phase-changed", change);
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             connect_simulator,
             disconnect_simulator,
+            get_connection_status,
+            get_xplane_config,
+            get_msfs_config,
+            get_callsign_profile,
+            get_aircraft_profile,
+            set_aircraft_profile,
+            get_traffic,
+            set_callsign_profile,
             get_flight_data,
+            start_flight_recording,
+            stop_flight_recording,
             start_recording,
             stop_recording,
             get_atc_response,
             get_current_phase,
+            set_cruise_altitude,
+            get_phase_history,
+            load_xplane_airports,
+            load_ourairports,
+            get_navmap_config,
+            list_navmap_databases,
+            set_navmap_database,
+            set_closed_taxiways,
+            plan_taxi_route,
             get_available_models,
             get_downloaded_models,
             download_model,
@@ -0,0 +1,278 @@
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use super::config_store;
+use super::simulator::FlightData;
+
+/// 航空器类别（决定飞行阶段判断的阈值）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftCategory {
+    LightPiston,    // 轻型活塞（如 C172）
+    Turboprop,      // 涡桨（如 ATR 72、King Air）
+    Jet,            // 喷气客机（如 A320、B737）
+    Heavy,          // 重型喷气（如 B777、A350）
+    Rotorcraft,     // 直升机
+}
+
+impl AircraftCategory {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AircraftCategory::LightPiston => "light_piston",
+            AircraftCategory::Turboprop => "turboprop",
+            AircraftCategory::Jet => "jet",
+            AircraftCategory::Heavy => "heavy",
+            AircraftCategory::Rotorcraft => "rotorcraft",
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            AircraftCategory::LightPiston => "轻型活塞",
+            AircraftCategory::Turboprop => "涡桨",
+            AircraftCategory::Jet => "喷气",
+            AircraftCategory::Heavy => "重型喷气",
+            AircraftCategory::Rotorcraft => "直升机",
+        }
+    }
+
+    /// 未知机型时该类别的尾流等级
+    pub fn default_wake(&self) -> WakeCategory {
+        match self {
+            AircraftCategory::LightPiston | AircraftCategory::Rotorcraft => WakeCategory::Light,
+            AircraftCategory::Turboprop | AircraftCategory::Jet => WakeCategory::Medium,
+            AircraftCategory::Heavy => WakeCategory::Heavy,
+        }
+    }
+}
+
+/// ICAO 尾流等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeCategory {
+    Light,
+    Medium,
+    Heavy,
+    Super,      // A380
+}
+
+impl WakeCategory {
+    /// 无线电通话中跟在呼号后的尾流等级（只有重型和超级需要报出）
+    pub fn callsign_suffix(&self, language: &str) -> Option<&'static str> {
+        match (self, language) {
+            (WakeCategory::Heavy, "zh") => Some("重型"),
+            (WakeCategory::Heavy, _) => Some("Heavy"),
+            (WakeCategory::Super, "zh") => Some("超级"),
+            (WakeCategory::Super, _) => Some("Super"),
+            _ => None,
+        }
+    }
+}
+
+/// 常用机型（ICAO 机型代码、类别、尾流等级）
+const AIRCRAFT_TYPES: &[(&str, AircraftCategory, WakeCategory)] = &[
+    // 轻型活塞
+    ("C152", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("C172", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("C182", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("PA28", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("SR22", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("DA40", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("DA62", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("BE58", AircraftCategory::LightPiston, WakeCategory::Light),
+    ("DR40", AircraftCategory::LightPiston, WakeCategory::Light),
+    // 涡桨
+    ("C208", AircraftCategory::Turboprop, WakeCategory::Light),
+    ("PC12", AircraftCategory::Turboprop, WakeCategory::Light),
+    ("TBM9", AircraftCategory::Turboprop, WakeCategory::Light),
+    ("BE20", AircraftCategory::Turboprop, WakeCategory::Light),
+    ("DHC6", AircraftCategory::Turboprop, WakeCategory::Light),
+    ("AT72", AircraftCategory::Turboprop, WakeCategory::Medium),
+    ("AT76", AircraftCategory::Turboprop, WakeCategory::Medium),
+    ("DH8D", AircraftCategory::Turboprop, WakeCategory::Medium),
+    ("MA60", AircraftCategory::Turboprop, WakeCategory::Medium),
+    // 喷气
+    ("A319", AircraftCategory::Jet, WakeCategory::Medium),
+    ("A320", AircraftCategory::Jet, WakeCategory::Medium),
+    ("A321", AircraftCategory::Jet, WakeCategory::Medium),
+    ("A20N", AircraftCategory::Jet, WakeCategory::Medium),
+    ("A21N", AircraftCategory::Jet, WakeCategory::Medium),
+    ("B737", AircraftCategory::Jet, WakeCategory::Medium),
+    ("B738", AircraftCategory::Jet, WakeCategory::Medium),
+    ("B739", AircraftCategory::Jet, WakeCategory::Medium),
+    ("B38M", AircraftCategory::Jet, WakeCategory::Medium),
+    ("E190", AircraftCategory::Jet, WakeCategory::Medium),
+    ("E195", AircraftCategory::Jet, WakeCategory::Medium),
+    ("CRJ9", AircraftCategory::Jet, WakeCategory::Medium),
+    ("AJ27", AircraftCategory::Jet, WakeCategory::Medium),    // ARJ21
+    ("C919", AircraftCategory::Jet, WakeCategory::Medium),
+    ("C25C", AircraftCategory::Jet, WakeCategory::Light),     // Citation CJ4
+    // 重型
+    ("A332", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("A333", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("A339", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("A359", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("A35K", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B744", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B748", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B772", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B77W", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B788", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B789", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("B78X", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("MD11", AircraftCategory::Heavy, WakeCategory::Heavy),
+    ("A388", AircraftCategory::Heavy, WakeCategory::Super),
+    // 直升机
+    ("R22", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("R44", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("B06", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("B407", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("EC35", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("EC45", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("AS50", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("S76", AircraftCategory::Rotorcraft, WakeCategory::Light),
+    ("H160", AircraftCategory::Rotorcraft, WakeCategory::Light),
+];
+
+/// 按 ICAO 机型代码查找类别和尾流等级
+pub fn lookup(icao_type: &str) -> Option<(AircraftCategory, WakeCategory)> {
+    let icao_type = icao_type.trim();
+    AIRCRAFT_TYPES.iter()
+        .find(|(code, _, _)| code.eq_ignore_ascii_case(icao_type))
+        .map(|&(_, category, wake)| (category, wake))
+}
+
+/// 用户设置的机型（优先于模拟器）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AircraftProfile {
+    pub aircraft_type: String,                  // ICAO 机型代码，如 "B738"
+    pub category: Option<AircraftCategory>,     // 手动指定类别（覆盖机型表）
+}
+
+impl AircraftProfile {
+    /// 读取上次保存的设置（不存在或无效时为空）
+    pub fn load() -> Self {
+        config_store::load_json::<AircraftProfile>("aircraft.json")
+            .filter(|profile| profile.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    /// 保存设置
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        config_store::save_json("aircraft.json", self)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let code = self.aircraft_type.trim();
+        let valid = code.is_empty()
+            || ((2..=4).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        if !valid {
+            return Err(format!("机型代码格式无效：{}（应为 2~4 位 ICAO 机型代码，如 B738、C172）", self.aircraft_type));
+        }
+        Ok(())
+    }
+}
+
+/// 机型信息来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftSource {
+    User,           // 用户设置
+    Simulator,      // 模拟器报告的 ICAO 机型代码
+    Default,        // 未知机型，按喷气客机处理
+}
+
+/// 解析后的机型
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Aircraft {
+    pub aircraft_type: String,
+    pub category: AircraftCategory,
+    pub wake: WakeCategory,
+    pub source: AircraftSource,
+}
+
+impl Aircraft {
+    /// 翼展等级（ICAO 机场基准代码字母），用于滑行道宽度限制
+    pub fn size_code(&self) -> char {
+        match (self.category, self.wake) {
+            (_, WakeCategory::Super) => 'F',
+            (AircraftCategory::Heavy, _) => 'E',
+            (AircraftCategory::Jet | AircraftCategory::Turboprop, _) => 'C',
+            (AircraftCategory::LightPiston | AircraftCategory::Rotorcraft, _) => 'A',
+        }
+    }
+}
+
+/// 解析机型：用户设置 > 模拟器机型代码 > 默认（喷气客机）
+pub fn resolve(profile: &AircraftProfile, data: &FlightData) -> Aircraft {
+    let user_type = profile.aircraft_type.trim().to_uppercase();
+    let sim_type = data.aircraft_type.trim().to_uppercase();
+
+    let (aircraft_type, source) = if !user_type.is_empty() {
+        (user_type, AircraftSource::User)
+    } else if !sim_type.is_empty() {
+        (sim_type, AircraftSource::Simulator)
+    } else {
+        (String::new(), AircraftSource::Default)
+    };
+
+    let known = lookup(&aircraft_type);
+
+    // 手动指定类别时，只有机型表中类别一致才沿用表中的尾流等级
+    let (category, wake, source) = match (profile.category, known) {
+        (Some(category), Some((known_category, wake))) if known_category == category => (category, wake, source),
+        (Some(category), _) => (category, category.default_wake(), AircraftSource::User),
+        (None, Some((category, wake))) => (category, wake, source),
+        (None, None) => (AircraftCategory::Jet, WakeCategory::Medium, source),
+    };
+
+    Aircraft { aircraft_type, category, wake, source }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sim(aircraft_type: &str) -> FlightData {
+        FlightData {
+            aircraft_type: aircraft_type.to_string(),
+            ..FlightData::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup_from_sim_type() {
+        let aircraft = resolve(&AircraftProfile::default(), &sim("c172"));
+        assert_eq!(aircraft.aircraft_type, "C172");
+        assert_eq!(aircraft.category, AircraftCategory::LightPiston);
+        assert_eq!(aircraft.source, AircraftSource::Simulator);
+
+        let aircraft = resolve(&AircraftProfile::default(), &sim("A388"));
+        assert_eq!((aircraft.category, aircraft.wake), (AircraftCategory::Heavy, WakeCategory::Super));
+        assert_eq!(aircraft.wake.callsign_suffix("en"), Some("Super"));
+        assert_eq!(aircraft.size_code(), 'F');
+    }
+
+    #[test]
+    fn test_user_selection_overrides_sim() {
+        let profile = AircraftProfile { aircraft_type: "B77W".to_string(), category: None };
+        let aircraft = resolve(&profile, &sim("C172"));
+        assert_eq!((aircraft.category, aircraft.wake), (AircraftCategory::Heavy, WakeCategory::Heavy));
+        assert_eq!(aircraft.source, AircraftSource::User);
+        assert_eq!(aircraft.size_code(), 'E');
+
+        // 未知机型手动指定为直升机
+        let profile = AircraftProfile { aircraft_type: String::new(), category: Some(AircraftCategory::Rotorcraft) };
+        let aircraft = resolve(&profile, &sim("XXXX"));
+        assert_eq!((aircraft.category, aircraft.wake), (AircraftCategory::Rotorcraft, WakeCategory::Light));
+    }
+
+    #[test]
+    fn test_unknown_type_defaults_to_jet() {
+        let aircraft = resolve(&AircraftProfile::default(), &sim(""));
+        assert_eq!(aircraft.category, AircraftCategory::Jet);
+        assert_eq!(aircraft.wake.callsign_suffix("zh"), None);
+        assert!(AircraftProfile { aircraft_type: "BOEING 737".to_string(), category: None }.validate().is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use rusqlite::{params, Connection, OptionalExtension};
+use super::atc_database::{ATCDatabase, Airport};
+
+/// 缓存结构版本，变化时重建缓存
+const SCHEMA_VERSION: i32 = 1;
+
+/// 网格大小（度）
+const CELL_DEG: f64 = 1.0;
+
+/// 每度纬度对应的公里数
+const KM_PER_DEG: f64 = 111.2;
+
+/// 机场坐标：（ICAO，纬度，经度）
+pub type AirportLocation = (String, f64, f64);
+
+/// 导入机场的本地缓存（SQLite）
+///
+/// 完整的机场数据以 JSON 存储，按需读取；启动时只读取坐标建立空间索引
+#[derive(Debug)]
+pub struct AirportStore {
+    conn: Connection,
+}
+
+impl AirportStore {
+    /// 缓存文件路径
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("VirtualATC")
+            .join("airports.sqlite")
+    }
+
+    /// 打开默认缓存（不存在时创建）
+    pub fn open_default() -> Result<Self, Box<dyn Error>> {
+        let path = Self::default_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Self::open(&path)
+    }
+
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// 内存缓存（测试用）
+    pub fn open_in_memory() -> Result<Self, Box<dyn Error>> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS airport;
+                 DROP TABLE IF EXISTS source;",
+            )?;
+        }
+
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS airport (
+                 icao TEXT PRIMARY KEY,
+                 latitude REAL NOT NULL,
+                 longitude REAL NOT NULL,
+                 data TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS source (
+                 path TEXT PRIMARY KEY,
+                 modified INTEGER NOT NULL
+             );
+             PRAGMA user_version = {};",
+            SCHEMA_VERSION
+        ))?;
+
+        Ok(AirportStore { conn })
+    }
+
+    /// 读取单个机场
+    pub fn get(&self, icao: &str) -> Result<Option<Airport>, Box<dyn Error>> {
+        let data: Option<String> = self.conn
+            .query_row("SELECT data FROM airport WHERE icao = ?1", params![icao], |row| row.get(0))
+            .optional()?;
+
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 全部机场坐标（用于建立空间索引）
+    pub fn locations(&self) -> Result<Vec<AirportLocation>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare("SELECT icao, latitude, longitude FROM airport")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 批量写入（同一事务）
+    pub fn save_all<'a>(&mut self, airports: impl IntoIterator<Item = &'a Airport>) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO airport (icao, latitude, longitude, data) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for airport in airports {
+                stmt.execute(params![
+                    airport.icao,
+                    airport.latitude,
+                    airport.longitude,
+                    serde_json::to_string(airport)?,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 数据文件是否已导入且之后未修改
+    pub fn is_source_current(&self, path: &Path) -> bool {
+        let Some(modified) = modified_secs(path) else { return false };
+        self.conn
+            .query_row(
+                "SELECT modified FROM source WHERE path = ?1",
+                params![path.to_string_lossy()],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|recorded| recorded == modified)
+            .unwrap_or(false)
+    }
+
+    /// 记录数据文件的修改时间
+    pub fn record_source(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let modified = modified_secs(path).ok_or_else(|| format!("无法读取 {} 的修改时间", path.display()))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO source (path, modified) VALUES (?1, ?2)",
+            params![path.to_string_lossy(), modified],
+        )?;
+        Ok(())
+    }
+}
+
+fn modified_secs(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+/// 机场坐标的网格索引（1° × 1°），用于附近机场查询
+#[derive(Debug, Default)]
+pub struct GridIndex {
+    cells: HashMap<(i32, i32), Vec<AirportLocation>>,
+    positions: HashMap<String, (i32, i32)>,
+}
+
+impl GridIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn cell(lat: f64, lon: f64) -> (i32, i32) {
+        ((lat / CELL_DEG).floor() as i32, (lon / CELL_DEG).floor() as i32)
+    }
+
+    /// 添加或移动机场
+    pub fn insert(&mut self, icao: &str, lat: f64, lon: f64) {
+        self.remove(icao);
+        let cell = Self::cell(lat, lon);
+        self.cells.entry(cell).or_default().push((icao.to_string(), lat, lon));
+        self.positions.insert(icao.to_string(), cell);
+    }
+
+    pub fn remove(&mut self, icao: &str) {
+        if let Some(cell) = self.positions.remove(icao) {
+            if let Some(entries) = self.cells.get_mut(&cell) {
+                entries.retain(|(id, _, _)| id != icao);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// 半径内最近的机场，返回（ICAO，距离公里）
+    pub fn nearest(&self, lat: f64, lon: f64, radius_km: f64) -> Option<(String, f64)> {
+        let (row, col) = Self::cell(lat, lon);
+        let lat_cells = (radius_km / (KM_PER_DEG * CELL_DEG)).ceil() as i32;
+        // 高纬度经线收敛，需要搜索更多列
+        let cos_lat = (lat.abs() + radius_km / KM_PER_DEG).min(90.0).to_radians().cos();
+        let total_cols = (360.0 / CELL_DEG) as i32;
+        let lon_cells = if cos_lat < 1e-6 {
+            total_cols / 2
+        } else {
+            ((radius_km / (KM_PER_DEG * CELL_DEG * cos_lat)).ceil() as i32).min(total_cols / 2)
+        };
+
+        let mut nearest: Option<(String, f64)> = None;
+        for dr in -lat_cells..=lat_cells {
+            for dc in -lon_cells..=lon_cells {
+                // 跨越 ±180° 经线
+                let c = (col + dc + total_cols / 2).rem_euclid(total_cols) - total_cols / 2;
+                let Some(entries) = self.cells.get(&(row + dr, c)) else { continue };
+
+                for (icao, a_lat, a_lon) in entries {
+                    let distance = ATCDatabase::calculate_distance(lat, lon, *a_lat, *a_lon);
+                    if distance <= radius_km && nearest.as_ref().is_none_or(|(_, d)| distance < *d) {
+                        nearest = Some((icao.clone(), distance));
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// 伪随机坐标（线性同余），避免引入 rand
+    fn pseudo_random_points(n: usize) -> Vec<AirportLocation> {
+        let mut seed: u64 = 42;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|i| (format!("X{:05}", i), next() * 170.0 - 85.0, next() * 360.0 - 180.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_grid_matches_linear_scan() {
+        let points = pseudo_random_points(70_000);
+        let mut index = GridIndex::new();
+        for (icao, lat, lon) in &points {
+            index.insert(icao, *lat, *lon);
+        }
+        assert_eq!(index.len(), 70_000);
+
+        let queries = pseudo_random_points(200);
+        for (_, lat, lon) in &queries {
+            let expected = points.iter()
+                .map(|(icao, a_lat, a_lon)| (icao, ATCDatabase::calculate_distance(*lat, *lon, *a_lat, *a_lon)))
+                .filter(|(_, d)| *d <= 150.0)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(icao, _)| icao.clone());
+            assert_eq!(index.nearest(*lat, *lon, 150.0).map(|(icao, _)| icao), expected);
+        }
+    }
+
+    /// 计时与构建配置和机器负载有关，需单独运行：cargo test --release -- --ignored
+    #[test]
+    #[ignore]
+    fn test_grid_query_speed() {
+        let mut index = GridIndex::new();
+        for (icao, lat, lon) in &pseudo_random_points(70_000) {
+            index.insert(icao, *lat, *lon);
+        }
+
+        let queries = pseudo_random_points(200);
+        let start = Instant::now();
+        for (_, lat, lon) in &queries {
+            index.nearest(*lat, *lon, 50.0);
+        }
+        let per_query = start.elapsed() / queries.len() as u32;
+        assert!(per_query.as_micros() < 1000, "单次查询 {:?}", per_query);
+    }
+
+    #[test]
+    fn test_grid_wraps_antimeridian() {
+        let mut index = GridIndex::new();
+        index.insert("NFFN", -17.755, 177.443);
+        index.insert("NSFA", -13.83, -172.008);
+        index.insert("EAST", 10.0, -179.9);
+
+        assert_eq!(index.nearest(10.0, 179.9, 50.0).map(|(icao, _)| icao), Some("EAST".to_string()));
+
+        // 移动后旧位置不再命中
+        index.insert("EAST", 20.0, 0.0);
+        assert_eq!(index.nearest(10.0, 179.9, 50.0), None);
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let mut store = AirportStore::open_in_memory().unwrap();
+        let mut db = ATCDatabase::with_store(None);
+        let mut airport = db.get_airport("ZBAA").unwrap().clone();
+        airport.icao = "TEST".to_string();
+
+        store.save_all([&airport]).unwrap();
+        assert_eq!(store.get("TEST").unwrap().unwrap().runways.len(), airport.runways.len());
+        assert!(store.get("NONE").unwrap().is_none());
+        assert_eq!(store.locations().unwrap(), vec![("TEST".to_string(), airport.latitude, airport.longitude)]);
+    }
+
+    #[test]
+    fn test_database_reloads_from_cache() {
+        let path = std::env::temp_dir().join(format!("vatc_airports_{}.sqlite", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        {
+            let mut db = ATCDatabase::with_store(Some(AirportStore::open(&path).unwrap()));
+            let mut imported = db.get_airport("ZBAA").unwrap().clone();
+            imported.icao = "ZLLL".to_string();
+            imported.latitude = 36.5152;
+            imported.longitude = 103.6208;
+            let mut zspd = imported.clone();
+            zspd.icao = "ZSPD".to_string();
+
+            assert_eq!(db.import_airports(vec![imported, zspd]), 2);
+        }
+
+        // 重新打开后无需再次导入
+        let mut db = ATCDatabase::with_store(Some(AirportStore::open(&path).unwrap()));
+        assert_eq!(db.airport_count(), 3);
+        assert_eq!(db.detect_nearest_airport(36.52, 103.62), Some("ZLLL".to_string()));
+        assert!(!db.get_current_airport().unwrap().taxiways.is_empty());
+        assert!(!db.get_airport("ZSPD").unwrap().taxiways.is_empty(), "补全后的内置机场应从缓存恢复");
+
+        drop(db);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,657 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use super::atc_database::{Airport, Frequencies, Gate, Runway, Taxiway, TaxiwayType};
+use super::taxi_network::{TaxiNetwork, TaxiNode, TaxiParking, TaxiRunwayEnd, TaxiSegment};
+
+/// 停机位与滑行道节点的最大关联距离（米）
+const GATE_ACCESS_RADIUS: f64 = 300.0;
+
+/// X-Plane apt.dat 解析器（支持 1000 / 1100 / 1200 格式）
+///
+/// 读取的行代码：
+/// - 1：陆地机场（水上机场 16、直升机场 17 跳过）
+/// - 100：陆地跑道
+/// - 1201 / 1202 / 1204：滑行网络节点、边、跑道活动区
+/// - 1300：停机位
+/// - 1302：机场元数据（基准点经纬度）
+/// - 1050-1056（以及旧格式 50-56）：通信频率
+pub fn parse<R: BufRead>(mut reader: R) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let mut airports = Vec::new();
+    let mut current: Option<AirportBuilder> = None;
+    let mut version_checked = false;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        // 部分第三方场景包含非 UTF-8 的机场名
+        let line = String::from_utf8_lossy(&buf);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(&code) = fields.first() else { continue };
+
+        if !version_checked {
+            // 文件头："I"/"A" 行之后是 "1100 Version ..."
+            if code == "I" || code == "A" {
+                continue;
+            }
+            let version: u32 = code.parse().map_err(|_| "不是有效的 apt.dat 文件（缺少版本号）")?;
+            if !(1000..=1299).contains(&version) {
+                return Err(format!("不支持的 apt.dat 版本: {}（支持 1000/1100/1200）", version).into());
+            }
+            version_checked = true;
+            continue;
+        }
+
+        let row: u32 = match code.parse() {
+            Ok(row) => row,
+            Err(_) => continue,
+        };
+
+        match row {
+            // 新机场开始，结束上一个
+            1 | 16 | 17 | 99 => {
+                if let Some(airport) = current.take().and_then(AirportBuilder::build) {
+                    airports.push(airport);
+                }
+                if row == 1 && fields.len() >= 5 {
+                    current = Some(AirportBuilder::new(&fields));
+                }
+                if row == 99 {
+                    break;
+                }
+            }
+            _ => {
+                if let Some(builder) = current.as_mut() {
+                    builder.add_row(row, &fields);
+                }
+            }
+        }
+    }
+
+    if let Some(airport) = current.take().and_then(AirportBuilder::build) {
+        airports.push(airport);
+    }
+
+    Ok(airports)
+}
+
+/// 读取单个 apt.dat 文件
+pub fn load(path: &Path) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| format!("无法打开 {}: {}", path.display(), e))?;
+    parse(BufReader::new(file))
+}
+
+/// 读取 apt.dat 文件或 X-Plane 安装目录，按场景优先级从高到低返回机场
+pub fn load_all(path: &Path) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let files = if path.is_dir() {
+        find_apt_files(path)
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    if files.is_empty() {
+        return Err(format!("{} 下未找到 apt.dat", path.display()).into());
+    }
+
+    let mut airports = Vec::new();
+    for file in &files {
+        match load(file) {
+            Ok(parsed) => airports.extend(parsed),
+            // 单个场景包损坏不影响其他场景
+            Err(e) if files.len() > 1 => eprintln!("跳过 {}: {}", file.display(), e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    println!("✓ 从 {} 个 apt.dat 读取 {} 个机场", files.len(), airports.len());
+    Ok(airports)
+}
+
+/// 查找 X-Plane 安装目录下的全部 apt.dat，按场景优先级从高到低排列
+///
+/// 自定义场景按 scenery_packs.ini 的顺序（没有该文件时按目录名排序），
+/// 最后是全球机场（X-Plane 11 在 Custom Scenery，X-Plane 12 在 Global Scenery）
+pub fn find_apt_files(xplane_root: &Path) -> Vec<PathBuf> {
+    let custom = xplane_root.join("Custom Scenery");
+    let packs: Vec<PathBuf> = match fs::read_to_string(custom.join("scenery_packs.ini")) {
+        Ok(content) => content.lines()
+            .filter_map(|line| line.trim().strip_prefix("SCENERY_PACK "))
+            .map(|pack| xplane_root.join(pack.trim()))
+            .collect(),
+        Err(_) => {
+            let mut dirs: Vec<PathBuf> = fs::read_dir(&custom)
+                .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                .unwrap_or_default();
+            dirs.sort();
+            dirs
+        }
+    };
+
+    let global = [
+        custom.join("Global Airports"),
+        xplane_root.join("Global Scenery").join("Global Airports"),
+    ];
+
+    packs.into_iter()
+        .filter(|pack| !global.contains(pack) && !pack.ends_with("*GLOBAL_AIRPORTS*"))
+        .chain(global.iter().cloned())
+        .map(|pack| pack.join("Earth nav data").join("apt.dat"))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// 滑行网络的一条边
+struct TaxiEdge {
+    from: u32,
+    to: u32,
+    runway: bool,           // 跑道上的边（名称为跑道号）
+    oneway: bool,
+    max_size: Option<char>, // taxiway_A ~ taxiway_F
+    name: String,
+    arrival_zone: bool,     // 位于着陆跑道活动区内
+    runways: Vec<String>,   // 所在活动区对应的跑道
+}
+
+/// 停机位原始数据
+struct ParkingSpot {
+    latitude: f64,
+    longitude: f64,
+    kind: String,
+    aircraft_types: String,
+    name: String,
+}
+
+/// 单个机场的解析状态
+struct AirportBuilder {
+    icao: String,
+    name: String,
+    elevation: i32,
+    datum: (Option<f64>, Option<f64>),
+    runway_ends: Vec<TaxiRunwayEnd>,
+    runways: Vec<Runway>,
+    nodes: HashMap<u32, (f64, f64)>,
+    edges: Vec<TaxiEdge>,
+    parking: Vec<ParkingSpot>,
+    frequencies: Frequencies,
+    unicom: Vec<f32>,
+}
+
+impl AirportBuilder {
+    /// 行 1：1 标高 0 0 ICAO 机场名称
+    fn new(fields: &[&str]) -> Self {
+        AirportBuilder {
+            icao: fields[4].to_uppercase(),
+            name: fields[5..].join(" "),
+            elevation: fields[1].parse::<f64>().map(|e| e.round() as i32).unwrap_or(0),
+            datum: (None, None),
+            runway_ends: Vec::new(),
+            runways: Vec::new(),
+            nodes: HashMap::new(),
+            edges: Vec::new(),
+            parking: Vec::new(),
+            frequencies: Frequencies::default(),
+            unicom: Vec::new(),
+        }
+    }
+
+    fn add_row(&mut self, row: u32, fields: &[&str]) {
+        let num = |i: usize| fields.get(i).and_then(|v| v.parse::<f64>().ok());
+
+        match row {
+            // 100 宽度 道面 道肩 平整度 中线灯 边灯 标志牌 [跑道端 1 × 9 项] [跑道端 2 × 9 项]
+            100 if fields.len() >= 20 => {
+                let (Some(width), Some(lat1), Some(lon1), Some(lat2), Some(lon2)) =
+                    (num(1), num(9), num(10), num(18), num(19)) else { return };
+                let bearing = initial_bearing(lat1, lon1, lat2, lon2);
+
+                self.runway_ends.push(TaxiRunwayEnd { name: fields[8].to_string(), latitude: lat1, longitude: lon1 });
+                self.runway_ends.push(TaxiRunwayEnd { name: fields[17].to_string(), latitude: lat2, longitude: lon2 });
+                self.runways.push(Runway {
+                    name: format!("{}/{}", fields[8], fields[17]),
+                    heading_1: Runway::designator_heading(fields[8]).unwrap_or(bearing.round() as u16),
+                    heading_2: Runway::designator_heading(fields[17]).unwrap_or(((bearing + 180.0) % 360.0).round() as u16),
+                    length: distance_m(lat1, lon1, lat2, lon2).round() as u32,
+                    width: width.round() as u32,
+                });
+            }
+            // 1201 纬度 经度 用途 节点编号 [名称]
+            1201 => {
+                if let (Some(lat), Some(lon), Some(id)) =
+                    (num(1), num(2), fields.get(4).and_then(|v| v.parse::<u32>().ok())) {
+                    self.nodes.insert(id, (lat, lon));
+                }
+            }
+            // 1202 起点 终点 oneway/twoway runway/taxiway[_X] [名称]
+            1202 => {
+                let (Some(from), Some(to)) = (
+                    fields.get(1).and_then(|v| v.parse::<u32>().ok()),
+                    fields.get(2).and_then(|v| v.parse::<u32>().ok()),
+                ) else { return };
+
+                self.edges.push(TaxiEdge {
+                    from,
+                    to,
+                    runway: fields.get(4).is_some_and(|kind| *kind == "runway"),
+                    oneway: fields.get(3).is_some_and(|dir| *dir == "oneway"),
+                    max_size: fields.get(4)
+                        .and_then(|kind| kind.strip_prefix("taxiway_"))
+                        .and_then(|size| size.chars().next()),
+                    name: fields.get(5..).map(|rest| rest.join(" ")).unwrap_or_default(),
+                    arrival_zone: false,
+                    runways: Vec::new(),
+                });
+            }
+            // 1204 departure/arrival/ils 跑道列表（作用于上一条 1202 边）
+            1204 => {
+                if let (Some(edge), Some(kind), Some(runways)) =
+                    (self.edges.last_mut(), fields.get(1), fields.get(2)) {
+                    if *kind == "arrival" {
+                        edge.arrival_zone = true;
+                    }
+                    for runway in runways.split(',') {
+                        if !edge.runways.iter().any(|r| r == runway) {
+                            edge.runways.push(runway.to_string());
+                        }
+                    }
+                }
+            }
+            // 1300 纬度 经度 航向 gate/hangar/misc/tie-down 可用机型 名称
+            1300 => {
+                if let (Some(lat), Some(lon)) = (num(1), num(2)) {
+                    self.parking.push(ParkingSpot {
+                        latitude: lat,
+                        longitude: lon,
+                        kind: fields.get(4).unwrap_or(&"").to_string(),
+                        aircraft_types: fields.get(5).unwrap_or(&"").replace('|', "/"),
+                        name: fields.get(6..).map(|rest| rest.join(" ")).unwrap_or_default(),
+                    });
+                }
+            }
+            // 1302 键 值
+            1302 => match (fields.get(1), num(2)) {
+                (Some(&"datum_lat"), Some(lat)) => self.datum.0 = Some(lat),
+                (Some(&"datum_lon"), Some(lon)) => self.datum.1 = Some(lon),
+                _ => {}
+            },
+            // 1050-1056 频率单位为 kHz；旧格式 50-56 单位为 10 kHz
+            50..=56 | 1050..=1056 => {
+                let Some(value) = num(1) else { return };
+                let mhz = (if row >= 1050 { value / 1000.0 } else { value / 100.0 }) as f32;
+                let list = match row % 1000 {
+                    50 => &mut self.frequencies.atis,
+                    51 => &mut self.unicom,
+                    52 => &mut self.frequencies.clearance,
+                    53 => &mut self.frequencies.ground,
+                    54 => &mut self.frequencies.tower,
+                    55 => &mut self.frequencies.approach,
+                    _ => &mut self.frequencies.departure,
+                };
+                if !list.contains(&mhz) {
+                    list.push(mhz);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 机场基准点：优先使用 1302 datum，其次跑道端点，再次滑行网络节点
+    fn reference_point(&self) -> Option<(f64, f64)> {
+        if let (Some(lat), Some(lon)) = self.datum {
+            return Some((lat, lon));
+        }
+
+        let points: Vec<(f64, f64)> = if !self.runway_ends.is_empty() {
+            self.runway_ends.iter().map(|e| (e.latitude, e.longitude)).collect()
+        } else if !self.nodes.is_empty() {
+            self.nodes.values().copied().collect()
+        } else {
+            self.parking.iter().map(|p| (p.latitude, p.longitude)).collect()
+        };
+
+        if points.is_empty() {
+            return None;
+        }
+        let n = points.len() as f64;
+        Some((
+            points.iter().map(|p| p.0).sum::<f64>() / n,
+            points.iter().map(|p| p.1).sum::<f64>() / n,
+        ))
+    }
+
+    /// 按名称把滑行道边合并为滑行道
+    fn build_taxiways(&self) -> Vec<Taxiway> {
+        // 每个节点连接的滑行道 / 跑道名称
+        let mut node_names: HashMap<u32, BTreeSet<&str>> = HashMap::new();
+        for edge in self.edges.iter().filter(|e| !e.name.is_empty()) {
+            node_names.entry(edge.from).or_default().insert(&edge.name);
+            node_names.entry(edge.to).or_default().insert(&edge.name);
+        }
+
+        let mut names: Vec<&str> = self.edges.iter()
+            .filter(|e| !e.runway && !e.name.is_empty())
+            .map(|e| e.name.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        names.sort_by_key(|name| natural_key(name));
+
+        names.into_iter().map(|name| {
+            let edges: Vec<&TaxiEdge> = self.edges.iter()
+                .filter(|e| !e.runway && e.name == name)
+                .collect();
+
+            let mut connects: BTreeSet<&str> = BTreeSet::new();
+            for edge in &edges {
+                for node in [edge.from, edge.to] {
+                    if let Some(others) = node_names.get(&node) {
+                        connects.extend(others.iter().filter(|other| **other != name));
+                    }
+                }
+                // 穿越跑道活动区也视为与该跑道相连
+                for runway in &edge.runways {
+                    if let Some(full) = self.runways.iter().find(|r| r.name.split('/').any(|end| end == runway)) {
+                        connects.insert(&full.name);
+                    }
+                }
+            }
+
+            let mut connects_to: Vec<String> = connects.into_iter().map(str::to_string).collect();
+            connects_to.sort_by_key(|name| natural_key(name));
+
+            Taxiway {
+                name: name.to_string(),
+                taxiway_type: classify_taxiway(name, &edges),
+                connects_to,
+            }
+        }).collect()
+    }
+
+    /// 停机位，关联最近的有名称滑行道节点
+    fn build_gates(&self) -> Vec<Gate> {
+        let mut node_names: HashMap<u32, BTreeSet<&str>> = HashMap::new();
+        for edge in self.edges.iter().filter(|e| !e.runway && !e.name.is_empty()) {
+            node_names.entry(edge.from).or_default().insert(&edge.name);
+            node_names.entry(edge.to).or_default().insert(&edge.name);
+        }
+
+        self.parking.iter()
+            .filter(|spot| !spot.name.is_empty())
+            .map(|spot| {
+                let nearest = node_names.iter()
+                    .filter_map(|(id, names)| {
+                        let (lat, lon) = self.nodes.get(id)?;
+                        Some((distance_m(spot.latitude, spot.longitude, *lat, *lon), names))
+                    })
+                    .filter(|(distance, _)| *distance <= GATE_ACCESS_RADIUS)
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+
+                Gate {
+                    number: spot.name.clone(),
+                    terminal: match spot.kind.as_str() {
+                        "gate" => String::new(),
+                        kind => kind.to_string(),
+                    },
+                    aircraft_type: spot.aircraft_types.clone(),
+                    taxiway_access: nearest
+                        .map(|(_, names)| names.iter().map(|n| n.to_string()).collect())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// 滑行网络（用于规划滑行路线）
+    fn build_network(&self) -> TaxiNetwork {
+        let used: BTreeSet<u32> = self.edges.iter().flat_map(|e| [e.from, e.to]).collect();
+
+        TaxiNetwork {
+            nodes: used.into_iter()
+                .filter_map(|id| {
+                    let (latitude, longitude) = self.nodes.get(&id)?;
+                    Some(TaxiNode { id, latitude: *latitude, longitude: *longitude })
+                })
+                .collect(),
+            segments: self.edges.iter()
+                .map(|e| TaxiSegment {
+                    from: e.from,
+                    to: e.to,
+                    name: e.name.clone(),
+                    runway: e.runway,
+                    oneway: e.oneway,
+                    max_size: e.max_size,
+                    runway_zone: e.runways.clone(),
+                    closed: false,
+                })
+                .collect(),
+            parking: self.parking.iter()
+                .filter(|spot| !spot.name.is_empty())
+                .map(|spot| TaxiParking {
+                    name: spot.name.clone(),
+                    latitude: spot.latitude,
+                    longitude: spot.longitude,
+                })
+                .collect(),
+            runway_ends: self.runway_ends.clone(),
+        }
+    }
+
+    fn build(self) -> Option<Airport> {
+        let (latitude, longitude) = self.reference_point()?;
+        let taxiways = self.build_taxiways();
+        let gates = self.build_gates();
+        let taxi_network = self.build_network();
+
+        let mut frequencies = self.frequencies;
+        // 无塔台机场使用 UNICOM / CTAF
+        if frequencies.tower.is_empty() {
+            frequencies.tower = self.unicom;
+        }
+
+        Some(Airport {
+            icao: self.icao,
+            name: self.name.clone(),
+            name_en: self.name,
+            latitude,
+            longitude,
+            elevation: self.elevation,
+            runways: self.runways,
+            taxiways,
+            gates,
+            taxi_routes: vec![],
+            taxi_network,
+            hotspots: vec![],
+            frequencies,
+            sids: vec![],
+            stars: vec![],
+        })
+    }
+}
+
+/// 滑行道类型：名称形如 R1 或位于着陆活动区的单向滑行道视为快速脱离道
+fn classify_taxiway(name: &str, edges: &[&TaxiEdge]) -> TaxiwayType {
+    let rapid_name = name.strip_prefix('R')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()));
+    let rapid_exit = !edges.is_empty() && edges.iter().all(|e| e.oneway && e.arrival_zone);
+
+    if rapid_name || rapid_exit {
+        TaxiwayType::Rapid
+    } else {
+        TaxiwayType::Main
+    }
+}
+
+/// 排序用：字母前缀 + 数字（A2 排在 A10 之前）
+fn natural_key(name: &str) -> (String, u32, String) {
+    let prefix: String = name.chars().take_while(|c| !c.is_ascii_digit()).collect();
+    let rest = &name[prefix.len()..];
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    (prefix, digits.parse().unwrap_or(0), rest[digits.len()..].to_string())
+}
+
+/// 两点距离（米）
+fn distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let r = 6_371_000.0;
+    let dlat = (lat2 - lat1).to_radians();
+    let dlon = (lon2 - lon1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) +
+            lat1.to_radians().cos() * lat2.to_radians().cos() *
+            (dlon / 2.0).sin().powi(2);
+    2.0 * r * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+/// 初始真航向（度）
+fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dlon = (lon2 - lon1).to_radians();
+    let y = dlon.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlon.cos();
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::taxi_network::{RouteOptions, TaxiStart, TaxiTarget};
+    use super::super::atc_database::ATCDatabase;
+
+    const SAMPLE: &str = "I
+1100 Version - data cycle 2024.10
+
+1   4888 0 0 ZLLL Lanzhou Zhongchuan
+1302 datum_lat 36.515200
+1302 datum_lon 103.620800
+100 45.00 1 0 0.25 1 3 1 18 36.53540000 103.62070000 0.00 0.00 3 0 0 0 36 36.49770000 103.62090000 0.00 0.00 3 0 0 0
+1300 36.51600000 103.62500000 270.00 gate heavy|jets Stand 5
+1300 36.51700000 103.62500000 270.00 tie_down props GA 1
+1201 36.53300000 103.62070000 both 0 rwy_n
+1201 36.53300000 103.62300000 both 1 a_n
+1201 36.51600000 103.62300000 both 2 a_s
+1201 36.51600000 103.62400000 both 3 apron
+1202 0 1 twoway taxiway_E A1
+1204 departure 18
+1204 arrival 36
+1202 1 2 twoway taxiway_E A
+1202 2 3 twoway taxiway_D B
+1202 1 0 oneway taxiway_E R2
+1204 arrival 36
+1054 118100 LANZHOU TWR
+1053 121700 LANZHOU GND
+1052 121950 LANZHOU DEL
+1050 127400 ATIS
+1055 126150 LANZHOU APP
+
+1   120 0 0 XUNC Uncontrolled Field
+100 30.00 1 0 0.25 0 0 0 09 10.00000000 20.00000000 0.00 0.00 1 0 0 0 27 10.00000000 20.02000000 0.00 0.00 1 0 0 0
+1051 122800 CTAF
+17  200 0 0 XHEL Helipad Only
+99
+";
+
+    fn parse_sample() -> Vec<Airport> {
+        parse(SAMPLE.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_airports() {
+        let airports = parse_sample();
+        assert_eq!(airports.len(), 2, "直升机场不应导入");
+
+        let zlll = &airports[0];
+        assert_eq!(zlll.icao, "ZLLL");
+        assert_eq!(zlll.name_en, "Lanzhou Zhongchuan");
+        assert_eq!(zlll.elevation, 4888);
+        assert!((zlll.latitude - 36.5152).abs() < 1e-6);
+
+        let runway = &zlll.runways[0];
+        assert_eq!(runway.name, "18/36");
+        assert_eq!((runway.heading_1, runway.heading_2), (180, 360));
+        assert!((4150..4250).contains(&runway.length), "跑道长度 {}", runway.length);
+        assert_eq!(runway.width, 45);
+
+        assert_eq!(zlll.frequencies.tower, vec![118.1]);
+        assert_eq!(zlll.frequencies.ground, vec![121.7]);
+        assert_eq!(zlll.frequencies.clearance, vec![121.95]);
+        assert_eq!(zlll.frequencies.approach, vec![126.15]);
+        assert_eq!(zlll.frequencies.atis, vec![127.4]);
+
+        // 无 datum 时用跑道中点，无塔台时使用 CTAF
+        let field = &airports[1];
+        assert!((field.longitude - 20.01).abs() < 1e-6);
+        assert_eq!(field.frequencies.tower, vec![122.8]);
+    }
+
+    #[test]
+    fn test_taxiway_network() {
+        let airports = parse_sample();
+        let zlll = &airports[0];
+        let names: Vec<&str> = zlll.taxiways.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "A1", "B", "R2"]);
+
+        let a = &zlll.taxiways[0];
+        assert_eq!(a.connects_to, vec!["A1", "B", "R2"]);
+        assert!(matches!(a.taxiway_type, TaxiwayType::Main));
+
+        // A1 进入 18/36 活动区
+        let a1 = &zlll.taxiways[1];
+        assert!(a1.connects_to.contains(&"18/36".to_string()));
+        assert!(matches!(zlll.taxiways[3].taxiway_type, TaxiwayType::Rapid));
+
+        let stand = zlll.gates.iter().find(|g| g.number == "Stand 5").unwrap();
+        assert_eq!(stand.aircraft_type, "heavy/jets");
+        assert_eq!(stand.taxiway_access, vec!["B"]);
+        let ga = zlll.gates.iter().find(|g| g.number == "GA 1").unwrap();
+        assert_eq!(ga.terminal, "tie_down");
+
+        // 从停机位经 B、A 滑行到 18 号跑道等待点（A1 位于活动区内）
+        let network = &zlll.taxi_network;
+        assert_eq!((network.nodes.len(), network.segments.len(), network.parking.len()), (4, 4, 2));
+        let start = TaxiStart::Gate("Stand 5".to_string());
+        let target = TaxiTarget::Runway("18".to_string());
+        let route = zlll.plan_taxi_route(&start, &target, &RouteOptions::default()).unwrap();
+        assert_eq!(route.route, vec!["B", "A"]);
+        assert_eq!(route.to, "RWY18");
+        assert!(route.distance > 1900 && route.distance < 2100, "{}", route.distance);
+
+        // 等待点都在 18 号跑道入口一侧，36 号跑道没有可用的等待点
+        assert_eq!(network.runway_ends.len(), 2);
+        assert!(zlll.plan_taxi_route(&start, &TaxiTarget::Runway("36".to_string()), &RouteOptions::default()).is_none());
+
+        // B 只允许 D 类以下
+        let heavy = RouteOptions { size: Some('E'), closed: vec![] };
+        assert!(zlll.plan_taxi_route(&start, &target, &heavy).is_none());
+    }
+
+    #[test]
+    fn test_import_keeps_builtin_data() {
+        let mut db = ATCDatabase::with_store(None);
+        let before = db.airport_count();
+        let mut zspd = parse_sample().remove(0);
+        zspd.icao = "ZSPD".to_string();
+
+        assert_eq!(db.import_airports(parse_sample()), 2);
+        assert_eq!(db.import_airports(vec![zspd]), 1, "内置机场缺少滑行道时应补全");
+        assert_eq!(db.airport_count(), before + 2);
+
+        db.set_current_airport("ZSPD");
+        let airport = db.get_current_airport().unwrap();
+        assert_eq!(airport.name, "上海浦东国际机场");
+        assert_eq!(airport.runways[0].name, "16L/34R");
+        assert_eq!(airport.taxiways.len(), 4);
+        assert_eq!(airport.frequencies.tower, vec![118.8]);
+        assert_eq!(airport.frequencies.clearance, vec![121.95]);
+
+        assert_eq!(db.detect_nearest_airport(36.52, 103.62), Some("ZLLL".to_string()));
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        assert!(parse("I\n850 Version\n".as_bytes()).is_err());
+        assert!(parse("I\nnot an apt file\n".as_bytes()).is_err());
+    }
+}
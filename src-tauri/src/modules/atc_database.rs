@@ -1,28 +1,75 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use super::airport_store::{AirportStore, GridIndex};
+use super::taxi_network::{self, NetworkRoute, RouteOptions, TaxiNetwork, TaxiStart, TaxiTarget};
+
+/// 自动检测机场的搜索半径（公里）
+const DETECT_RADIUS_KM: f64 = 50.0;
 
 /// ATC 数据库 - 存储机场、航路、频率等信息
-#[derive(Debug, Clone)]
+///
+/// 内置机场常驻内存；导入的机场保存在本地缓存中，按需读取
+#[derive(Debug)]
 pub struct ATCDatabase {
     airports: HashMap<String, Airport>,
+    index: GridIndex,
+    store: Option<AirportStore>,
     current_airport: Option<String>,
 }
 
 impl ATCDatabase {
     pub fn new() -> Self {
+        let store = match AirportStore::open_default() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                eprintln!("⚠ 机场缓存不可用，导入的机场仅保存在内存中: {}", e);
+                None
+            }
+        };
+        Self::with_store(store)
+    }
+    
+    /// 使用指定的缓存（None 表示全部保存在内存中）
+    pub fn with_store(store: Option<AirportStore>) -> Self {
         let mut db = ATCDatabase {
             airports: HashMap::new(),
+            index: GridIndex::new(),
+            store,
             current_airport: None,
         };
         
         // 预加载一些常用机场
         db.load_default_airports();
+        
+        // 缓存中的内置机场是补全后的版本
+        if let Some(store) = &db.store {
+            for (icao, airport) in db.airports.iter_mut() {
+                if let Ok(Some(cached)) = store.get(icao) {
+                    *airport = cached;
+                }
+            }
+            match store.locations() {
+                Ok(locations) => {
+                    for (icao, lat, lon) in locations {
+                        db.index.insert(&icao, lat, lon);
+                    }
+                }
+                Err(e) => eprintln!("⚠ 读取机场缓存失败: {}", e),
+            }
+        }
+        
+        for airport in db.airports.values() {
+            db.index.insert(&airport.icao, airport.latitude, airport.longitude);
+        }
         db
     }
     
     /// 设置当前机场（根据飞机位置自动检测）
     pub fn set_current_airport(&mut self, icao: &str) {
-        self.current_airport = Some(icao.to_uppercase());
+        let icao = icao.to_uppercase();
+        self.ensure_loaded(&icao);
+        self.current_airport = Some(icao);
     }
     
     /// 获取当前机场信息
@@ -31,34 +78,46 @@ impl ATCDatabase {
             .and_then(|icao| self.airports.get(icao))
     }
     
+    /// 按 ICAO 获取机场（必要时从缓存读取）
+    pub fn get_airport(&mut self, icao: &str) -> Option<&Airport> {
+        let icao = icao.to_uppercase();
+        self.ensure_loaded(&icao);
+        self.airports.get(&icao)
+    }
+    
     /// 根据经纬度自动检测最近的机场
     pub fn detect_nearest_airport(&mut self, lat: f64, lon: f64) -> Option<String> {
-        let mut nearest: Option<(String, f64)> = None;
+        let (icao, _) = self.index.nearest(lat, lon, DETECT_RADIUS_KM)?;
         
-        for (icao, airport) in &self.airports {
-            let distance = Self::calculate_distance(lat, lon, airport.latitude, airport.longitude);
-            
-            if distance < 50.0 {  // 50公里范围内
-                if let Some((_, min_dist)) = nearest {
-                    if distance < min_dist {
-                        nearest = Some((icao.clone(), distance));
-                    }
-                } else {
-                    nearest = Some((icao.clone(), distance));
+        self.ensure_loaded(&icao);
+        self.current_airport = Some(icao.clone());
+        Some(icao)
+    }
+    
+    /// 把缓存中的机场读入内存
+    fn ensure_loaded(&mut self, icao: &str) {
+        if self.airports.contains_key(icao) {
+            return;
+        }
+        if let Some(store) = &self.store {
+            match store.get(icao) {
+                Ok(Some(airport)) => {
+                    self.airports.insert(icao.to_string(), airport);
                 }
+                Ok(None) => {}
+                Err(e) => eprintln!("读取机场 {} 失败: {}", icao, e),
             }
         }
-        
-        if let Some((icao, _)) = nearest {
-            self.current_airport = Some(icao.clone());
-            Some(icao)
-        } else {
-            None
-        }
+    }
+    
+    /// 查找机场（内存优先，其次缓存），不读入内存
+    fn lookup(&self, icao: &str) -> Option<Airport> {
+        self.airports.get(icao).cloned()
+            .or_else(|| self.store.as_ref().and_then(|store| store.get(icao).ok().flatten()))
     }
     
     /// 计算两点距离（简化版，单位：公里）
-    fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
         let r = 6371.0; // 地球半径（公里）
         let dlat = (lat2 - lat1).to_radians();
         let dlon = (lon2 - lon1).to_radians();
@@ -69,6 +128,74 @@ impl ATCDatabase {
         r * c
     }
     
+    /// 导入外部机场数据，返回新增或补全的机场数量
+    ///
+    /// 已有机场（内置数据或更高优先级的场景）只补全缺失的部分，
+    /// 因此应按优先级从高到低导入
+    pub fn import_airports(&mut self, airports: Vec<Airport>) -> usize {
+        let mut changed: HashMap<String, Airport> = HashMap::new();
+        
+        for airport in airports {
+            // 本批次已改动的机场即使没有新内容也需要保存
+            let (existing, pending) = match changed.remove(&airport.icao) {
+                Some(pending) => (Some(pending), true),
+                None => (self.lookup(&airport.icao), false),
+            };
+            
+            match existing {
+                Some(mut existing) => {
+                    if existing.merge_missing(airport) || pending {
+                        changed.insert(existing.icao.clone(), existing);
+                    }
+                }
+                None => {
+                    changed.insert(airport.icao.clone(), airport);
+                }
+            }
+        }
+        
+        for airport in changed.values() {
+            self.index.insert(&airport.icao, airport.latitude, airport.longitude);
+        }
+        
+        let count = changed.len();
+        match self.store.as_mut() {
+            Some(store) => {
+                if let Err(e) = store.save_all(changed.values()) {
+                    eprintln!("⚠ 写入机场缓存失败: {}", e);
+                }
+                // 已在内存中的机场同步更新，其余按需读取
+                for (icao, airport) in changed {
+                    if let Some(slot) = self.airports.get_mut(&icao) {
+                        *slot = airport;
+                    }
+                }
+            }
+            None => self.airports.extend(changed),
+        }
+        
+        count
+    }
+    
+    /// 数据文件自上次导入后是否未修改（没有缓存时总是需要重新导入）
+    pub fn is_source_current(&self, path: &Path) -> bool {
+        self.store.as_ref().is_some_and(|store| store.is_source_current(path))
+    }
+    
+    /// 记录已导入的数据文件
+    pub fn record_source(&self, path: &Path) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record_source(path) {
+                eprintln!("记录数据来源失败: {}", e);
+            }
+        }
+    }
+    
+    /// 已加载的机场数量
+    pub fn airport_count(&self) -> usize {
+        self.index.len()
+    }
+    
     /// 获取 ATC 上下文信息（用于 LLM）
     pub fn get_atc_context(&self, language: &str) -> String {
         if let Some(airport) = self.get_current_airport() {
@@ -135,6 +262,7 @@ impl ATCDatabase {
                     route: vec!["A3".to_string(), "A".to_string()],
                     distance: 2500,
                     hotspots: vec!["HS1".to_string()],
+                    crossings: vec![],
                 },
                 TaxiRoute {
                     from: "RWY36R".to_string(),
@@ -142,8 +270,10 @@ impl ATCDatabase {
                     route: vec!["R5".to_string(), "B".to_string(), "B2".to_string()],
                     distance: 1800,
                     hotspots: vec![],
+                    crossings: vec![],
                 },
             ],
+            taxi_network: TaxiNetwork::default(),
             hotspots: vec![
                 Hotspot {
                     id: "HS1".to_string(),
@@ -155,6 +285,7 @@ impl ATCDatabase {
             frequencies: Frequencies {
                 tower: vec![118.5, 118.7],
                 ground: vec![121.6, 121.75],
+                clearance: vec![],
                 departure: vec![125.75, 124.6],
                 approach: vec![119.7, 124.5],
                 atis: vec![127.6],
@@ -177,10 +308,12 @@ impl ATCDatabase {
             taxiways: vec![],
             gates: vec![],
             taxi_routes: vec![],
+            taxi_network: TaxiNetwork::default(),
             hotspots: vec![],
             frequencies: Frequencies {
                 tower: vec![118.8],
                 ground: vec![121.65],
+                clearance: vec![],
                 departure: vec![120.4],
                 approach: vec![119.1],
                 atis: vec![127.75],
@@ -199,11 +332,13 @@ pub struct Airport {
     pub name_en: String,
     pub latitude: f64,
     pub longitude: f64,
-    pub elevation: i32,
+    pub elevation: i32,     // 标高（英尺）
     pub runways: Vec<Runway>,
     pub taxiways: Vec<Taxiway>,
     pub gates: Vec<Gate>,
     pub taxi_routes: Vec<TaxiRoute>,
+    #[serde(default)]
+    pub taxi_network: TaxiNetwork,
     pub hotspots: Vec<Hotspot>,
     pub frequencies: Frequencies,
     pub sids: Vec<String>,
@@ -211,6 +346,36 @@ pub struct Airport {
 }
 
 impl Airport {
+    /// 用另一份数据补全缺失的跑道、滑行道、停机位、滑行网络、频率和程序，返回是否有改动
+    pub fn merge_missing(&mut self, other: Airport) -> bool {
+        let mut changed = false;
+        
+        fn fill<T>(target: &mut Vec<T>, source: Vec<T>, changed: &mut bool) {
+            if target.is_empty() && !source.is_empty() {
+                *target = source;
+                *changed = true;
+            }
+        }
+        
+        fill(&mut self.runways, other.runways, &mut changed);
+        fill(&mut self.taxiways, other.taxiways, &mut changed);
+        fill(&mut self.gates, other.gates, &mut changed);
+        if self.taxi_network.is_empty() && !other.taxi_network.is_empty() {
+            self.taxi_network = other.taxi_network;
+            changed = true;
+        }
+        fill(&mut self.frequencies.tower, other.frequencies.tower, &mut changed);
+        fill(&mut self.frequencies.ground, other.frequencies.ground, &mut changed);
+        fill(&mut self.frequencies.clearance, other.frequencies.clearance, &mut changed);
+        fill(&mut self.frequencies.departure, other.frequencies.departure, &mut changed);
+        fill(&mut self.frequencies.approach, other.frequencies.approach, &mut changed);
+        fill(&mut self.frequencies.atis, other.frequencies.atis, &mut changed);
+        fill(&mut self.sids, other.sids, &mut changed);
+        fill(&mut self.stars, other.stars, &mut changed);
+        
+        changed
+    }
+    
     /// 获取滑行路线
     pub fn get_taxi_route(&self, from: &str, to: &str) -> Option<&TaxiRoute> {
         self.taxi_routes.iter().find(|r| {
@@ -218,21 +383,143 @@ impl Airport {
         })
     }
     
+    /// 规划滑行路线
+    ///
+    /// 优先使用预设路线，其次在滑行网络中搜索最短路线；没有滑行网络时按滑行道连接关系搜索（只支持从停机位到跑道，不检查翼展限制）
+    pub fn plan_taxi_route(&self, start: &TaxiStart, target: &TaxiTarget, options: &RouteOptions) -> Option<TaxiRoute> {
+        let to = match target {
+            TaxiTarget::Runway(end) => format!("RWY{}", taxi_network::normalize_runway_end(end)),
+            TaxiTarget::Gate(name) => name.trim().to_string(),
+        };
+        let gate = match start {
+            TaxiStart::Gate(name) => self.gates.iter().find(|g| g.number.eq_ignore_ascii_case(name.trim())),
+            TaxiStart::Position { .. } => None,
+        };
+        let from = match (start, gate) {
+            (_, Some(gate)) if !gate.terminal.is_empty() => format!("{}-{}", gate.terminal, gate.number),
+            (_, Some(gate)) => gate.number.clone(),
+            (TaxiStart::Gate(name), None) => name.trim().to_string(),
+            (TaxiStart::Position { latitude, longitude }, None) => self.taxi_network
+                .nearest_parking(*latitude, *longitude)
+                .unwrap_or_default()
+                .to_string(),
+        };
+        
+        // 预设路线（可反向使用）
+        if let Some(preset) = self.get_taxi_route(&from, &to) {
+            if !preset.route.iter().any(|name| options.is_closed(name)) {
+                let mut route = preset.clone();
+                if route.from != from {
+                    route.route.reverse();
+                    route.from = from;
+                    route.to = to;
+                }
+                return Some(route);
+            }
+        }
+        
+        if !self.taxi_network.is_empty() {
+            let position = match start {
+                TaxiStart::Position { latitude, longitude } => Some((*latitude, *longitude)),
+                TaxiStart::Gate(name) => self.taxi_network.parking_position(name),
+            };
+            let found = position.and_then(|(lat, lon)| self.taxi_network.find_route(lat, lon, target, options))?;
+            return Some(self.network_taxi_route(from, to, found));
+        }
+        
+        let (Some(gate), TaxiTarget::Runway(end)) = (gate, target) else { return None };
+        let runway = self.runway_name(end)?;
+        let mut route = taxi_network::find_named_route(&self.taxiways, &gate.taxiway_access, &[runway], options)?;
+        route.pop();   // 最后一项为跑道本身
+        
+        Some(TaxiRoute {
+            from,
+            to,
+            hotspots: self.hotspots_on(&route),
+            route,
+            distance: 0,
+            crossings: vec![],
+        })
+    }
+    
+    /// 把网络中的路线整理为滑行道名称序列，并记录穿越的跑道
+    fn network_taxi_route(&self, from: String, to: String, found: NetworkRoute) -> TaxiRoute {
+        let mut route: Vec<String> = Vec::new();
+        let mut crossings: Vec<String> = Vec::new();
+        
+        for segment in &found.segments {
+            let crossed = if segment.runway {
+                Some(self.runway_name(&segment.name).unwrap_or(&segment.name).to_string())
+            } else {
+                segment.runway_zone.first()
+                    .map(|end| self.runway_name(end).map(str::to_string).unwrap_or_else(|| segment.runway_zone.join("/")))
+            };
+            if let Some(crossed) = crossed {
+                if !crossings.contains(&crossed) {
+                    crossings.push(crossed);
+                }
+            }
+            
+            if !segment.runway && !segment.name.is_empty() && route.last() != Some(&segment.name) {
+                route.push(segment.name.clone());
+            }
+        }
+        
+        TaxiRoute {
+            from,
+            to,
+            hotspots: self.hotspots_on(&route),
+            route,
+            distance: found.distance.round() as u32,
+            crossings,
+        }
+    }
+    
+    /// 跑道端所在跑道的完整名称（"36R" → "18L/36R"）
+    fn runway_name(&self, end: &str) -> Option<&str> {
+        let end = end.split(['/', ',']).next().unwrap_or(end);
+        self.runways.iter()
+            .find(|r| taxi_network::runway_ends(&r.name).iter().any(|e| taxi_network::same_runway_end(e, end)))
+            .map(|r| r.name.as_str())
+    }
+    
+    /// 位置描述中提到了路线上滑行道的热点
+    fn hotspots_on(&self, route: &[String]) -> Vec<String> {
+        self.hotspots.iter()
+            .filter(|h| h.location
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|token| !token.is_empty() && route.iter().any(|name| name == token)))
+            .map(|h| h.id.clone())
+            .collect()
+    }
+    
     /// 格式化滑行指令
     pub fn format_taxi_instruction(&self, route: &TaxiRoute, language: &str) -> String {
         if language == "zh" {
-            let mut instruction = format!("经滑行道 {} 滑行至{}", 
-                route.route.join("、"), route.to);
+            let mut instruction = if route.route.is_empty() {
+                format!("滑行至{}", route.to)
+            } else {
+                format!("经滑行道 {} 滑行至{}", route.route.join("、"), route.to)
+            };
             
+            if !route.crossings.is_empty() {
+                instruction.push_str(&format!("，穿越跑道 {}", route.crossings.join("、")));
+            }
             if !route.hotspots.is_empty() {
                 instruction.push_str(&format!("，注意热点区域：{}", route.hotspots.join("、")));
             }
             
             instruction
         } else {
-            let mut instruction = format!("Taxi via {} to {}", 
-                route.route.join(", "), route.to);
+            let mut instruction = if route.route.is_empty() {
+                format!("Taxi to {}", route.to)
+            } else {
+                format!("Taxi via {} to {}", route.route.join(", "), route.to)
+            };
             
+            if !route.crossings.is_empty() {
+                instruction.push_str(&format!(", cross runway {}", route.crossings.join(", ")));
+            }
             if !route.hotspots.is_empty() {
                 instruction.push_str(&format!(", caution hotspots: {}", route.hotspots.join(", ")));
             }
@@ -241,6 +528,36 @@ impl Airport {
         }
     }
     
+    /// 从当前位置到各跑道端等待点的滑行路线（需要滑行网络）
+    pub fn format_taxi_options(&self, lat: f64, lon: f64, options: &RouteOptions, language: &str) -> Option<String> {
+        if self.taxi_network.is_empty() {
+            return None;
+        }
+        
+        let start = TaxiStart::Position { latitude: lat, longitude: lon };
+        let lines: Vec<String> = self.runways.iter()
+            .flat_map(|r| taxi_network::runway_ends(&r.name))
+            .filter_map(|end| {
+                let route = self.plan_taxi_route(&start, &TaxiTarget::Runway(end.to_string()), options)?;
+                let instruction = self.format_taxi_instruction(&route, language);
+                Some(if language == "zh" {
+                    format!("- 跑道 {}：{}（约 {} 米）", end, instruction, route.distance)
+                } else {
+                    format!("- Runway {}: {} (approx {} m)", end, instruction, route.distance)
+                })
+            })
+            .collect();
+        
+        if lines.is_empty() {
+            return None;
+        }
+        Some(if language == "zh" {
+            format!("**从当前位置到各跑道等待点的滑行路线：**\n{}", lines.join("\n"))
+        } else {
+            format!("**Taxi routes from current position to runway holding points:**\n{}", lines.join("\n"))
+        })
+    }
+    
     /// 格式化为 LLM 上下文
     pub fn format_context(&self, language: &str) -> String {
         if language == "zh" {
@@ -249,7 +566,7 @@ impl Airport {
 
 **基本信息：**
 - ICAO 代码：{}
-- 海拔：{} 米
+- 海拔：{} 英尺
 - 坐标：{:.4}°N, {:.4}°E
 
 **跑道信息：**
@@ -270,6 +587,7 @@ impl Airport {
 **通信频率：**
 - 塔台：{} MHz
 - 地面：{} MHz
+- 放行：{} MHz
 - 离场：{} MHz
 - 进近：{} MHz
 - ATIS：{} MHz
@@ -294,6 +612,7 @@ impl Airport {
                 self.format_hotspots(language),
                 self.frequencies.format_list(&self.frequencies.tower),
                 self.frequencies.format_list(&self.frequencies.ground),
+                self.frequencies.format_list(&self.frequencies.clearance),
                 self.frequencies.format_list(&self.frequencies.departure),
                 self.frequencies.format_list(&self.frequencies.approach),
                 self.frequencies.format_list(&self.frequencies.atis),
@@ -306,7 +625,7 @@ impl Airport {
 
 **Basic Info:**
 - ICAO Code: {}
-- Elevation: {} m
+- Elevation: {} ft
 - Coordinates: {:.4}°N, {:.4}°E
 
 **Runways:**
@@ -327,6 +646,7 @@ impl Airport {
 **Frequencies:**
 - Tower: {} MHz
 - Ground: {} MHz
+- Delivery: {} MHz
 - Departure: {} MHz
 - Approach: {} MHz
 - ATIS: {} MHz
@@ -351,6 +671,7 @@ impl Airport {
                 self.format_hotspots(language),
                 self.frequencies.format_list(&self.frequencies.tower),
                 self.frequencies.format_list(&self.frequencies.ground),
+                self.frequencies.format_list(&self.frequencies.clearance),
                 self.frequencies.format_list(&self.frequencies.departure),
                 self.frequencies.format_list(&self.frequencies.approach),
                 self.frequencies.format_list(&self.frequencies.atis),
@@ -397,7 +718,13 @@ impl Airport {
         }
         
         self.gates.iter().take(10).map(|g| {
-            if language == "zh" {
+            if g.terminal.is_empty() {
+                if language == "zh" {
+                    format!("- 停机位 {}（{}）", g.number, g.aircraft_type)
+                } else {
+                    format!("- Gate {} ({})", g.number, g.aircraft_type)
+                }
+            } else if language == "zh" {
                 format!("- {} 停机位 {}（{}）", g.terminal, g.number, g.aircraft_type)
             } else {
                 format!("- Terminal {} Gate {} ({})", g.terminal, g.number, g.aircraft_type)
@@ -446,6 +773,15 @@ pub struct Runway {
     pub width: u32,
 }
 
+impl Runway {
+    /// 跑道号对应的航向（"09L" → 90，"36" → 360）
+    pub fn designator_heading(designator: &str) -> Option<u16> {
+        let digits: String = designator.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let number: u16 = digits.parse().ok()?;
+        (1..=36).contains(&number).then_some(number * 10)
+    }
+}
+
 /// 滑行道信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Taxiway {
@@ -479,6 +815,8 @@ pub struct TaxiRoute {
     pub route: Vec<String>,
     pub distance: u32,
     pub hotspots: Vec<String>,
+    #[serde(default)]
+    pub crossings: Vec<String>,   // 途中穿越的跑道
 }
 
 /// 热点区域
@@ -491,10 +829,12 @@ pub struct Hotspot {
 }
 
 /// 通信频率
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Frequencies {
     pub tower: Vec<f32>,
     pub ground: Vec<f32>,
+    #[serde(default)]
+    pub clearance: Vec<f32>,
     pub departure: Vec<f32>,
     pub approach: Vec<f32>,
     pub atis: Vec<f32>,
@@ -0,0 +1,329 @@
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use super::aircraft::WakeCategory;
+use super::config_store;
+use super::simulator::FlightData;
+
+/// 航空公司无线电呼号
+#[derive(Debug, Clone, Copy)]
+pub struct Airline {
+    pub icao: &'static str,
+    pub iata: &'static str,
+    pub telephony_en: &'static str,
+    pub telephony_zh: Option<&'static str>,   // 无中文呼号时使用英文
+}
+
+/// 常用航空公司（ICAO 代码、IATA 代码、英文呼号、中文呼号）
+const AIRLINES: &[Airline] = &[
+    Airline { icao: "CCA", iata: "CA", telephony_en: "Air China", telephony_zh: Some("国航") },
+    Airline { icao: "CES", iata: "MU", telephony_en: "China Eastern", telephony_zh: Some("东方") },
+    Airline { icao: "CSN", iata: "CZ", telephony_en: "China Southern", telephony_zh: Some("南方") },
+    Airline { icao: "CHH", iata: "HU", telephony_en: "Hainan", telephony_zh: Some("海南") },
+    Airline { icao: "CXA", iata: "MF", telephony_en: "Xiamen Air", telephony_zh: Some("白鹭") },
+    Airline { icao: "CSZ", iata: "ZH", telephony_en: "Shenzhen Air", telephony_zh: Some("深圳") },
+    Airline { icao: "CSC", iata: "3U", telephony_en: "Sichuan", telephony_zh: Some("四川") },
+    Airline { icao: "CDG", iata: "SC", telephony_en: "Shandong", telephony_zh: Some("山东") },
+    Airline { icao: "CQH", iata: "9C", telephony_en: "Air Spring", telephony_zh: Some("春秋") },
+    Airline { icao: "DKH", iata: "HO", telephony_en: "Juneyao Air", telephony_zh: Some("吉祥") },
+    Airline { icao: "CBJ", iata: "JD", telephony_en: "Capital Jet", telephony_zh: Some("首都") },
+    Airline { icao: "CHB", iata: "PN", telephony_en: "West China", telephony_zh: Some("西部") },
+    Airline { icao: "CPA", iata: "CX", telephony_en: "Cathay", telephony_zh: Some("国泰") },
+    Airline { icao: "UAL", iata: "UA", telephony_en: "United", telephony_zh: None },
+    Airline { icao: "AAL", iata: "AA", telephony_en: "American", telephony_zh: None },
+    Airline { icao: "DAL", iata: "DL", telephony_en: "Delta", telephony_zh: None },
+    Airline { icao: "SWA", iata: "WN", telephony_en: "Southwest", telephony_zh: None },
+    Airline { icao: "BAW", iata: "BA", telephony_en: "Speedbird", telephony_zh: None },
+    Airline { icao: "DLH", iata: "LH", telephony_en: "Lufthansa", telephony_zh: None },
+    Airline { icao: "AFR", iata: "AF", telephony_en: "Airfrans", telephony_zh: None },
+    Airline { icao: "KLM", iata: "KL", telephony_en: "KLM", telephony_zh: None },
+    Airline { icao: "RYR", iata: "FR", telephony_en: "Ryanair", telephony_zh: None },
+    Airline { icao: "EZY", iata: "U2", telephony_en: "Easy", telephony_zh: None },
+    Airline { icao: "UAE", iata: "EK", telephony_en: "Emirates", telephony_zh: None },
+    Airline { icao: "SIA", iata: "SQ", telephony_en: "Singapore", telephony_zh: None },
+    Airline { icao: "JAL", iata: "JL", telephony_en: "Japan Air", telephony_zh: None },
+    Airline { icao: "ANA", iata: "NH", telephony_en: "All Nippon", telephony_zh: None },
+    Airline { icao: "KAL", iata: "KE", telephony_en: "Korean Air", telephony_zh: None },
+    Airline { icao: "AAR", iata: "OZ", telephony_en: "Asiana", telephony_zh: None },
+    Airline { icao: "QFA", iata: "QF", telephony_en: "Qantas", telephony_zh: None },
+];
+
+/// ICAO 字母解释法
+const PHONETIC: [&str; 26] = [
+    "Alfa", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India",
+    "Juliett", "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo",
+    "Sierra", "Tango", "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
+const DIGITS_EN: [&str; 10] = ["Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Niner"];
+
+/// 按 ICAO/IATA 代码或呼号查找航空公司（不区分大小写）
+pub fn find_airline(name: &str) -> Option<&'static Airline> {
+    let name = name.trim();
+    AIRLINES.iter().find(|airline| {
+        airline.icao.eq_ignore_ascii_case(name)
+            || airline.iata.eq_ignore_ascii_case(name)
+            || airline.telephony_en.eq_ignore_ascii_case(name)
+            || airline.telephony_zh == Some(name)
+    })
+}
+
+/// 用户设置的呼号（优先于模拟器）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CallsignProfile {
+    pub airline: String,        // 航空公司 ICAO/IATA 代码或呼号，如 "CCA"、"CA"、"Air China"
+    pub flight_number: String,  // 航班号，如 "1234"
+}
+
+impl CallsignProfile {
+    /// 读取上次保存的设置（不存在或无效时为空）
+    pub fn load() -> Self {
+        config_store::load_json::<CallsignProfile>("callsign.json")
+            .filter(|profile| profile.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    /// 保存设置
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        config_store::save_json("callsign.json", self)
+    }
+
+    /// 是否已设置（航空公司和航班号均为空表示使用模拟器呼号）
+    pub fn is_set(&self) -> bool {
+        !self.airline.trim().is_empty() || !self.flight_number.trim().is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.is_set() {
+            return Ok(());
+        }
+        if self.airline.trim().is_empty() {
+            return Err("请填写航空公司".to_string());
+        }
+        if !is_flight_number(self.flight_number.trim()) {
+            return Err(format!("航班号格式无效：{}（应为 1~4 位数字，可带 1~2 个字母后缀，如 1234、123A）", self.flight_number));
+        }
+        Ok(())
+    }
+}
+
+/// 呼号来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallsignSource {
+    User,           // 用户设置
+    FlightPlan,     // 模拟器航班信息（如 MSFS ATC_AIRLINE + ATC_FLIGHT_NUMBER）
+    Simulator,      // 模拟器机尾号/ATC ID
+    Unknown,        // 无可用呼号
+}
+
+/// 解析后的呼号
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Callsign {
+    pub code: String,       // 书面形式，如 "CCA1234"、"N172SP"
+    pub spoken_en: String,  // 英文读法，如 "Air China 1234"
+    pub spoken_zh: String,  // 中文读法，如 "国航1234"
+    pub source: CallsignSource,
+}
+
+impl Callsign {
+    /// 按语言返回无线电通话中使用的呼号
+    pub fn spoken(&self, language: &str) -> &str {
+        if language == "zh" {
+            &self.spoken_zh
+        } else {
+            &self.spoken_en
+        }
+    }
+
+    /// 在呼号后加上尾流等级（如 "Air China 981 Heavy"、"国航981重型"）
+    pub fn with_wake(mut self, wake: WakeCategory) -> Self {
+        if self.source == CallsignSource::Unknown {
+            return self;
+        }
+        if let Some(suffix) = wake.callsign_suffix("en") {
+            self.spoken_en = format!("{} {}", self.spoken_en, suffix);
+        }
+        if let Some(suffix) = wake.callsign_suffix("zh") {
+            self.spoken_zh = format!("{}{}", self.spoken_zh, suffix);
+        }
+        self
+    }
+
+    /// 航空公司 + 航班号
+    fn airline(airline: &str, flight_number: &str, source: CallsignSource) -> Self {
+        let flight_number = flight_number.trim().to_uppercase();
+
+        match find_airline(airline) {
+            Some(known) => Callsign {
+                code: format!("{}{}", known.icao, flight_number),
+                spoken_en: format!("{} {}", known.telephony_en, flight_number),
+                spoken_zh: format!("{}{}", known.telephony_zh.unwrap_or(known.telephony_en), flight_number),
+                source,
+            },
+            // 未知航空公司直接使用输入的名称
+            None => {
+                let airline = airline.trim();
+                Callsign {
+                    code: format!("{}{}", airline.to_uppercase().replace(' ', ""), flight_number),
+                    spoken_en: format!("{} {}", airline, flight_number),
+                    spoken_zh: format!("{}{}", airline, flight_number),
+                    source,
+                }
+            }
+        }
+    }
+
+    /// 机尾号/ATC ID（如 "N172SP"、"B-1234"，或 "CCA1234" 这样的航班呼号）
+    fn registration(id: &str) -> Self {
+        let code: String = id.trim().to_uppercase().chars().filter(|c| *c != '-').collect();
+
+        // 三字母航空公司代码 + 航班号
+        if code.len() > 3 && code.is_char_boundary(3) {
+            let (prefix, number) = code.split_at(3);
+            if find_airline(prefix).is_some() && is_flight_number(number) {
+                return Callsign::airline(prefix, number, CallsignSource::Simulator);
+            }
+        }
+
+        Callsign {
+            spoken_en: spell(&code),
+            spoken_zh: code.clone(),
+            code,
+            source: CallsignSource::Simulator,
+        }
+    }
+
+    fn unknown() -> Self {
+        Callsign {
+            code: String::new(),
+            spoken_en: "Aircraft".to_string(),
+            spoken_zh: "该机".to_string(),
+            source: CallsignSource::Unknown,
+        }
+    }
+}
+
+/// 解析呼号：用户设置 > 模拟器航班信息 > 模拟器机尾号
+pub fn resolve(profile: &CallsignProfile, data: &FlightData) -> Callsign {
+    if profile.is_set() && profile.validate().is_ok() {
+        return Callsign::airline(&profile.airline, &profile.flight_number, CallsignSource::User);
+    }
+
+    if !data.atc_airline.trim().is_empty() && is_flight_number(data.atc_flight_number.trim()) {
+        return Callsign::airline(&data.atc_airline, &data.atc_flight_number, CallsignSource::FlightPlan);
+    }
+
+    if !data.callsign.trim().is_empty() {
+        return Callsign::registration(&data.callsign);
+    }
+
+    Callsign::unknown()
+}
+
+/// 航班号：1~4 位数字 + 最多 2 个字母后缀
+fn is_flight_number(number: &str) -> bool {
+    let digits = number.chars().take_while(|c| c.is_ascii_digit()).count();
+    let suffix = &number[digits..];
+
+    (1..=4).contains(&digits)
+        && suffix.len() <= 2
+        && suffix.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// 逐字读出（字母使用 ICAO 字母解释法）
+fn spell(code: &str) -> String {
+    code.chars()
+        .filter_map(|c| {
+            if c.is_ascii_uppercase() {
+                Some(PHONETIC[(c as u8 - b'A') as usize])
+            } else if c.is_ascii_digit() {
+                Some(DIGITS_EN[(c as u8 - b'0') as usize])
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sim_data(callsign: &str, airline: &str, flight_number: &str) -> FlightData {
+        FlightData {
+            callsign: callsign.to_string(),
+            atc_airline: airline.to_string(),
+            atc_flight_number: flight_number.to_string(),
+            ..FlightData::default()
+        }
+    }
+
+    #[test]
+    fn test_user_profile_takes_priority() {
+        let profile = CallsignProfile { airline: "CA".to_string(), flight_number: "1234".to_string() };
+        let callsign = resolve(&profile, &sim_data("N172SP", "Delta", "55"));
+
+        assert_eq!(callsign.code, "CCA1234");
+        assert_eq!(callsign.spoken("en"), "Air China 1234");
+        assert_eq!(callsign.spoken("zh"), "国航1234");
+        assert_eq!(callsign.source, CallsignSource::User);
+    }
+
+    #[test]
+    fn test_sim_flight_plan_and_registration() {
+        let empty = CallsignProfile::default();
+
+        let callsign = resolve(&empty, &sim_data("N172SP", "Delta", "55"));
+        assert_eq!(callsign.spoken("en"), "Delta 55");
+        assert_eq!(callsign.source, CallsignSource::FlightPlan);
+
+        let callsign = resolve(&empty, &sim_data("N172SP", "", ""));
+        assert_eq!(callsign.code, "N172SP");
+        assert_eq!(callsign.spoken("en"), "November One Seven Two Sierra Papa");
+
+        // ATC ID 本身是航班呼号
+        let callsign = resolve(&empty, &sim_data("CES5678", "", ""));
+        assert_eq!(callsign.spoken("zh"), "东方5678");
+
+        assert_eq!(resolve(&empty, &sim_data("", "", "")).source, CallsignSource::Unknown);
+    }
+
+    #[test]
+    fn test_callsign_does_not_depend_on_altitude() {
+        let empty = CallsignProfile::default();
+        let mut data = sim_data("B-6543", "", "");
+        let on_ground = resolve(&empty, &data);
+        data.altitude = 35000.0;
+
+        assert_eq!(resolve(&empty, &data), on_ground);
+        assert_eq!(on_ground.code, "B6543");
+    }
+
+    #[test]
+    fn test_wake_category_suffix() {
+        let empty = CallsignProfile::default();
+        let callsign = resolve(&empty, &sim_data("", "CCA", "981")).with_wake(WakeCategory::Heavy);
+        assert_eq!(callsign.spoken("en"), "Air China 981 Heavy");
+        assert_eq!(callsign.spoken("zh"), "国航981重型");
+        assert_eq!(callsign.code, "CCA981");
+
+        let callsign = resolve(&empty, &sim_data("", "CCA", "981")).with_wake(WakeCategory::Medium);
+        assert_eq!(callsign.spoken("en"), "Air China 981");
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let profile = |airline: &str, number: &str| CallsignProfile {
+            airline: airline.to_string(),
+            flight_number: number.to_string(),
+        };
+
+        assert!(profile("", "").validate().is_ok());
+        assert!(profile("CSN", "123A").validate().is_ok());
+        assert!(profile("CSN", "").validate().is_err());
+        assert!(profile("", "1234").validate().is_err());
+        assert!(profile("CSN", "12345").validate().is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 配置文件路径（数据目录/VirtualATC/<file>）
+pub fn config_path(file: &str) -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("VirtualATC")
+        .join(file)
+}
+
+/// 读取 JSON 配置（文件不存在或无法解析时返回 None）
+pub fn load_json<T: DeserializeOwned>(file: &str) -> Option<T> {
+    fs::read_to_string(config_path(file))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// 保存 JSON 配置（目录不存在时自动创建）
+pub fn save_json<T: Serialize>(file: &str, value: &T) -> Result<(), Box<dyn Error>> {
+    let path = config_path(file);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use serde::Serialize;
+use super::msfs_supervisor::BridgeStatus;
+use super::simulator::FlightData;
+
+/// 连接健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionHealth {
+    Waiting,        // 已连接，尚未收到数据
+    Receiving,      // 正常接收数据
+    Stale,          // 超时未收到数据（正在尝试恢复）
+    Incompatible,   // 数据源协议不兼容（需要用户处理）
+    Disconnected,   // 未连接
+}
+
+/// 连接状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    pub sim_type: String,
+    pub health: ConnectionHealth,
+    pub last_data_age_secs: Option<f64>,   // 距离上次收到数据的秒数
+    pub resubscribe_count: u32,            // 自动重新订阅次数
+    pub error: Option<String>,             // 需要提示用户的错误
+    pub bridge: Option<BridgeStatus>,      // MSFS 桥接进程状态
+}
+
+impl ConnectionStatus {
+    pub fn disconnected() -> Self {
+        ConnectionStatus {
+            sim_type: String::new(),
+            health: ConnectionHealth::Disconnected,
+            last_data_age_secs: None,
+            resubscribe_count: 0,
+            error: None,
+            bridge: None,
+        }
+    }
+}
+
+/// 飞行数据源 - 所有模拟器连接的统一接口
+///
+/// `AppState` 只持有一个 `Box<dyn FlightDataSource>`，新增模拟器时只需实现此 trait，
+/// 无需修改各个 Tauri 命令。
+pub trait FlightDataSource: Send {
+    /// 模拟器类型标识（如 "xplane"、"msfs"）
+    fn sim_type(&self) -> &'static str;
+
+    /// 显示名称
+    fn display_name(&self) -> &'static str;
+
+    /// 获取最新的飞行数据（统一格式）
+    fn get_flight_data(&self) -> Result<FlightData, Box<dyn Error>>;
+
+    /// 连接健康状态（默认认为数据源始终可用）
+    fn status(&self) -> ConnectionStatus {
+        ConnectionStatus {
+            sim_type: self.sim_type().to_string(),
+            health: ConnectionHealth::Receiving,
+            last_data_age_secs: None,
+            resubscribe_count: 0,
+            error: None,
+            bridge: None,
+        }
+    }
+
+    /// 断开连接并释放资源
+    ///
+    /// 返回时必须已停止后台线程并关闭套接字，调用方可立即在同一端口上重新连接。
+    fn disconnect(&mut self) {}
+}
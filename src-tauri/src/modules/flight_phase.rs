@@ -1,9 +1,15 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
+use super::aircraft::AircraftCategory;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FlightPhase {
     PreFlight,      // 飞行前（停机坪）
+    Pushback,       // 推出
     Taxi,           // 滑行
+    LineUp,         // 进跑道、起飞滑跑
     Takeoff,        // 起飞
     Climb,          // 爬升
     Cruise,         // 巡航
@@ -11,13 +17,17 @@ pub enum FlightPhase {
     Approach,       // 进近
     Landing,        // 着陆
     GoAround,       // 复飞
+    TaxiIn,         // 落地后滑行
+    Parked,         // 停机
 }
 
 impl FlightPhase {
     pub fn as_str(&self) -> &str {
         match self {
             FlightPhase::PreFlight => "pre_flight",
+            FlightPhase::Pushback => "pushback",
             FlightPhase::Taxi => "taxi",
+            FlightPhase::LineUp => "line_up",
             FlightPhase::Takeoff => "takeoff",
             FlightPhase::Climb => "climb",
             FlightPhase::Cruise => "cruise",
@@ -25,13 +35,17 @@ impl FlightPhase {
             FlightPhase::Approach => "approach",
             FlightPhase::Landing => "landing",
             FlightPhase::GoAround => "go_around",
+            FlightPhase::TaxiIn => "taxi_in",
+            FlightPhase::Parked => "parked",
         }
     }
     
     pub fn display_name(&self) -> &str {
         match self {
             FlightPhase::PreFlight => "飞行前",
+            FlightPhase::Pushback => "推出",
             FlightPhase::Taxi => "滑行",
+            FlightPhase::LineUp => "进跑道",
             FlightPhase::Takeoff => "起飞",
             FlightPhase::Climb => "爬升",
             FlightPhase::Cruise => "巡航",
@@ -39,8 +53,45 @@ impl FlightPhase {
             FlightPhase::Approach => "进近",
             FlightPhase::Landing => "着陆",
             FlightPhase::GoAround => "复飞",
+            FlightPhase::TaxiIn => "落地滑行",
+            FlightPhase::Parked => "停机",
         }
     }
+    
+    pub fn is_on_ground(&self) -> bool {
+        matches!(
+            self,
+            FlightPhase::PreFlight | FlightPhase::Pushback | FlightPhase::Taxi | FlightPhase::LineUp
+                | FlightPhase::TaxiIn | FlightPhase::Parked
+        )
+    }
+    
+    /// 合法的下一阶段
+    ///
+    /// 地面阶段在检测到离地时都可以直接进入起飞（采样间隔较大时可能错过滑跑过程）。
+    pub fn next_phases(&self) -> &'static [FlightPhase] {
+        use FlightPhase::*;
+        
+        match self {
+            PreFlight => &[Pushback, Taxi, Takeoff],
+            Pushback => &[Taxi, Takeoff],
+            Taxi => &[LineUp, Takeoff],
+            LineUp => &[Taxi, Takeoff],
+            Takeoff => &[Climb],
+            Climb => &[Cruise, Descent, Approach],
+            Cruise => &[Climb, Descent],
+            Descent => &[Cruise, Climb, Approach],
+            Approach => &[Landing, GoAround],
+            Landing => &[TaxiIn, GoAround],
+            GoAround => &[Climb, Descent, Approach],
+            TaxiIn => &[Parked, LineUp, Takeoff],
+            Parked => &[Pushback, Taxi, Takeoff],
+        }
+    }
+    
+    pub fn can_transition_to(&self, next: FlightPhase) -> bool {
+        self.next_phases().contains(&next)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,13 +101,201 @@ pub struct FlightData {
     pub heading: f64,            // 度
     pub vertical_speed: f64,     // 英尺/分钟
     pub on_ground: bool,         // 是否在地面
+    pub altitude_agl: Option<f64>,  // 模拟器提供的离地高度（英尺），未知时为 None
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl From<&super::simulator::FlightData> for FlightData {
+    fn from(data: &super::simulator::FlightData) -> Self {
+        FlightData {
+            altitude: data.altitude,
+            speed: data.speed,
+            heading: data.heading,
+            vertical_speed: data.vertical_speed,
+            on_ground: data.on_ground,
+            // 部分数据源（如回放、只输出 DATA 的 X-Plane）没有离地高度，空中为 0 视为未知
+            altitude_agl: (data.on_ground || data.altitude_agl > 0.0).then_some(data.altitude_agl),
+            latitude: data.latitude,
+            longitude: data.longitude,
+        }
+    }
+}
+
+/// 平滑窗口：垂直速度和速度取最近若干个样本的平均值
+const SMOOTHING_SAMPLES: usize = 5;
+
+/// 两个样本间隔超过该时间（重新连接、回放跳转）时重新判断阶段
+const BOOTSTRAP_GAP: Duration = Duration::from_secs(30);
+
+/// 距巡航高度该范围内改平视为巡航（英尺）
+const CRUISE_BAND: f64 = 1000.0;
+
+/// 阶段切换阈值（按航空器类别）。进入某阶段和离开该阶段的条件不同（例如喷气机爬升需要
+/// +500 英尺/分钟，改平到 ±200 以内才进入巡航），在阈值附近的抖动不会造成来回切换。
+/// 高度阈值均为相对机场标高的高度（见 `FlightPhaseDetector::height_above_field`）。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseThresholds {
+    pub pushback_speed: f64,     // 节
+    pub taxi_speed: f64,
+    pub roll_speed: f64,         // 超过该速度视为在跑道上滑跑
+    pub reject_speed: f64,       // 滑跑中减速到该速度以下视为中断起飞
+    pub runway_speed: f64,       // 地面速度超过该值只可能在起飞或着陆滑跑
+    pub climb_vs: f64,           // 英尺/分钟
+    pub descent_vs: f64,
+    pub level_vs: f64,           // 垂直速度绝对值
+    pub approach_vs: f64,
+    pub go_around_vs: f64,
+    pub approach_height: f64,    // 英尺（相对机场）
+    pub landing_height: f64,
+    pub initial_climb: f64,      // 离地高度超过该值后进入爬升
+    pub cruise_min_height: f64,  // 低于该高度的改平不视为巡航
+}
+
+impl PhaseThresholds {
+    pub fn for_category(category: AircraftCategory) -> Self {
+        match category {
+            AircraftCategory::Jet | AircraftCategory::Heavy => PhaseThresholds {
+                pushback_speed: 1.0,
+                taxi_speed: 5.0,
+                roll_speed: 30.0,
+                reject_speed: 20.0,
+                runway_speed: 60.0,
+                climb_vs: 500.0,
+                descent_vs: -500.0,
+                level_vs: 200.0,
+                approach_vs: -100.0,
+                go_around_vs: 1000.0,
+                approach_height: 5000.0,
+                landing_height: 500.0,
+                initial_climb: 1000.0,
+                cruise_min_height: 3000.0,
+            },
+            AircraftCategory::Turboprop => PhaseThresholds {
+                pushback_speed: 1.0,
+                taxi_speed: 5.0,
+                roll_speed: 30.0,
+                reject_speed: 20.0,
+                runway_speed: 50.0,
+                climb_vs: 400.0,
+                descent_vs: -400.0,
+                level_vs: 150.0,
+                approach_vs: -100.0,
+                go_around_vs: 700.0,
+                approach_height: 4000.0,
+                landing_height: 400.0,
+                initial_climb: 800.0,
+                cruise_min_height: 2500.0,
+            },
+            // 轻型飞机滑行慢、爬升率低，起落航线高度约 1000 英尺
+            AircraftCategory::LightPiston => PhaseThresholds {
+                pushback_speed: 1.0,
+                taxi_speed: 3.0,
+                roll_speed: 25.0,
+                reject_speed: 15.0,
+                runway_speed: 40.0,
+                climb_vs: 300.0,
+                descent_vs: -300.0,
+                level_vs: 100.0,
+                approach_vs: -100.0,
+                go_around_vs: 400.0,
+                approach_height: 2000.0,
+                landing_height: 300.0,
+                initial_climb: 500.0,
+                cruise_min_height: 1500.0,
+            },
+            // 直升机没有起飞滑跑，离地即起飞
+            AircraftCategory::Rotorcraft => PhaseThresholds {
+                pushback_speed: 1.0,
+                taxi_speed: 3.0,
+                roll_speed: f64::INFINITY,
+                reject_speed: 0.0,
+                runway_speed: f64::INFINITY,
+                climb_vs: 300.0,
+                descent_vs: -300.0,
+                level_vs: 100.0,
+                approach_vs: -100.0,
+                go_around_vs: 500.0,
+                approach_height: 1000.0,
+                landing_height: 100.0,
+                initial_climb: 300.0,
+                cruise_min_height: 500.0,
+            },
+        }
+    }
 }
 
+impl Default for PhaseThresholds {
+    fn default() -> Self {
+        PhaseThresholds::for_category(AircraftCategory::Jet)
+    }
+}
+
+/// 新阶段需要持续的最短时间，之后才会切换
+fn min_dwell(phase: FlightPhase) -> Duration {
+    match phase {
+        FlightPhase::LineUp | FlightPhase::Takeoff | FlightPhase::Landing | FlightPhase::GoAround => Duration::from_secs(2),
+        FlightPhase::PreFlight | FlightPhase::Pushback | FlightPhase::Taxi | FlightPhase::TaxiIn => Duration::from_secs(3),
+        FlightPhase::Climb | FlightPhase::Cruise | FlightPhase::Descent | FlightPhase::Approach => Duration::from_secs(10),
+        FlightPhase::Parked => Duration::from_secs(60),
+    }
+}
+
+/// 保留的阶段历史条数
+const PHASE_HISTORY: usize = 64;
+
+/// 阶段切换事件（以 `phase-changed` 事件发送给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseChange {
+    pub from: FlightPhase,
+    pub to: FlightPhase,
+    pub display_name: String,    // 新阶段名称
+    pub timestamp: u64,          // Unix 时间（秒）
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+/// 阶段历史记录（用于飞行总结）
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseRecord {
+    pub phase: FlightPhase,
+    pub display_name: String,
+    pub started_at: u64,         // Unix 时间（秒）
+    pub duration_secs: u64,      // 当前阶段为截至最近一个样本的时长
+    pub latitude: f64,           // 进入该阶段时的位置
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+type PhaseListener = Box<dyn Fn(&PhaseChange) + Send>;
+
+struct PhaseEntry {
+    phase: FlightPhase,
+    since: Instant,
+    started_at: u64,
+    latitude: f64,
+    longitude: f64,
+    altitude: f64,
+}
+
+/// 飞行阶段状态机 - 只允许按 `FlightPhase::next_phases` 切换
 pub struct FlightPhaseDetector {
     current_phase: FlightPhase,
     previous_altitude: f64,
     previous_speed: f64,
-    phase_start_time: std::time::Instant,
+    phase_start_time: Instant,
+    samples: VecDeque<FlightData>,
+    pending: Option<(FlightPhase, Instant)>,    // 候选阶段及其首次出现时间
+    last_sample: Option<Instant>,
+    ground_altitude: Option<f64>,               // 最近一次在地面时的高度
+    top_altitude: Option<f64>,                  // 本次飞行到达的最高高度
+    field_elevation: Option<f64>,               // 附近机场标高（英尺）
+    cruise_altitude: Option<f64>,               // 计划巡航高度（英尺）
+    thresholds: PhaseThresholds,
+    history: VecDeque<PhaseEntry>,
+    clock_origin: Option<(Instant, SystemTime)>,
+    listener: Option<PhaseListener>,
 }
 
 impl FlightPhaseDetector {
@@ -65,17 +304,108 @@ impl FlightPhaseDetector {
             current_phase: FlightPhase::PreFlight,
             previous_altitude: 0.0,
             previous_speed: 0.0,
-            phase_start_time: std::time::Instant::now(),
+            phase_start_time: Instant::now(),
+            samples: VecDeque::with_capacity(SMOOTHING_SAMPLES),
+            pending: None,
+            last_sample: None,
+            ground_altitude: None,
+            top_altitude: None,
+            field_elevation: None,
+            cruise_altitude: None,
+            thresholds: PhaseThresholds::default(),
+            history: VecDeque::with_capacity(PHASE_HISTORY),
+            clock_origin: None,
+            listener: None,
         }
     }
     
+    /// 重新开始（切换模拟器连接时调用），下一个样本将重新判断阶段
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.pending = None;
+        self.last_sample = None;
+        self.ground_altitude = None;
+        self.top_altitude = None;
+        self.field_elevation = None;
+        self.history.clear();
+        self.clock_origin = None;
+    }
+    
+    /// 设置阶段切换回调（例如向前端发送事件）
+    pub fn set_listener<F: Fn(&PhaseChange) + Send + 'static>(&mut self, listener: F) {
+        self.listener = Some(Box::new(listener));
+    }
+    
+    /// 设置附近机场的标高（英尺），离开机场范围后设为 None
+    pub fn set_field_elevation(&mut self, elevation: Option<f64>) {
+        self.field_elevation = elevation;
+    }
+    
+    /// 设置计划巡航高度（英尺）。未设置时以本次飞行到达的最高高度为准
+    pub fn set_cruise_altitude(&mut self, altitude: Option<f64>) {
+        self.cruise_altitude = altitude;
+    }
+    
+    pub fn get_cruise_altitude(&self) -> Option<f64> {
+        self.cruise_altitude
+    }
+    
+    /// 按航空器类别设置阶段判断阈值
+    pub fn set_category(&mut self, category: AircraftCategory) {
+        self.thresholds = PhaseThresholds::for_category(category);
+    }
+    
     pub fn update(&mut self, data: &FlightData) -> FlightPhase {
-        let new_phase = self.detect_phase(data);
+        self.update_at(data, Instant::now())
+    }
+    
+    /// 使用指定的采样时间更新（回放和测试使用）
+    pub fn update_at(&mut self, data: &FlightData, now: Instant) -> FlightPhase {
+        let bootstrap = match self.last_sample {
+            Some(last) => now.saturating_duration_since(last) > BOOTSTRAP_GAP,
+            None => true,
+        };
+        self.last_sample = Some(now);
+        
+        if bootstrap {
+            self.samples.clear();
+            self.pending = None;
+        }
+        if self.samples.len() == SMOOTHING_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(data.clone());
         
-        if new_phase != self.current_phase {
-            println!("Flight phase changed: {:?} -> {:?}", self.current_phase, new_phase);
-            self.current_phase = new_phase;
-            self.phase_start_time = std::time::Instant::now();
+        if data.on_ground {
+            self.ground_altitude = Some(data.altitude);
+            self.top_altitude = None;
+        } else {
+            self.top_altitude = Some(self.top_altitude.map_or(data.altitude, |top| top.max(data.altitude)));
+        }
+        
+        let smoothed = self.smoothed();
+        
+        if bootstrap {
+            // 首个样本（例如在空中连接模拟器）直接按当前状态确定阶段
+            let phase = self.bootstrap_phase(&smoothed);
+            self.commit(phase, now, data);
+        } else {
+            match self.next_phase(&smoothed) {
+                None => self.pending = None,
+                Some(next) => {
+                    let since = match self.pending {
+                        Some((phase, since)) if phase == next => since,
+                        _ => {
+                            self.pending = Some((next, now));
+                            now
+                        }
+                    };
+                    
+                    if now.saturating_duration_since(since) >= min_dwell(next) {
+                        self.commit(next, since, data);
+                    }
+                }
+            }
         }
         
         self.previous_altitude = data.altitude;
@@ -88,63 +418,197 @@ impl FlightPhaseDetector {
         self.current_phase
     }
     
-    pub fn get_phase_duration(&self) -> std::time::Duration {
+    pub fn get_phase_duration(&self) -> Duration {
         self.phase_start_time.elapsed()
     }
     
-    fn detect_phase(&self, data: &FlightData) -> FlightPhase {
-        let alt = data.altitude;
-        let spd = data.speed;
-        let vs = data.vertical_speed;
-        let on_ground = data.on_ground;
+    /// 阶段历史（最早的在前），用于飞行总结
+    pub fn get_history(&self) -> Vec<PhaseRecord> {
+        let last_sample = self.last_sample.unwrap_or_else(Instant::now);
         
-        // 飞行前：在地面，速度很低
-        if on_ground && spd < 5.0 {
-            return FlightPhase::PreFlight;
-        }
+        self.history.iter().enumerate()
+            .map(|(i, entry)| {
+                let end = self.history.get(i + 1).map_or(last_sample, |next| next.since);
+                PhaseRecord {
+                    phase: entry.phase,
+                    display_name: entry.phase.display_name().to_string(),
+                    started_at: entry.started_at,
+                    duration_secs: end.saturating_duration_since(entry.since).as_secs(),
+                    latitude: entry.latitude,
+                    longitude: entry.longitude,
+                    altitude: entry.altitude,
+                }
+            })
+            .collect()
+    }
+    
+    /// 切换阶段，阶段开始时间记为候选阶段首次出现的时间
+    fn commit(&mut self, phase: FlightPhase, since: Instant, data: &FlightData) {
+        let previous = self.current_phase;
+        let changed = phase != previous;
+        self.pending = None;
         
-        // 滑行：在地面，速度较低
-        if on_ground && spd >= 5.0 && spd < 60.0 {
-            return FlightPhase::Taxi;
+        if changed {
+            println!("Flight phase changed: {:?} -> {:?}", previous, phase);
+            self.current_phase = phase;
+            self.phase_start_time = since;
+        } else if !self.history.is_empty() {
+            return;
         }
         
-        // 起飞：在地面或低空，速度快，正在加速
-        if (on_ground || alt < 500.0) && spd >= 60.0 && vs > 500.0 {
-            return FlightPhase::Takeoff;
-        }
+        // 以首个样本对应的系统时间为基准换算（回放和测试中的采样时间不是当前时间）
+        let (origin, origin_time) = *self.clock_origin.get_or_insert_with(|| (since, SystemTime::now()));
+        let started_at = (origin_time + since.saturating_duration_since(origin))
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
         
-        // 爬升：低于巡航高度，垂直速度为正
-        if !on_ground && alt < 20000.0 && vs > 300.0 {
-            return FlightPhase::Climb;
+        if self.history.len() == PHASE_HISTORY {
+            self.history.pop_front();
         }
+        self.history.push_back(PhaseEntry {
+            phase,
+            since,
+            started_at,
+            latitude: data.latitude,
+            longitude: data.longitude,
+            altitude: data.altitude,
+        });
         
-        // 巡航：高空，垂直速度接近零
-        if !on_ground && alt >= 20000.0 && vs.abs() < 500.0 {
-            return FlightPhase::Cruise;
+        if changed {
+            if let Some(listener) = &self.listener {
+                listener(&PhaseChange {
+                    from: previous,
+                    to: phase,
+                    display_name: phase.display_name().to_string(),
+                    timestamp: started_at,
+                    latitude: data.latitude,
+                    longitude: data.longitude,
+                    altitude: data.altitude,
+                });
+            }
         }
+    }
+    
+    /// 平滑后的数据：速度和垂直速度取窗口平均值，其余取最新样本
+    fn smoothed(&self) -> FlightData {
+        let latest = self.samples.back().cloned().expect("at least one sample");
+        let count = self.samples.len() as f64;
         
-        // 下降：从高空下降，垂直速度为负
-        if !on_ground && alt > 5000.0 && vs < -300.0 {
-            return FlightPhase::Descent;
+        FlightData {
+            speed: self.samples.iter().map(|s| s.speed).sum::<f64>() / count,
+            vertical_speed: self.samples.iter().map(|s| s.vertical_speed).sum::<f64>() / count,
+            ..latest
         }
+    }
+    
+    /// 相对机场的高度。优先使用附近机场标高（起飞和进近以跑道为准），
+    /// 其次使用模拟器离地高度，都没有时使用相对起飞机场的高度
+    fn height_above_field(&self, data: &FlightData) -> f64 {
+        if let Some(field) = self.field_elevation {
+            return data.altitude - field;
+        }
+        if let Some(agl) = data.altitude_agl {
+            return agl;
+        }
+        data.altitude - self.ground_altitude.unwrap_or(0.0)
+    }
+    
+    /// 没有历史数据时，根据单个样本判断阶段
+    fn bootstrap_phase(&self, data: &FlightData) -> FlightPhase {
+        let t = self.thresholds;
+        let height = self.height_above_field(data);
+        let spd = data.speed;
+        let vs = data.vertical_speed;
         
-        // 进近：低空，下降中
-        if !on_ground && alt >= 500.0 && alt <= 5000.0 && vs < -100.0 {
-            return FlightPhase::Approach;
+        if data.on_ground {
+            return if spd < t.taxi_speed {
+                FlightPhase::PreFlight
+            } else if spd < t.runway_speed {
+                FlightPhase::Taxi
+            } else {
+                FlightPhase::Landing
+            };
         }
         
-        // 着陆：极低空或刚接地
-        if (on_ground && spd > 60.0) || (!on_ground && alt < 500.0 && vs < -100.0) {
-            return FlightPhase::Landing;
+        if height < t.landing_height && vs > t.climb_vs {
+            FlightPhase::Takeoff
+        } else if vs > t.climb_vs {
+            FlightPhase::Climb
+        } else if height <= t.approach_height && vs < t.approach_vs {
+            FlightPhase::Approach
+        } else if vs < t.descent_vs {
+            FlightPhase::Descent
+        } else {
+            FlightPhase::Cruise
         }
+    }
+    
+    /// 从当前阶段出发，判断是否满足某个合法下一阶段的条件
+    fn next_phase(&self, data: &FlightData) -> Option<FlightPhase> {
+        let t = self.thresholds;
+        let alt = data.altitude;
+        let height = self.height_above_field(data);
+        let spd = data.speed;
+        let vs = data.vertical_speed;
+        let on_ground = data.on_ground;
         
-        // 复飞：低空突然爬升
-        if !on_ground && alt < 3000.0 && vs > 1000.0 && self.current_phase == FlightPhase::Approach {
-            return FlightPhase::GoAround;
+        // 地面阶段离地即进入起飞
+        if self.current_phase.is_on_ground() && !on_ground {
+            return Some(FlightPhase::Takeoff);
         }
         
-        // 默认保持当前阶段
-        self.current_phase
+        let next = match self.current_phase {
+            FlightPhase::PreFlight | FlightPhase::Parked => {
+                if spd >= t.taxi_speed {
+                    FlightPhase::Taxi
+                } else if spd >= t.pushback_speed {
+                    FlightPhase::Pushback
+                } else {
+                    return None;
+                }
+            }
+            FlightPhase::Pushback if spd >= t.taxi_speed => FlightPhase::Taxi,
+            FlightPhase::Taxi | FlightPhase::TaxiIn if spd >= t.roll_speed => FlightPhase::LineUp,
+            FlightPhase::TaxiIn if spd < t.pushback_speed => FlightPhase::Parked,
+            // 中断起飞
+            FlightPhase::LineUp if spd < t.reject_speed => FlightPhase::Taxi,
+            FlightPhase::Takeoff if height >= t.initial_climb => FlightPhase::Climb,
+            FlightPhase::Climb | FlightPhase::Cruise | FlightPhase::Descent | FlightPhase::GoAround => {
+                return self.airborne_phase(alt, height, vs);
+            }
+            FlightPhase::Approach | FlightPhase::Landing if !on_ground && vs > t.go_around_vs => FlightPhase::GoAround,
+            FlightPhase::Approach if on_ground || (height < t.landing_height && vs < t.approach_vs) => FlightPhase::Landing,
+            FlightPhase::Landing if on_ground && spd < t.roll_speed => FlightPhase::TaxiIn,
+            _ => return None,
+        };
+        
+        Some(next).filter(|next| *next != self.current_phase && self.current_phase.can_transition_to(*next))
+    }
+    
+    /// 爬升、巡航、下降和复飞之间的切换，按顺序取第一个满足条件的合法阶段
+    fn airborne_phase(&self, alt: f64, height: f64, vs: f64) -> Option<FlightPhase> {
+        let t = self.thresholds;
+        let current = self.current_phase;
+        
+        // 巡航：在计划巡航高度（或本次飞行最高高度）附近改平
+        let cruise_reference = self.cruise_altitude.or(self.top_altitude).unwrap_or(alt);
+        let cruising = height >= t.cruise_min_height && alt >= cruise_reference - CRUISE_BAND && vs.abs() < t.level_vs;
+        
+        // 复飞后的低空爬升仍属于复飞；下降中继续下降即进入进近，其他阶段需要明显下降
+        let climbing = vs > t.climb_vs && !(current == FlightPhase::GoAround && height <= t.approach_height);
+        let approach_vs = if current == FlightPhase::Descent { t.approach_vs } else { t.descent_vs };
+        
+        let candidates = [
+            (climbing, FlightPhase::Climb),
+            (cruising, FlightPhase::Cruise),
+            (height <= t.approach_height && vs < approach_vs, FlightPhase::Approach),
+            (vs < t.descent_vs, FlightPhase::Descent),
+        ];
+        
+        candidates.iter()
+            .find(|(hit, phase)| *hit && *phase != current && current.can_transition_to(*phase))
+            .map(|(_, phase)| *phase)
     }
     
     /// 获取当前阶段的 ATC 提示词
@@ -164,12 +628,24 @@ impl FlightPhaseDetector {
                 - 放行许可\n\
                 - 滑行指令"
             }
+            FlightPhase::Pushback => {
+                "飞行员正在推出。你应该提供：\n\
+                - 推出许可确认\n\
+                - 推出方向\n\
+                - 开车许可"
+            }
             FlightPhase::Taxi => {
                 "飞行员正在滑行。你应该提供：\n\
                 - 滑行路线指引\n\
                 - 等待指令\n\
                 - 跑道穿越许可"
             }
+            FlightPhase::LineUp => {
+                "飞行员已进入跑道。你应该提供：\n\
+                - 进跑道等待指令\n\
+                - 起飞许可\n\
+                - 地面风"
+            }
             FlightPhase::Takeoff => {
                 "飞行员正在起飞。你应该提供：\n\
                 - 起飞许可\n\
@@ -212,6 +688,17 @@ impl FlightPhaseDetector {
                 - 爬升高度\n\
                 - 复飞航向"
             }
+            FlightPhase::TaxiIn => {
+                "飞行员已脱离跑道。你应该提供：\n\
+                - 地面频率切换\n\
+                - 滑行至停机位的路线\n\
+                - 停机位信息"
+            }
+            FlightPhase::Parked => {
+                "飞行员已到达停机位。你应该提供：\n\
+                - 到位确认\n\
+                - 关车许可"
+            }
         }.to_string()
     }
     
@@ -224,12 +711,24 @@ impl FlightPhaseDetector {
                 - Clearance delivery\n\
                 - Taxi instructions"
             }
+            FlightPhase::Pushback => {
+                "Pilot is pushing back. You should provide:\n\
+                - Pushback approval confirmation\n\
+                - Pushback direction\n\
+                - Engine start approval"
+            }
             FlightPhase::Taxi => {
                 "Pilot is taxiing. You should provide:\n\
                 - Taxi route guidance\n\
                 - Hold short instructions\n\
                 - Runway crossing clearance"
             }
+            FlightPhase::LineUp => {
+                "Pilot is on the runway. You should provide:\n\
+                - Line up and wait instructions\n\
+                - Takeoff clearance\n\
+                - Surface wind"
+            }
             FlightPhase::Takeoff => {
                 "Pilot is taking off. You should provide:\n\
                 - Takeoff clearance\n\
@@ -272,6 +771,17 @@ impl FlightPhaseDetector {
                 - Climb altitude\n\
                 - Go-around heading"
             }
+            FlightPhase::TaxiIn => {
+                "Pilot has vacated the runway. You should provide:\n\
+                - Ground frequency change\n\
+                - Taxi route to the gate\n\
+                - Parking stand information"
+            }
+            FlightPhase::Parked => {
+                "Pilot has arrived at the gate. You should provide:\n\
+                - Arrival confirmation\n\
+                - Engine shutdown approval"
+            }
         }.to_string()
     }
 }
@@ -289,6 +799,9 @@ mod tests {
             heading: 0.0,
             vertical_speed: 0.0,
             on_ground: true,
+            altitude_agl: None,
+            latitude: 0.0,
+            longitude: 0.0,
         };
         
         let phase = detector.update(&data);
@@ -304,6 +817,9 @@ mod tests {
             heading: 90.0,
             vertical_speed: 0.0,
             on_ground: true,
+            altitude_agl: None,
+            latitude: 0.0,
+            longitude: 0.0,
         };
         
         let phase = detector.update(&data);
@@ -319,6 +835,9 @@ mod tests {
             heading: 90.0,
             vertical_speed: 1500.0,
             on_ground: false,
+            altitude_agl: None,
+            latitude: 0.0,
+            longitude: 0.0,
         };
         
         let phase = detector.update(&data);
@@ -334,9 +853,417 @@ mod tests {
             heading: 270.0,
             vertical_speed: 0.0,
             on_ground: false,
+            altitude_agl: None,
+            latitude: 0.0,
+            longitude: 0.0,
         };
         
         let phase = detector.update(&data);
         assert_eq!(phase, FlightPhase::Cruise);
     }
+    
+    fn sample(altitude: f64, speed: f64, vertical_speed: f64) -> FlightData {
+        FlightData {
+            altitude,
+            speed,
+            heading: 90.0,
+            vertical_speed,
+            on_ground: false,
+            altitude_agl: None,
+            latitude: 0.0,
+            longitude: 0.0,
+        }
+    }
+    
+    /// 巡航中的颠簸：原始垂直速度在 ±650 英尺/分钟之间跳动
+    const TURBULENCE: [f64; 6] = [650.0, -520.0, 480.0, -610.0, 350.0, -450.0];
+    
+    #[test]
+    fn test_turbulence_does_not_flap_cruise() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        
+        assert_eq!(detector.update_at(&sample(35000.0, 450.0, 0.0), start), FlightPhase::Cruise);
+        
+        for i in 1..300u64 {
+            let vs = TURBULENCE[i as usize % TURBULENCE.len()];
+            let phase = detector.update_at(&sample(35000.0, 450.0, vs), start + Duration::from_secs(i));
+            assert_eq!(phase, FlightPhase::Cruise, "flapped at sample {} (vs {})", i, vs);
+        }
+    }
+    
+    #[test]
+    fn test_brief_level_off_keeps_climb() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        let noisy_climb = [1450.0, 1620.0, 1380.0, 1550.0];
+        
+        let mut altitude = 5000.0;
+        for i in 0..60u64 {
+            // 第 20-25 秒垂直速度在 ±300 附近摆动（例如短暂改平）
+            let vs = if (20..26).contains(&i) {
+                if i % 2 == 0 { 320.0 } else { -280.0 }
+            } else {
+                noisy_climb[i as usize % noisy_climb.len()]
+            };
+            altitude += vs / 60.0;
+            
+            let phase = detector.update_at(&sample(altitude, 280.0, vs), start + Duration::from_secs(i));
+            assert_eq!(phase, FlightPhase::Climb, "flapped at sample {} (vs {})", i, vs);
+        }
+    }
+    
+    #[test]
+    fn test_sustained_change_commits_after_dwell() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        detector.update_at(&sample(35000.0, 450.0, 0.0), start);
+        
+        // 开始下降：平滑窗口和最短持续时间都满足之前保持巡航
+        let mut phases = Vec::new();
+        for i in 1..=20u64 {
+            phases.push(detector.update_at(&sample(35000.0, 450.0, -1800.0), start + Duration::from_secs(i)));
+        }
+        
+        // 第 1 秒平均值已低于 -500，再持续 10 秒才切换
+        assert!(phases[..10].iter().all(|p| *p == FlightPhase::Cruise));
+        assert!(phases[10..].iter().all(|p| *p == FlightPhase::Descent));
+    }
+    
+    #[test]
+    fn test_interrupted_candidate_restarts_dwell() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        
+        let ground = |speed: f64| FlightData { on_ground: true, ..sample(0.0, speed, 0.0) };
+        assert_eq!(detector.update_at(&ground(0.0), at(0)), FlightPhase::PreFlight);
+        
+        // 短暂移动：平均速度超过 5 节的时间不足 3 秒
+        detector.update_at(&ground(12.0), at(1));
+        detector.update_at(&ground(12.0), at(2));
+        for i in 3..10 {
+            assert_eq!(detector.update_at(&ground(0.0), at(i)), FlightPhase::PreFlight);
+        }
+        
+        // 持续滑行后切换
+        let phases: Vec<FlightPhase> = (10..20).map(|i| detector.update_at(&ground(15.0), at(i))).collect();
+        assert_eq!(phases.last(), Some(&FlightPhase::Taxi));
+    }
+    
+    /// 按秒生成的飞行剖面
+    struct Profile {
+        samples: Vec<FlightData>,
+        altitude: f64,
+    }
+    
+    impl Profile {
+        fn new() -> Self {
+            Profile { samples: Vec::new(), altitude: 0.0 }
+        }
+        
+        fn segment(&mut self, secs: u32, speed: (f64, f64), vertical_speed: f64, on_ground: bool) -> &mut Self {
+            for i in 0..secs {
+                let t = i as f64 / secs as f64;
+                self.altitude = (self.altitude + vertical_speed / 60.0).max(0.0);
+                self.samples.push(FlightData {
+                    altitude: self.altitude,
+                    speed: speed.0 + (speed.1 - speed.0) * t,
+                    heading: 90.0,
+                    vertical_speed,
+                    on_ground,
+                    altitude_agl: None,
+                    latitude: 0.0,
+                    longitude: 0.0,
+                });
+            }
+            self
+        }
+        
+        /// 依次送入检测器，返回经过的阶段（去重）
+        fn run(&self, detector: &mut FlightPhaseDetector) -> Vec<FlightPhase> {
+            let start = Instant::now();
+            let mut phases: Vec<FlightPhase> = Vec::new();
+            
+            for (i, sample) in self.samples.iter().enumerate() {
+                let phase = detector.update_at(sample, start + Duration::from_secs(i as u64));
+                if phases.last() != Some(&phase) {
+                    phases.push(phase);
+                }
+            }
+            phases
+        }
+    }
+    
+    #[test]
+    fn test_full_flight_transitions() {
+        let mut profile = Profile::new();
+        profile
+            .segment(30, (0.0, 0.0), 0.0, true)             // 停机位
+            .segment(60, (3.0, 3.0), 0.0, true)             // 推出
+            .segment(30, (0.0, 0.0), 0.0, true)             // 开车
+            .segment(200, (15.0, 15.0), 0.0, true)          // 滑行
+            .segment(30, (15.0, 150.0), 0.0, true)          // 起飞滑跑
+            .segment(1000, (160.0, 280.0), 2100.0, false)   // 爬升至 35000 英尺
+            .segment(600, (450.0, 450.0), 0.0, false)       // 巡航
+            .segment(900, (300.0, 250.0), -2000.0, false)   // 下降至 5000 英尺
+            .segment(360, (220.0, 140.0), -800.0, false)    // 进近
+            .segment(20, (140.0, 40.0), 0.0, true)          // 接地减速
+            .segment(120, (15.0, 15.0), 0.0, true)          // 滑回
+            .segment(90, (0.0, 0.0), 0.0, true);            // 停机
+        
+        let phases = profile.run(&mut FlightPhaseDetector::new());
+        
+        assert_eq!(phases, vec![
+            FlightPhase::PreFlight,
+            FlightPhase::Pushback,
+            FlightPhase::Taxi,
+            FlightPhase::LineUp,
+            FlightPhase::Takeoff,
+            FlightPhase::Climb,
+            FlightPhase::Cruise,
+            FlightPhase::Descent,
+            FlightPhase::Approach,
+            FlightPhase::Landing,
+            FlightPhase::TaxiIn,
+            FlightPhase::Parked,
+        ]);
+        assert!(phases.windows(2).all(|pair| pair[0].can_transition_to(pair[1])));
+    }
+    
+    #[test]
+    fn test_go_around_is_reachable() {
+        let mut profile = Profile::new();
+        profile.altitude = 2000.0;
+        profile
+            .segment(60, (150.0, 140.0), -700.0, false)     // 进近
+            .segment(60, (140.0, 180.0), 2000.0, false)     // 复飞爬升至 3000 英尺左右
+            .segment(60, (180.0, 180.0), 0.0, false)        // 改平
+            .segment(60, (180.0, 150.0), -800.0, false);    // 再次进近
+        
+        let phases = profile.run(&mut FlightPhaseDetector::new());
+        
+        assert_eq!(phases, vec![FlightPhase::Approach, FlightPhase::GoAround, FlightPhase::Approach]);
+    }
+    
+    #[test]
+    fn test_rejected_takeoff_returns_to_taxi() {
+        let mut profile = Profile::new();
+        profile
+            .segment(10, (15.0, 15.0), 0.0, true)
+            .segment(20, (15.0, 90.0), 0.0, true)           // 加速
+            .segment(20, (90.0, 15.0), 0.0, true)           // 中断起飞
+            .segment(20, (15.0, 15.0), 0.0, true);          // 脱离跑道
+        
+        let phases = profile.run(&mut FlightPhaseDetector::new());
+        
+        assert_eq!(phases, vec![FlightPhase::Taxi, FlightPhase::LineUp, FlightPhase::Taxi]);
+    }
+    
+    #[test]
+    fn test_mid_flight_bootstrap() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        let at_gate = FlightData { on_ground: true, ..sample(0.0, 0.0, 0.0) };
+        assert_eq!(detector.update_at(&at_gate, start), FlightPhase::PreFlight);
+        
+        // 连续采样时，地面阶段离地只能进入起飞，不会跳到巡航
+        let cruise = sample(35000.0, 450.0, 0.0);
+        let phase = detector.update_at(&cruise, start + Duration::from_secs(1));
+        assert_eq!(phase, FlightPhase::PreFlight);
+        assert_eq!(detector.update_at(&cruise, start + Duration::from_secs(3)), FlightPhase::Takeoff);
+        
+        // 长时间中断后（例如飞行中重新连接）直接按当前状态判断
+        let phase = detector.update_at(&cruise, start + Duration::from_secs(600));
+        assert_eq!(phase, FlightPhase::Cruise);
+        
+        detector.reset();
+        let descending = sample(12000.0, 300.0, -1800.0);
+        assert_eq!(detector.update_at(&descending, start + Duration::from_secs(601)), FlightPhase::Descent);
+    }
+    
+    /// 兰州中川 ZLLL 标高 6388 英尺
+    const ZLLL_ELEVATION: f64 = 6388.0;
+    
+    #[test]
+    fn test_high_elevation_departure() {
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_field_elevation(Some(ZLLL_ELEVATION));
+        
+        // 离地 100 英尺时（海拔 6500 英尺）连接，应判断为起飞而不是爬升
+        let start = Instant::now();
+        assert_eq!(detector.update_at(&sample(6500.0, 150.0, 1800.0), start), FlightPhase::Takeoff);
+        
+        let mut profile = Profile::new();
+        profile.altitude = 6500.0;
+        profile.segment(60, (160.0, 200.0), 1800.0, false);
+        
+        let phases = profile.run(&mut detector);
+        assert_eq!(phases, vec![FlightPhase::Takeoff, FlightPhase::Climb]);
+    }
+    
+    #[test]
+    fn test_high_elevation_approach() {
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_field_elevation(Some(ZLLL_ELEVATION));
+        
+        // 海拔始终高于 5000 英尺，按机场标高判断进近和着陆
+        let mut profile = Profile::new();
+        profile.altitude = 16000.0;
+        profile
+            .segment(480, (250.0, 180.0), -1000.0, false)     // 下降至海拔 8000 英尺
+            .segment(150, (160.0, 140.0), -600.0, false);     // 进近至离场高约 100 英尺
+        
+        let phases = profile.run(&mut detector);
+        assert_eq!(phases, vec![FlightPhase::Descent, FlightPhase::Approach, FlightPhase::Landing]);
+    }
+    
+    #[test]
+    fn test_sim_agl_without_field_elevation() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        let approach = |altitude_agl: f64| FlightData {
+            altitude_agl: Some(altitude_agl),
+            ..sample(altitude_agl + ZLLL_ELEVATION, 140.0, -700.0)
+        };
+        
+        assert_eq!(detector.update_at(&approach(2000.0), start), FlightPhase::Approach);
+        
+        let phases: Vec<FlightPhase> = (1..=5)
+            .map(|i| detector.update_at(&approach(400.0 - i as f64 * 10.0), start + Duration::from_secs(i)))
+            .collect();
+        assert_eq!(phases.last(), Some(&FlightPhase::Landing));
+    }
+    
+    #[test]
+    fn test_cruise_relative_to_filed_level() {
+        // 计划巡航高度 12000 英尺：改平后进入巡航
+        let mut profile = Profile::new();
+        profile
+            .segment(5, (0.0, 0.0), 0.0, true)
+            .segment(400, (160.0, 250.0), 1800.0, false)      // 爬升至 12000 英尺
+            .segment(120, (250.0, 250.0), 0.0, false);
+        
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_cruise_altitude(Some(12000.0));
+        assert_eq!(profile.run(&mut detector).last(), Some(&FlightPhase::Cruise));
+        
+        // 计划巡航高度 35000 英尺：在 12000 英尺临时改平仍为爬升
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_cruise_altitude(Some(35000.0));
+        assert_eq!(profile.run(&mut detector).last(), Some(&FlightPhase::Climb));
+    }
+    
+    #[test]
+    fn test_descent_level_off_is_not_cruise() {
+        // 未设置计划巡航高度时，以最高高度为准；下降途中改平仍为下降
+        let mut profile = Profile::new();
+        profile.altitude = 30000.0;
+        profile
+            .segment(60, (450.0, 450.0), 0.0, false)
+            .segment(600, (300.0, 280.0), -2000.0, false)     // 下降至 10000 英尺
+            .segment(120, (250.0, 250.0), 0.0, false);
+        
+        let phases = profile.run(&mut FlightPhaseDetector::new());
+        assert_eq!(phases, vec![FlightPhase::Cruise, FlightPhase::Descent]);
+    }
+    
+    #[test]
+    fn test_phase_change_events_and_history() {
+        use std::sync::{Arc, Mutex};
+        
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut detector = FlightPhaseDetector::new();
+        {
+            let events = events.clone();
+            detector.set_listener(move |change| events.lock().unwrap().push(change.clone()));
+        }
+        
+        let mut profile = Profile::new();
+        profile
+            .segment(10, (0.0, 0.0), 0.0, true)
+            .segment(60, (15.0, 15.0), 0.0, true);
+        profile.run(&mut detector);
+        
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].from, events[0].to), (FlightPhase::PreFlight, FlightPhase::Taxi));
+        assert_eq!(events[0].display_name, "滑行");
+        
+        // 滑行从平滑速度超过 5 节（第 11 秒）开始计时，持续到最后一个样本（第 69 秒）
+        let history = detector.get_history();
+        let summary: Vec<(FlightPhase, u64)> = history.iter().map(|r| (r.phase, r.duration_secs)).collect();
+        assert_eq!(summary, vec![(FlightPhase::PreFlight, 11), (FlightPhase::Taxi, 58)]);
+        assert_eq!(history[1].started_at - history[0].started_at, 11);
+    }
+    
+    #[test]
+    fn test_history_is_bounded() {
+        let mut detector = FlightPhaseDetector::new();
+        let start = Instant::now();
+        let at_gate = FlightData { on_ground: true, ..sample(0.0, 0.0, 0.0) };
+        let cruise = sample(35000.0, 450.0, 0.0);
+        
+        // 每次间隔超过 30 秒都会重新判断阶段
+        for i in 0..100u64 {
+            let data = if i % 2 == 0 { &at_gate } else { &cruise };
+            detector.update_at(data, start + Duration::from_secs(i * 60));
+        }
+        
+        let history = detector.get_history();
+        assert_eq!(history.len(), PHASE_HISTORY);
+        assert_eq!(history.last().map(|r| r.phase), Some(FlightPhase::Cruise));
+    }
+    
+    #[test]
+    fn test_light_aircraft_circuit() {
+        // C172 起落航线：滑跑 55 节离地，700 英尺/分钟爬升至 1000 英尺改平，500 英尺/分钟下降
+        let mut profile = Profile::new();
+        profile
+            .segment(10, (0.0, 0.0), 0.0, true)
+            .segment(60, (8.0, 8.0), 0.0, true)             // 滑行
+            .segment(20, (10.0, 55.0), 0.0, true)           // 起飞滑跑
+            .segment(90, (65.0, 75.0), 700.0, false)        // 爬升
+            .segment(120, (90.0, 90.0), 0.0, false)         // 三边
+            .segment(120, (70.0, 65.0), -500.0, false)      // 进近
+            .segment(20, (55.0, 10.0), 0.0, true);          // 接地减速
+        
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_category(AircraftCategory::LightPiston);
+        let phases = profile.run(&mut detector);
+        
+        assert_eq!(phases, vec![
+            FlightPhase::PreFlight,
+            FlightPhase::Taxi,
+            FlightPhase::LineUp,
+            FlightPhase::Takeoff,
+            FlightPhase::Climb,
+            FlightPhase::Approach,
+            FlightPhase::Landing,
+            FlightPhase::TaxiIn,
+        ]);
+        
+        // 按喷气客机的阈值，500 英尺/分钟的下降率不足以判断为进近
+        let phases = profile.run(&mut FlightPhaseDetector::new());
+        assert!(!phases.contains(&FlightPhase::Approach));
+    }
+    
+    #[test]
+    fn test_rotorcraft_lifts_off_without_runway() {
+        let mut profile = Profile::new();
+        profile
+            .segment(10, (0.0, 0.0), 0.0, true)
+            .segment(60, (20.0, 60.0), 600.0, false)        // 垂直起飞后加速爬升
+            .segment(60, (100.0, 100.0), 0.0, false);
+        
+        let mut detector = FlightPhaseDetector::new();
+        detector.set_category(AircraftCategory::Rotorcraft);
+        
+        assert_eq!(profile.run(&mut detector), vec![
+            FlightPhase::PreFlight,
+            FlightPhase::Takeoff,
+            FlightPhase::Climb,
+            FlightPhase::Cruise,
+        ]);
+    }
 }
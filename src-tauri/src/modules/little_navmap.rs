@@ -1,88 +1,551 @@
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use super::config_store;
+use super::atc_database::{ATCDatabase, Airport, Frequencies, Gate, Runway};
+use super::taxi_network::{normalize_runway_end, TaxiNetwork, TaxiNode, TaxiParking, TaxiRunwayEnd, TaxiSegment};
+
+/// 英尺换算为米
+const FT_TO_M: f64 = 0.3048;
+
+/// Little Navmap 数据库文件名（同类型按优先级排序）
+const DATABASE_FILES: [(DatabaseType, &str); 5] = [
+    (DatabaseType::Navigraph, "little_navmap_navigraph.sqlite"),
+    (DatabaseType::XPlane, "little_navmap_xp12.sqlite"),
+    (DatabaseType::XPlane, "little_navmap_xp11.sqlite"),
+    (DatabaseType::MSFS, "little_navmap_msfs24.sqlite"),
+    (DatabaseType::MSFS, "little_navmap_msfs.sqlite"),
+];
 
 /// Little Navmap 数据库连接器
 pub struct LittleNavmapDB {
     conn: Connection,
     db_type: DatabaseType,
+    path: PathBuf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DatabaseType {
     Navigraph,
     XPlane,
     MSFS,
 }
 
+impl DatabaseType {
+    /// 与模拟器对应的数据库类型（回放等没有对应数据库）
+    pub fn for_simulator(sim_type: &str) -> Option<Self> {
+        match sim_type {
+            "xplane" => Some(DatabaseType::XPlane),
+            "msfs" => Some(DatabaseType::MSFS),
+            _ => None,
+        }
+    }
+    
+    /// 按 Little Navmap 的文件命名推断类型
+    pub fn from_file_name(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.contains("navigraph") {
+            Some(DatabaseType::Navigraph)
+        } else if name.contains("_xp") {
+            Some(DatabaseType::XPlane)
+        } else if name.contains("_msfs") {
+            Some(DatabaseType::MSFS)
+        } else {
+            None
+        }
+    }
+}
+
+/// Little Navmap 数据库选择（保存在 VirtualATC/little_navmap.json）
+///
+/// 都为空时自动选择：优先与当前模拟器对应的数据库，其次 Navigraph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NavmapConfig {
+    /// 指定数据库文件，优先于自动检测
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 指定数据库类型（指定文件时用于标注类型）
+    #[serde(default)]
+    pub db_type: Option<DatabaseType>,
+}
+
+impl NavmapConfig {
+    /// 读取上次保存的配置（不存在或无效时自动选择）
+    pub fn load() -> Self {
+        config_store::load_json::<NavmapConfig>("little_navmap.json")
+            .filter(|config| config.validate().is_ok())
+            .unwrap_or_default()
+    }
+    
+    /// 保存配置
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        config_store::save_json("little_navmap.json", self)
+    }
+    
+    /// 校验指定的数据库文件
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(path) = &self.path {
+            if path.trim().is_empty() {
+                return Err("数据库路径不能为空".to_string());
+            }
+            if !Path::new(path.trim()).is_file() {
+                return Err(format!("数据库文件不存在：{}", path.trim()));
+            }
+        }
+        Ok(())
+    }
+    
+    /// 是否自动选择数据库
+    pub fn is_auto(&self) -> bool {
+        self.path.is_none() && self.db_type.is_none()
+    }
+    
+    /// 指定的数据库文件及其类型（未指定类型时按文件名推断）
+    fn override_path(&self) -> Option<(DatabaseType, PathBuf)> {
+        let path = PathBuf::from(self.path.as_ref()?.trim());
+        let db_type = self.db_type
+            .or_else(|| DatabaseType::from_file_name(&path))
+            .unwrap_or(DatabaseType::Navigraph);
+        Some((db_type, path))
+    }
+}
+
+/// 数据库信息（metadata 表）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseInfo {
+    pub db_type: DatabaseType,
+    pub path: String,
+    pub airac_cycle: Option<String>,
+    pub valid_through: Option<String>,
+    pub data_source: Option<String>,
+    pub compiled: Option<String>,   // 编译数据库的时间
+    pub active: bool,
+}
+
 impl LittleNavmapDB {
-    /// 自动检测并连接 Little Navmap 数据库
+    /// 按保存的配置连接 Little Navmap 数据库
     pub fn new() -> Result<Self> {
-        Self::auto_detect()
+        Self::select(&NavmapConfig::load(), None)
     }
     
     /// 自动检测并连接 Little Navmap 数据库
     pub fn auto_detect() -> Result<Self> {
-        let db_paths = Self::get_database_paths();
-        
-        // 优先使用 Navigraph 数据库
-        for (db_type, path) in db_paths {
-            if path.exists() {
-                println!("检测到 Little Navmap 数据库: {:?} at {:?}", db_type, path);
-                let conn = Connection::open_with_flags(
-                    &path,
-                    rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
-                )?;
-                return Ok(LittleNavmapDB { conn, db_type });
-            }
+        Self::select(&NavmapConfig::default(), None)
+    }
+    
+    /// 以只读方式打开数据库文件
+    pub fn open(path: &Path, db_type: DatabaseType) -> Result<Self> {
+        if !path.is_file() {
+            return Err(rusqlite::Error::InvalidPath(path.to_path_buf()));
         }
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // 不是 Little Navmap 数据库时尽早报错
+        conn.prepare("SELECT ident FROM airport LIMIT 1")?;
         
-        Err(rusqlite::Error::InvalidPath(PathBuf::from(
-            "未找到 Little Navmap 数据库。请确保已安装 Little Navmap。"
-        )))
+        println!("检测到 Little Navmap 数据库: {:?} at {:?}", db_type, path);
+        Ok(LittleNavmapDB { conn, db_type, path: path.to_path_buf() })
     }
     
-    /// 获取数据库路径（按优先级排序）
-    fn get_database_paths() -> Vec<(DatabaseType, PathBuf)> {
-        let mut paths = Vec::new();
+    /// 按配置选择数据库
+    ///
+    /// 指定文件时只使用该文件；指定类型时只使用该类型；否则优先 `simulator` 对应的数据库
+    pub fn select(config: &NavmapConfig, simulator: Option<DatabaseType>) -> Result<Self> {
+        if let Some((db_type, path)) = config.override_path() {
+            return Self::open(&path, db_type);
+        }
         
-        #[cfg(target_os = "windows")]
-        {
-            if let Some(appdata) = dirs::config_dir() {
-                let base = appdata.join("ABarthel").join("little_navmap_db");
-                paths.push((DatabaseType::Navigraph, base.join("little_navmap_navigraph.sqlite")));
-                paths.push((DatabaseType::XPlane, base.join("little_navmap_xp12.sqlite")));
-                paths.push((DatabaseType::MSFS, base.join("little_navmap_msfs.sqlite")));
+        let candidates = Self::order_candidates(Self::detect_databases(), config.db_type, simulator);
+        for (db_type, path) in candidates {
+            match Self::open(&path, db_type) {
+                Ok(db) => return Ok(db),
+                Err(e) => eprintln!("⚠ 无法打开 Little Navmap 数据库 {:?}: {}", path, e),
+            }
+        }
+        
+        Err(rusqlite::Error::InvalidPath(PathBuf::from(match config.db_type {
+            Some(db_type) => format!("未找到 {:?} 类型的 Little Navmap 数据库。", db_type),
+            None => "未找到 Little Navmap 数据库。请确保已安装 Little Navmap。".to_string(),
+        })))
+    }
+    
+    /// 候选数据库排序：指定类型时只保留该类型，否则模拟器对应的类型排在最前
+    fn order_candidates(
+        mut candidates: Vec<(DatabaseType, PathBuf)>,
+        db_type: Option<DatabaseType>,
+        simulator: Option<DatabaseType>,
+    ) -> Vec<(DatabaseType, PathBuf)> {
+        if let Some(db_type) = db_type {
+            candidates.retain(|(t, _)| *t == db_type);
+        } else if let Some(simulator) = simulator {
+            // 稳定排序，同类型保持原有优先级
+            candidates.sort_by_key(|(t, _)| *t != simulator);
+        }
+        candidates
+    }
+    
+    /// 已安装的数据库（按优先级排序）
+    pub fn detect_databases() -> Vec<(DatabaseType, PathBuf)> {
+        Self::get_database_paths()
+            .into_iter()
+            .filter(|(_, path)| path.is_file())
+            .collect()
+    }
+    
+    /// 全部数据库及其 AIRAC 周期；指定的文件排在最前
+    pub fn list_databases(config: &NavmapConfig, active: Option<&Path>) -> Vec<DatabaseInfo> {
+        let mut databases = Self::detect_databases();
+        if let Some((db_type, path)) = config.override_path() {
+            if path.is_file() && !databases.iter().any(|(_, p)| *p == path) {
+                databases.insert(0, (db_type, path));
             }
         }
         
+        databases
+            .into_iter()
+            .map(|(db_type, path)| {
+                let mut info = Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .map(|conn| Self::read_info(&conn, db_type, &path))
+                    .unwrap_or_else(|_| Self::empty_info(db_type, &path));
+                info.active = active == Some(path.as_path());
+                info
+            })
+            .collect()
+    }
+    
+    /// 当前数据库的信息
+    pub fn info(&self) -> DatabaseInfo {
+        let mut info = Self::read_info(&self.conn, self.db_type, &self.path);
+        info.active = true;
+        info
+    }
+    
+    pub fn db_type(&self) -> DatabaseType {
+        self.db_type
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// 读取 metadata 表（旧版数据库缺少的列留空）
+    fn read_info(conn: &Connection, db_type: DatabaseType, path: &Path) -> DatabaseInfo {
+        let metadata = conn
+            .query_row(
+                "SELECT airac_cycle, valid_through, data_source, last_load_timestamp FROM metadata LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional();
+        
+        match metadata {
+            Ok(Some((airac_cycle, valid_through, data_source, compiled))) => DatabaseInfo {
+                airac_cycle,
+                valid_through,
+                data_source,
+                compiled,
+                ..Self::empty_info(db_type, path)
+            },
+            _ => Self::empty_info(db_type, path),
+        }
+    }
+    
+    fn empty_info(db_type: DatabaseType, path: &Path) -> DatabaseInfo {
+        DatabaseInfo {
+            db_type,
+            path: path.to_string_lossy().to_string(),
+            airac_cycle: None,
+            valid_through: None,
+            data_source: None,
+            compiled: None,
+            active: false,
+        }
+    }
+    
+    /// Little Navmap 数据库目录
+    fn database_dir() -> Option<PathBuf> {
         #[cfg(target_os = "macos")]
         {
-            if let Some(home) = dirs::home_dir() {
-                let base = home
-                    .join("Library")
+            dirs::home_dir().map(|home| {
+                home.join("Library")
                     .join("Application Support")
                     .join("ABarthel")
-                    .join("little_navmap_db");
-                paths.push((DatabaseType::Navigraph, base.join("little_navmap_navigraph.sqlite")));
-                paths.push((DatabaseType::XPlane, base.join("little_navmap_xp12.sqlite")));
-                paths.push((DatabaseType::MSFS, base.join("little_navmap_msfs.sqlite")));
-            }
+                    .join("little_navmap_db")
+            })
         }
         
-        #[cfg(target_os = "linux")]
+        #[cfg(not(target_os = "macos"))]
         {
-            if let Some(config) = dirs::config_dir() {
-                let base = config
-                    .join("ABarthel")
-                    .join("little_navmap_db");
-                paths.push((DatabaseType::Navigraph, base.join("little_navmap_navigraph.sqlite")));
-                paths.push((DatabaseType::XPlane, base.join("little_navmap_xp12.sqlite")));
-                paths.push((DatabaseType::MSFS, base.join("little_navmap_msfs.sqlite")));
+            dirs::config_dir().map(|config| config.join("ABarthel").join("little_navmap_db"))
+        }
+    }
+    
+    /// 获取数据库路径（按优先级排序）
+    fn get_database_paths() -> Vec<(DatabaseType, PathBuf)> {
+        let Some(base) = Self::database_dir() else { return Vec::new() };
+        DATABASE_FILES
+            .iter()
+            .map(|(db_type, file)| (*db_type, base.join(file)))
+            .collect()
+    }
+    
+    /// 半径内最近的机场（不含直升机场）
+    pub fn find_nearest_airport(&self, lat: f64, lon: f64, radius_km: f64) -> Result<Option<String>> {
+        let area = SearchArea::new(lat, lon, radius_km);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT ident, lat, lonx FROM airport WHERE num_runways > 0 AND {}",
+            SearchArea::CONDITION
+        ))?;
+        
+        let candidates = stmt.query_map(area.params(), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?, row.get::<_, f64>(2)?))
+        })?;
+        
+        let mut nearest: Option<(String, f64)> = None;
+        for candidate in candidates {
+            let (ident, a_lat, a_lon) = candidate?;
+            let distance = ATCDatabase::calculate_distance(lat, lon, a_lat, a_lon);
+            if distance <= radius_km && nearest.as_ref().is_none_or(|(_, d)| distance < *d) {
+                nearest = Some((ident, distance));
+            }
+        }
+        
+        Ok(nearest.map(|(ident, _)| ident))
+    }
+    
+    /// 查询 VOR、NDB 和航路点（同一位置的导航台与航路点只保留导航台）
+    fn query_fixes(&self, condition: &str, params: &[(&str, &dyn rusqlite::ToSql)]) -> Result<Vec<FixData>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT 'vor', ident, name, region, frequency, lat, lonx FROM vor WHERE {0}
+             UNION ALL
+             SELECT 'ndb', ident, name, region, frequency, lat, lonx FROM ndb WHERE {0}
+             UNION ALL
+             SELECT 'waypoint', ident, NULL, region, NULL, lat, lonx FROM waypoint WHERE {0}",
+            condition
+        ))?;
+        
+        let rows = stmt.query_map(params, |row| {
+            let fix_type = match row.get::<_, String>(0)?.as_str() {
+                "vor" => FixType::Vor,
+                "ndb" => FixType::Ndb,
+                _ => FixType::Waypoint,
+            };
+            // VOR 频率单位为 MHz × 1000，NDB 为 kHz × 100
+            let frequency = row.get::<_, Option<i32>>(4)?.map(|f| match fix_type {
+                FixType::Ndb => f as f64 / 100.0,
+                _ => f as f64 / 1000.0,
+            });
+            Ok(FixData {
+                ident: row.get(1)?,
+                name: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                region: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                fix_type,
+                frequency,
+                latitude: row.get(5)?,
+                longitude: row.get(6)?,
+            })
+        })?;
+        
+        let mut fixes: Vec<FixData> = Vec::new();
+        for fix in rows {
+            let fix = fix?;
+            let duplicate = fixes.iter().any(|f| {
+                f.ident == fix.ident
+                    && ATCDatabase::calculate_distance(f.latitude, f.longitude, fix.latitude, fix.longitude) < 1.0
+            });
+            if !duplicate {
+                fixes.push(fix);
+            }
+        }
+        Ok(fixes)
+    }
+    
+    /// 半径内的导航台和航路点，按距离排序
+    pub fn find_nearby_fixes(&self, lat: f64, lon: f64, radius_km: f64, limit: usize) -> Result<Vec<FixData>> {
+        let area = SearchArea::new(lat, lon, radius_km);
+        let [lat_min, lat_max, west_1, east_1, west_2, east_2] = area.params();
+        let mut fixes = self.query_fixes(SearchArea::NAMED_CONDITION, &[
+            (":lat_min", &lat_min), (":lat_max", &lat_max),
+            (":west_1", &west_1), (":east_1", &east_1),
+            (":west_2", &west_2), (":east_2", &east_2),
+        ])?;
+        
+        let distance = |f: &FixData| ATCDatabase::calculate_distance(lat, lon, f.latitude, f.longitude);
+        fixes.retain(|f| distance(f) <= radius_km);
+        fixes.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        fixes.truncate(limit);
+        Ok(fixes)
+    }
+    
+    /// 半径内最近的导航台或航路点
+    pub fn find_nearest_fix(&self, lat: f64, lon: f64, radius_km: f64) -> Result<Option<FixData>> {
+        Ok(self.find_nearby_fixes(lat, lon, radius_km, 1)?.into_iter().next())
+    }
+    
+    /// 按识别码查找导航台或航路点（识别码全球不唯一，返回离参考点最近的一个）
+    pub fn get_fix(&self, ident: &str, near_lat: f64, near_lon: f64) -> Result<Option<FixData>> {
+        let ident = ident.trim().to_uppercase();
+        let fixes = self.query_fixes("ident = :ident", &[(":ident", &ident)])?;
+        
+        Ok(fixes.into_iter().min_by(|a, b| {
+            let da = ATCDatabase::calculate_distance(near_lat, near_lon, a.latitude, a.longitude);
+            let db = ATCDatabase::calculate_distance(near_lat, near_lon, b.latitude, b.longitude);
+            da.total_cmp(&db)
+        }))
+    }
+    
+    /// 航路的全部航段（按片段和顺序排列）
+    pub fn get_airway(&self, name: &str) -> Result<Vec<AirwaySegment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.airway_name, a.airway_type, a.airway_fragment_no, a.sequence_no,
+                    wf.ident, wt.ident, a.direction, a.minimum_altitude, a.maximum_altitude,
+                    a.from_lat, a.from_lonx, a.to_lat, a.to_lonx
+             FROM airway a
+             JOIN waypoint wf ON wf.waypoint_id = a.from_waypoint_id
+             JOIN waypoint wt ON wt.waypoint_id = a.to_waypoint_id
+             WHERE a.airway_name = ?1
+             ORDER BY a.airway_fragment_no, a.sequence_no"
+        )?;
+        
+        let segments = stmt.query_map([name.trim().to_uppercase()], |row| {
+            Ok(AirwaySegment {
+                airway_name: row.get(0)?,
+                airway_type: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                fragment: row.get(2)?,
+                sequence: row.get(3)?,
+                from_ident: row.get(4)?,
+                to_ident: row.get(5)?,
+                direction: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                minimum_altitude: row.get::<_, Option<i32>>(7)?.unwrap_or(0),
+                maximum_altitude: row.get::<_, Option<i32>>(8)?.unwrap_or(0),
+                from_latitude: row.get(9)?,
+                from_longitude: row.get(10)?,
+                to_latitude: row.get(11)?,
+                to_longitude: row.get(12)?,
+            })
+        })?;
+        
+        segments.collect()
+    }
+    
+    /// 航路上两个航路点之间的航段（按飞行方向排列）
+    ///
+    /// 两点不在同一片段上，或方向与航路的单向限制相反时返回空
+    pub fn get_airway_segment(&self, name: &str, from: &str, to: &str) -> Result<Vec<AirwaySegment>> {
+        let (from, to) = (from.trim().to_uppercase(), to.trim().to_uppercase());
+        let segments = self.get_airway(name)?;
+        
+        let mut fragments: Vec<Vec<AirwaySegment>> = Vec::new();
+        for segment in segments {
+            match fragments.last_mut() {
+                Some(fragment) if fragment[0].fragment == segment.fragment => fragment.push(segment),
+                _ => fragments.push(vec![segment]),
             }
         }
         
-        paths
+        for fragment in fragments {
+            // 片段上的航路点序列
+            let mut fixes: Vec<&str> = vec![&fragment[0].from_ident];
+            fixes.extend(fragment.iter().map(|s| s.to_ident.as_str()));
+            
+            let (Some(i), Some(j)) = (
+                fixes.iter().position(|f| *f == from),
+                fixes.iter().position(|f| *f == to),
+            ) else { continue };
+            
+            if i < j {
+                let path = fragment[i..j].to_vec();
+                // B 表示只能逆向飞行
+                if path.iter().all(|s| s.direction != "B") {
+                    return Ok(path);
+                }
+            } else if i > j {
+                let path: Vec<AirwaySegment> = fragment[j..i].iter().rev().map(AirwaySegment::reversed).collect();
+                // F 表示只能正向飞行
+                if fragment[j..i].iter().all(|s| s.direction != "F") {
+                    return Ok(path);
+                }
+            }
+        }
+        
+        Ok(Vec::new())
+    }
+    
+    /// 机场的全部 ILS
+    pub fn get_airport_ils(&self, airport_icao: &str) -> Result<Vec<IlsData>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ident, name, frequency, loc_airport_ident, loc_runway_name, loc_heading,
+                    gs_pitch, lat, lonx
+             FROM ils WHERE loc_airport_ident = ?1 ORDER BY loc_runway_name"
+        )?;
+        
+        let ils = stmt.query_map([airport_icao.trim().to_uppercase()], |row| {
+            Ok(IlsData {
+                ident: row.get(0)?,
+                name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                frequency: row.get::<_, i32>(2)? as f64 / 1000.0,
+                airport: row.get(3)?,
+                runway: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                course: row.get(5)?,
+                glideslope: row.get::<_, Option<f64>>(6)?.filter(|pitch| *pitch > 0.0),
+                latitude: row.get(7)?,
+                longitude: row.get(8)?,
+            })
+        })?;
+        
+        ils.collect()
+    }
+    
+    /// 指定跑道端的 ILS（跑道号可带 "RW" / "RWY" 前缀，如 "RWY36R"）
+    pub fn get_ils(&self, airport_icao: &str, runway: &str) -> Result<Option<IlsData>> {
+        let runway = normalize_runway_end(runway);
+        
+        Ok(self.get_airport_ils(airport_icao)?
+            .into_iter()
+            .find(|ils| normalize_runway_end(&ils.runway) == runway))
+    }
+    
+    /// 格式化附近导航设施（用于 LLM 上下文）
+    pub fn format_navaids_for_llm(ils: &[IlsData], fixes: &[FixData], language: &str) -> String {
+        let ils_list = if ils.is_empty() {
+            if language == "zh" { "（暂无数据）".to_string() } else { "(No data)".to_string() }
+        } else {
+            ils.iter().map(|i| {
+                let glideslope = match i.glideslope {
+                    Some(pitch) if language == "zh" => format!("，下滑角 {:.1}°", pitch),
+                    Some(pitch) => format!(", glideslope {:.1}°", pitch),
+                    None => String::new(),
+                };
+                if language == "zh" {
+                    format!("- 跑道 {}：{} {:.2} MHz，航道 {:.0}°{}", i.runway, i.ident, i.frequency, i.course, glideslope)
+                } else {
+                    format!("- Runway {}: {} {:.2} MHz, course {:.0}°{}", i.runway, i.ident, i.frequency, i.course, glideslope)
+                }
+            }).collect::<Vec<_>>().join("\n")
+        };
+        
+        let fix_list = if fixes.is_empty() {
+            if language == "zh" { "（暂无数据）".to_string() } else { "(No data)".to_string() }
+        } else {
+            fixes.iter().map(|f| {
+                let frequency = match (f.fix_type, f.frequency) {
+                    (FixType::Vor, Some(mhz)) => format!(" {:.2} MHz", mhz),
+                    (FixType::Ndb, Some(khz)) => format!(" {:.0} kHz", khz),
+                    _ => String::new(),
+                };
+                format!("- {} {}{}{}", f.fix_type.as_str(), f.ident,
+                    if f.name.is_empty() { String::new() } else { format!("（{}）", f.name) },
+                    frequency)
+            }).collect::<Vec<_>>().join("\n")
+        };
+        
+        if language == "zh" {
+            format!("**ILS：**\n{}\n\n**附近导航台和航路点：**\n{}", ils_list, fix_list)
+        } else {
+            format!("**ILS:**\n{}\n\n**Nearby Navaids and Fixes:**\n{}", ils_list, fix_list)
+        }
     }
     
     /// 获取机场完整信息
@@ -113,13 +576,14 @@ impl LittleNavmapDB {
                 parking: Vec::new(),
                 sids: Vec::new(),
                 stars: Vec::new(),
+                taxi_network: TaxiNetwork::default(),
             })
         })?;
         
         Ok(airport)
     }
     
-    /// ��取跑道信息
+    /// 获取跑道信息
     pub fn get_runways(&self, airport_id: i32) -> Result<Vec<RunwayData>> {
         let mut stmt = self.conn.prepare(
             "SELECT primary_name, secondary_name, heading, length, width, surface
@@ -137,7 +601,7 @@ impl LittleNavmapDB {
             })
         })?;
         
-        Ok(runways.collect::<Result<Vec<_>>>()?)
+        runways.collect()
     }
     
     /// 获取停机位信息
@@ -160,47 +624,257 @@ impl LittleNavmapDB {
             })
         })?;
         
-        Ok(parking.collect::<Result<Vec<_>>>()?)
+        parking.collect()
     }
     
-    /// 获取 SID 程序
-    pub fn get_sids(&self, airport_id: i32) -> Result<Vec<ProcedureData>> {
+    /// 获取滑行网络（taxi_path 表，端点坐标相同的视为同一节点）
+    ///
+    /// 类型 V（车辆通道）不导入，C 为关闭的滑行道，R 为跑道；宽度换算为可用的翼展等级
+    pub fn get_taxi_network(&self, airport_id: i32) -> Result<TaxiNetwork> {
+        let mut stmt = self.conn.prepare(
+            "SELECT type, name, width, start_lat, start_lonx, end_lat, end_lonx
+             FROM taxi_path WHERE airport_id = ?1 AND (type IS NULL OR type <> 'V')"
+        )?;
+        
+        let paths = stmt.query_map([airport_id], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, Option<f64>>(2)?.unwrap_or(0.0),
+                (row.get::<_, f64>(3)?, row.get::<_, f64>(4)?),
+                (row.get::<_, f64>(5)?, row.get::<_, f64>(6)?),
+            ))
+        })?;
+        
+        let mut network = TaxiNetwork::default();
+        let mut node_ids: HashMap<(i64, i64), u32> = HashMap::new();
+        let mut node_id = |network: &mut TaxiNetwork, (lat, lon): (f64, f64)| {
+            // 约 0.1 米以内的端点视为同一节点
+            let key = ((lat * 1e6).round() as i64, (lon * 1e6).round() as i64);
+            *node_ids.entry(key).or_insert_with(|| {
+                let id = network.nodes.len() as u32;
+                network.nodes.push(TaxiNode { id, latitude: lat, longitude: lon });
+                id
+            })
+        };
+        
+        for path in paths {
+            let (path_type, name, width_ft, start, end) = path?;
+            let from = node_id(&mut network, start);
+            let to = node_id(&mut network, end);
+            network.segments.push(TaxiSegment {
+                from,
+                to,
+                name,
+                runway: path_type == "R",
+                oneway: false,
+                max_size: taxiway_size_code(width_ft * FT_TO_M),
+                runway_zone: vec![],
+                closed: path_type == "C",
+            });
+        }
+        
+        // 跑道入口位置，用于选择指定跑道端的等待点
         let mut stmt = self.conn.prepare(
-            "SELECT DISTINCT fix_ident, runway_name 
-             FROM transition 
-             WHERE airport_id = ?1 AND type = 'SID'
-             ORDER BY fix_ident"
+            "SELECT primary_name, primary_lat, primary_lonx, secondary_name, secondary_lat, secondary_lonx
+             FROM runway WHERE airport_id = ?1"
         )?;
         
-        let sids = stmt.query_map([airport_id], |row| {
+        let ends = stmt.query_map([airport_id], |row| {
+            Ok([
+                TaxiRunwayEnd { name: row.get(0)?, latitude: row.get(1)?, longitude: row.get(2)? },
+                TaxiRunwayEnd { name: row.get(3)?, latitude: row.get(4)?, longitude: row.get(5)? },
+            ])
+        })?;
+        
+        for pair in ends {
+            network.runway_ends.extend(pair?);
+        }
+        
+        Ok(network)
+    }
+    
+    /// 程序名称和适用跑道（同一程序按跑道分别列出）
+    fn get_procedure_names(&self, airport_id: i32, kind: ProcedureKind) -> Result<Vec<ProcedureData>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT DISTINCT {}, runway_name
+             FROM approach
+             WHERE airport_id = ?1 AND {}
+             ORDER BY 1, 2",
+            kind.name_column(), kind.condition()
+        ))?;
+        
+        let procedures = stmt.query_map([airport_id], |row| {
             Ok(ProcedureData {
                 name: row.get(0)?,
-                runway: row.get(1).unwrap_or_default(),
-                procedure_type: "SID".to_string(),
+                runway: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                procedure_type: kind.as_str().to_string(),
             })
         })?;
         
-        Ok(sids.collect::<Result<Vec<_>>>()?)
+        procedures.collect()
+    }
+    
+    /// 获取 SID 程序
+    pub fn get_sids(&self, airport_id: i32) -> Result<Vec<ProcedureData>> {
+        self.get_procedure_names(airport_id, ProcedureKind::Sid)
     }
     
     /// 获取 STAR 程序
     pub fn get_stars(&self, airport_id: i32) -> Result<Vec<ProcedureData>> {
+        self.get_procedure_names(airport_id, ProcedureKind::Star)
+    }
+    
+    /// 机场的完整程序（含过渡和航段）
+    pub fn get_procedures(&self, airport_icao: &str, kind: ProcedureKind) -> Result<Vec<Procedure>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT approach_id, {}, runway_name, type
+             FROM approach
+             WHERE airport_ident = ?1 AND {}
+             ORDER BY 2, 3",
+            kind.name_column(), kind.condition()
+        ))?;
+        
+        let headers = stmt.query_map([airport_icao.trim().to_uppercase()], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            ))
+        })?.collect::<Result<Vec<_>>>()?;
+        
+        headers.into_iter().map(|(approach_id, name, runway, approach_type)| {
+            Ok(Procedure {
+                name,
+                kind,
+                runway,
+                approach_type,
+                legs: self.get_legs("approach_leg", "approach_id", approach_id)?,
+                transitions: self.get_transitions(approach_id)?,
+            })
+        }).collect()
+    }
+    
+    /// 按名称查找程序；指定跑道时优先返回该跑道的版本
+    pub fn get_procedure(&self, airport_icao: &str, kind: ProcedureKind, name: &str, runway: Option<&str>) -> Result<Option<Procedure>> {
+        let name = name.trim().to_uppercase();
+        let runway = runway.map(normalize_runway_end);
+        
+        let mut candidates: Vec<Procedure> = self.get_procedures(airport_icao, kind)?
+            .into_iter()
+            .filter(|p| p.name == name)
+            .collect();
+        
+        if let Some(runway) = runway {
+            if let Some(index) = candidates.iter().position(|p| normalize_runway_end(&p.runway) == runway) {
+                return Ok(Some(candidates.swap_remove(index)));
+            }
+            // 适用所有跑道的公共段
+            candidates.retain(|p| p.runway.is_empty() || p.runway == "ALL");
+        }
+        
+        Ok(candidates.into_iter().next())
+    }
+    
+    fn get_transitions(&self, approach_id: i32) -> Result<Vec<ProcedureTransition>> {
         let mut stmt = self.conn.prepare(
-            "SELECT DISTINCT fix_ident, runway_name 
-             FROM transition 
-             WHERE airport_id = ?1 AND type = 'STAR'
-             ORDER BY fix_ident"
+            "SELECT transition_id, fix_ident FROM transition WHERE approach_id = ?1 ORDER BY fix_ident"
         )?;
         
-        let stars = stmt.query_map([airport_id], |row| {
-            Ok(ProcedureData {
-                name: row.get(0)?,
-                runway: row.get(1).unwrap_or_default(),
-                procedure_type: "STAR".to_string(),
+        let headers = stmt.query_map([approach_id], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+        })?.collect::<Result<Vec<_>>>()?;
+        
+        headers.into_iter().map(|(transition_id, name)| {
+            Ok(ProcedureTransition {
+                name,
+                legs: self.get_legs("transition_leg", "transition_id", transition_id)?,
+            })
+        }).collect()
+    }
+    
+    /// 读取程序或过渡的航段（approach_leg / transition_leg 列相同）
+    fn get_legs(&self, table: &str, key: &str, id: i32) -> Result<Vec<ProcedureLeg>> {
+        let missed = if table == "approach_leg" { "is_missed" } else { "0" };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT type, fix_ident, fix_type, fix_lat, fix_lonx, course, distance,
+                    alt_descriptor, altitude1, altitude2, speed_limit_type, speed_limit,
+                    turn_direction, is_flyover, {}
+             FROM {} WHERE {} = ?1 ORDER BY {}_id",
+            missed, table, key, table
+        ))?;
+        
+        let legs = stmt.query_map([id], |row| {
+            Ok(ProcedureLeg {
+                leg_type: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                fix_ident: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                fix_type: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                latitude: row.get(3)?,
+                longitude: row.get(4)?,
+                course: row.get(5)?,
+                distance: row.get(6)?,
+                altitude: AltitudeConstraint::from_arinc(
+                    row.get::<_, Option<String>>(7)?.as_deref(),
+                    row.get(8)?,
+                    row.get(9)?,
+                ),
+                speed: SpeedConstraint::from_arinc(
+                    row.get::<_, Option<String>>(10)?.as_deref(),
+                    row.get(11)?,
+                ),
+                turn_direction: row.get::<_, Option<String>>(12)?.unwrap_or_default(),
+                flyover: row.get::<_, Option<i32>>(13)?.unwrap_or(0) != 0,
+                missed: row.get::<_, Option<i32>>(14)?.unwrap_or(0) != 0,
             })
         })?;
         
-        Ok(stars.collect::<Result<Vec<_>>>()?)
+        legs.collect()
+    }
+    
+    /// 格式化离场、进场程序（用于 LLM 上下文）
+    pub fn format_procedures_for_llm(sids: &[Procedure], stars: &[Procedure], language: &str) -> String {
+        let format_list = |procedures: &[Procedure]| -> String {
+            if procedures.is_empty() {
+                return if language == "zh" { "（暂无数据）".to_string() } else { "(No data)".to_string() };
+            }
+            
+            procedures.iter().map(|p| {
+                let runway = match (p.runway.is_empty(), language == "zh") {
+                    (true, _) => String::new(),
+                    (false, true) => format!("（跑道 {}）", p.runway),
+                    (false, false) => format!(" (RWY {})", p.runway),
+                };
+                let mut line = if language == "zh" {
+                    format!("- {}{}：{}", p.name, runway, p.fixes().join(" "))
+                } else {
+                    format!("- {}{}: {}", p.name, runway, p.fixes().join(" "))
+                };
+                
+                if let Some(altitude) = p.initial_climb_altitude() {
+                    line.push_str(&if language == "zh" {
+                        format!("；初始爬升 {} 英尺", altitude)
+                    } else {
+                        format!("; initial climb {} ft", altitude)
+                    });
+                }
+                if !p.transitions.is_empty() {
+                    let names = p.transitions.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+                    line.push_str(&if language == "zh" {
+                        format!("；过渡：{}", names.join("、"))
+                    } else {
+                        format!("; transitions: {}", names.join(", "))
+                    });
+                }
+                line
+            }).collect::<Vec<_>>().join("\n")
+        };
+        
+        if language == "zh" {
+            format!("**离场程序（SID）：**\n{}\n\n**进场程序（STAR）：**\n{}", format_list(sids), format_list(stars))
+        } else {
+            format!("**SID:**\n{}\n\n**STAR:**\n{}", format_list(sids), format_list(stars))
+        }
     }
     
     /// 获取机场完整数据（包含所有子数据）
@@ -211,6 +885,7 @@ impl LittleNavmapDB {
         airport.parking = self.get_parking(airport.airport_id).unwrap_or_default();
         airport.sids = self.get_sids(airport.airport_id).unwrap_or_default();
         airport.stars = self.get_stars(airport.airport_id).unwrap_or_default();
+        airport.taxi_network = self.get_taxi_network(airport.airport_id).unwrap_or_default();
         
         Ok(airport)
     }
@@ -319,10 +994,10 @@ impl LittleNavmapDB {
         
         runways.iter().map(|r| {
             if language == "zh" {
-                format!("- 跑道 {}/{}：长 {} 米，宽 {} 米，航向 {}°，表面：{}", 
+                format!("- 跑道 {}/{}：长 {} 英尺，宽 {} 英尺，航向 {}°，表面：{}", 
                     r.primary_name, r.secondary_name, r.length, r.width, r.heading, r.surface)
             } else {
-                format!("- Runway {}/{}: {} m x {} m, heading {}°, surface: {}", 
+                format!("- Runway {}/{}: {} ft x {} ft, heading {}°, surface: {}", 
                     r.primary_name, r.secondary_name, r.length, r.width, r.heading, r.surface)
             }
         }).collect::<Vec<_>>().join("\n")
@@ -360,25 +1035,25 @@ impl LittleNavmapDB {
         
         if let Some(f) = airport.tower_frequency {
             freqs.push(if language == "zh" {
-                format!("- 塔台：{:.3} MHz", f / 1000.0)
+                format!("- 塔台：{:.3} MHz", f as f64 / 1000.0)
             } else {
-                format!("- Tower: {:.3} MHz", f / 1000.0)
+                format!("- Tower: {:.3} MHz", f as f64 / 1000.0)
             });
         }
         
         if let Some(f) = airport.atis_frequency {
             freqs.push(if language == "zh" {
-                format!("- ATIS：{:.3} MHz", f / 1000.0)
+                format!("- ATIS：{:.3} MHz", f as f64 / 1000.0)
             } else {
-                format!("- ATIS: {:.3} MHz", f / 1000.0)
+                format!("- ATIS: {:.3} MHz", f as f64 / 1000.0)
             });
         }
         
         if let Some(f) = airport.unicom_frequency {
             freqs.push(if language == "zh" {
-                format!("- UNICOM：{:.3} MHz", f / 1000.0)
+                format!("- UNICOM：{:.3} MHz", f as f64 / 1000.0)
             } else {
-                format!("- UNICOM: {:.3} MHz", f / 1000.0)
+                format!("- UNICOM: {:.3} MHz", f as f64 / 1000.0)
             });
         }
         
@@ -424,6 +1099,71 @@ pub struct AirportData {
     pub parking: Vec<ParkingData>,
     pub sids: Vec<ProcedureData>,
     pub stars: Vec<ProcedureData>,
+    #[serde(default)]
+    pub taxi_network: TaxiNetwork,
+}
+
+impl AirportData {
+    /// 转换为 ATC 数据库的机场结构（长度单位换算为米）
+    pub fn to_atc_airport(&self) -> Airport {
+        let khz_to_mhz = |f: i32| f as f32 / 1000.0;
+        let mut frequencies = Frequencies {
+            tower: self.tower_frequency.map(khz_to_mhz).into_iter().collect(),
+            atis: self.atis_frequency.or(self.awos_frequency).or(self.asos_frequency)
+                .map(khz_to_mhz).into_iter().collect(),
+            ..Frequencies::default()
+        };
+        // 无塔台机场使用 UNICOM
+        if frequencies.tower.is_empty() {
+            frequencies.tower = self.unicom_frequency.map(khz_to_mhz).into_iter().collect();
+        }
+        
+        let mut sids: Vec<String> = self.sids.iter().map(|p| p.name.clone()).collect();
+        sids.dedup();
+        let mut stars: Vec<String> = self.stars.iter().map(|p| p.name.clone()).collect();
+        stars.dedup();
+        
+        Airport {
+            icao: self.icao.clone(),
+            name: self.name.clone(),
+            name_en: self.name.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            elevation: self.elevation,
+            runways: self.runways.iter().map(|r| {
+                let heading_1 = Runway::designator_heading(&r.primary_name)
+                    .unwrap_or(r.heading.round() as u16);
+                Runway {
+                    name: format!("{}/{}", r.primary_name, r.secondary_name),
+                    heading_1,
+                    heading_2: Runway::designator_heading(&r.secondary_name)
+                        .unwrap_or((heading_1 + 180) % 360),
+                    length: (r.length as f64 * FT_TO_M).round() as u32,
+                    width: (r.width as f64 * FT_TO_M).round() as u32,
+                }
+            }).collect(),
+            taxiways: vec![],
+            gates: self.parking.iter().map(|p| Gate {
+                number: p.label(),
+                terminal: String::new(),
+                aircraft_type: p.parking_type.clone(),
+                taxiway_access: vec![],
+            }).collect(),
+            taxi_routes: vec![],
+            taxi_network: TaxiNetwork {
+                parking: self.parking.iter().map(|p| TaxiParking {
+                    name: p.label(),
+                    latitude: p.latitude,
+                    longitude: p.longitude,
+                }).collect(),
+                ..self.taxi_network.clone()
+            },
+            hotspots: vec![],
+            frequencies,
+            sids,
+            stars,
+        }
+    }
 }
 
 /// 跑道数据
@@ -450,6 +1190,26 @@ pub struct ParkingData {
     pub longitude: f64,
 }
 
+impl ParkingData {
+    /// 停机位名称（"GATE 12"）
+    pub fn label(&self) -> String {
+        if self.number > 0 { format!("{} {}", self.name, self.number) } else { self.name.clone() }
+    }
+}
+
+/// 滑行道宽度（米）对应的最大翼展等级（ICAO 代码字母），宽度未知时不限
+fn taxiway_size_code(width_m: f64) -> Option<char> {
+    match width_m {
+        w if w <= 0.0 => None,
+        w if w < 10.5 => Some('A'),
+        w if w < 15.0 => Some('B'),
+        w if w < 18.0 => Some('C'),
+        w if w < 23.0 => Some('D'),
+        w if w < 25.0 => Some('E'),
+        _ => None,
+    }
+}
+
 /// 程序数据（SID/STAR）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcedureData {
@@ -457,3 +1217,604 @@ pub struct ProcedureData {
     pub runway: String,
     pub procedure_type: String,
 }
+
+/// 程序类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcedureKind {
+    Sid,
+    Star,
+    Approach,
+}
+
+impl ProcedureKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProcedureKind::Sid => "SID",
+            ProcedureKind::Star => "STAR",
+            ProcedureKind::Approach => "APPROACH",
+        }
+    }
+    
+    /// approach 表中的筛选条件：SID/STAR 的类型为 GPS，后缀分别为 D / A
+    fn condition(&self) -> &'static str {
+        match self {
+            ProcedureKind::Sid => "type = 'GPS' AND suffix = 'D'",
+            ProcedureKind::Star => "type = 'GPS' AND suffix = 'A'",
+            ProcedureKind::Approach => "NOT (type = 'GPS' AND COALESCE(suffix, '') IN ('D', 'A'))",
+        }
+    }
+    
+    /// 程序名称所在的列：SID/STAR 在 fix_ident，进近在 arinc_name（如 I36R）
+    fn name_column(&self) -> &'static str {
+        match self {
+            ProcedureKind::Approach => "arinc_name",
+            _ => "fix_ident",
+        }
+    }
+}
+
+/// 高度限制（英尺）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltitudeConstraint {
+    At(i32),
+    AtOrAbove(i32),
+    AtOrBelow(i32),
+    Between { lower: i32, upper: i32 },
+}
+
+impl AltitudeConstraint {
+    /// ARINC 424 高度描述符：A / 空 = 在，+ = 不低于，- = 不高于，B = 介于 altitude2 与 altitude1 之间
+    fn from_arinc(descriptor: Option<&str>, altitude1: Option<f64>, altitude2: Option<f64>) -> Option<Self> {
+        let altitude1 = altitude1.filter(|a| *a > 0.0)?.round() as i32;
+        Some(match descriptor.unwrap_or("").trim() {
+            "+" => AltitudeConstraint::AtOrAbove(altitude1),
+            "-" => AltitudeConstraint::AtOrBelow(altitude1),
+            "B" => {
+                let altitude2 = altitude2.unwrap_or(0.0).round() as i32;
+                AltitudeConstraint::Between { lower: altitude1.min(altitude2), upper: altitude1.max(altitude2) }
+            }
+            _ => AltitudeConstraint::At(altitude1),
+        })
+    }
+}
+
+/// 速度限制（节）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedConstraint {
+    At(i32),
+    AtOrAbove(i32),
+    AtOrBelow(i32),
+}
+
+impl SpeedConstraint {
+    fn from_arinc(limit_type: Option<&str>, speed: Option<f64>) -> Option<Self> {
+        let speed = speed.filter(|s| *s > 0.0)?.round() as i32;
+        Some(match limit_type.unwrap_or("").trim() {
+            "+" => SpeedConstraint::AtOrAbove(speed),
+            "-" => SpeedConstraint::AtOrBelow(speed),
+            _ => SpeedConstraint::At(speed),
+        })
+    }
+}
+
+/// 程序航段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcedureLeg {
+    pub leg_type: String,         // ARINC 424 航段类型（IF、TF、CF、DF、VA 等）
+    pub fix_ident: String,        // 无定位点的航段（如 VA）为空
+    pub fix_type: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub course: Option<f64>,
+    pub distance: Option<f64>,    // 海里
+    pub altitude: Option<AltitudeConstraint>,
+    pub speed: Option<SpeedConstraint>,
+    pub turn_direction: String,   // L / R / 空
+    pub flyover: bool,
+    pub missed: bool,             // 复飞航段
+}
+
+/// 程序过渡（进场过渡或离场过渡）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcedureTransition {
+    pub name: String,
+    pub legs: Vec<ProcedureLeg>,
+}
+
+/// SID / STAR / 进近程序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Procedure {
+    pub name: String,
+    pub kind: ProcedureKind,
+    pub runway: String,           // 空或 ALL 表示公共段
+    pub approach_type: String,    // GPS、ILS、VOR 等
+    pub legs: Vec<ProcedureLeg>,
+    pub transitions: Vec<ProcedureTransition>,
+}
+
+impl Procedure {
+    /// 程序经过的定位点（不含复飞航段）
+    pub fn fixes(&self) -> Vec<&str> {
+        let mut fixes: Vec<&str> = Vec::new();
+        for leg in self.legs.iter().filter(|l| !l.missed && !l.fix_ident.is_empty()) {
+            if fixes.last() != Some(&leg.fix_ident.as_str()) {
+                fixes.push(&leg.fix_ident);
+            }
+        }
+        fixes
+    }
+    
+    /// SID 的初始爬升高度：第一个限制上限的高度（在 / 不高于 / 介于），
+    /// 没有时取最高的"不低于"限制
+    pub fn initial_climb_altitude(&self) -> Option<i32> {
+        if self.kind != ProcedureKind::Sid {
+            return None;
+        }
+        
+        let constraints = || self.legs.iter().filter(|l| !l.missed).filter_map(|l| l.altitude);
+        constraints()
+            .find_map(|c| match c {
+                AltitudeConstraint::At(a) | AltitudeConstraint::AtOrBelow(a) => Some(a),
+                AltitudeConstraint::Between { upper, .. } => Some(upper),
+                AltitudeConstraint::AtOrAbove(_) => None,
+            })
+            .or_else(|| constraints().filter_map(|c| match c {
+                AltitudeConstraint::AtOrAbove(a) => Some(a),
+                _ => None,
+            }).max())
+    }
+    
+    pub fn get_transition(&self, name: &str) -> Option<&ProcedureTransition> {
+        self.transitions.iter().find(|t| t.name.eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// 定位点类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixType {
+    Vor,
+    Ndb,
+    Waypoint,
+}
+
+impl FixType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FixType::Vor => "VOR",
+            FixType::Ndb => "NDB",
+            FixType::Waypoint => "WPT",
+        }
+    }
+}
+
+/// 导航台或航路点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixData {
+    pub ident: String,
+    pub name: String,
+    pub region: String,
+    pub fix_type: FixType,
+    pub frequency: Option<f64>,   // VOR 为 MHz，NDB 为 kHz
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 航路航段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirwaySegment {
+    pub airway_name: String,
+    pub airway_type: String,      // V 低空 / J 高空 / B 两者
+    pub fragment: i32,
+    pub sequence: i32,
+    pub from_ident: String,
+    pub to_ident: String,
+    pub direction: String,        // N 双向 / F 仅正向 / B 仅逆向
+    pub minimum_altitude: i32,    // 英尺
+    pub maximum_altitude: i32,
+    pub from_latitude: f64,
+    pub from_longitude: f64,
+    pub to_latitude: f64,
+    pub to_longitude: f64,
+}
+
+impl AirwaySegment {
+    /// 反向飞行的同一航段
+    fn reversed(&self) -> Self {
+        AirwaySegment {
+            from_ident: self.to_ident.clone(),
+            to_ident: self.from_ident.clone(),
+            from_latitude: self.to_latitude,
+            from_longitude: self.to_longitude,
+            to_latitude: self.from_latitude,
+            to_longitude: self.from_longitude,
+            ..self.clone()
+        }
+    }
+}
+
+/// ILS 数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IlsData {
+    pub ident: String,
+    pub name: String,
+    pub frequency: f64,           // MHz
+    pub airport: String,
+    pub runway: String,
+    pub course: f64,              // 航道（真航向）
+    pub glideslope: Option<f64>,  // 下滑角，仅航向台时为 None
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 按经纬度范围查询的矩形区域（跨越 ±180° 经线时拆成两段）
+struct SearchArea {
+    lat_min: f64,
+    lat_max: f64,
+    range_1: (f64, f64),
+    range_2: (f64, f64),
+}
+
+impl SearchArea {
+    /// 对应 params() 的 SQL 条件
+    const CONDITION: &'static str =
+        "lat BETWEEN ?1 AND ?2 AND (lonx BETWEEN ?3 AND ?4 OR lonx BETWEEN ?5 AND ?6)";
+    /// 命名参数版本（用于 UNION 查询）
+    const NAMED_CONDITION: &'static str =
+        "lat BETWEEN :lat_min AND :lat_max AND (lonx BETWEEN :west_1 AND :east_1 OR lonx BETWEEN :west_2 AND :east_2)";
+    
+    fn new(lat: f64, lon: f64, radius_km: f64) -> Self {
+        let dlat = radius_km / 111.2;
+        let dlon = (radius_km / (111.2 * lat.to_radians().cos().max(0.01))).min(180.0);
+        
+        let (west, east) = (lon - dlon, lon + dlon);
+        let (range_1, range_2) = if west < -180.0 {
+            ((-180.0, east), (west + 360.0, 180.0))
+        } else if east > 180.0 {
+            ((west, 180.0), (-180.0, east - 360.0))
+        } else {
+            ((west, east), (1.0, 0.0))  // 第二段为空
+        };
+        
+        SearchArea { lat_min: lat - dlat, lat_max: lat + dlat, range_1, range_2 }
+    }
+    
+    /// 纬度下限、上限，两段经度范围
+    fn params(&self) -> [f64; 6] {
+        [self.lat_min, self.lat_max, self.range_1.0, self.range_1.1, self.range_2.0, self.range_2.1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use super::super::taxi_network::{RouteOptions, TaxiStart, TaxiTarget};
+    
+    /// 只包含用到的列的 Little Navmap 数据库
+    fn sample_db() -> LittleNavmapDB {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE airport (
+                 airport_id INTEGER PRIMARY KEY, ident TEXT, name TEXT, city TEXT, country TEXT,
+                 lat REAL, lonx REAL, altitude INTEGER, num_runways INTEGER,
+                 tower_frequency INTEGER, atis_frequency INTEGER, awos_frequency INTEGER,
+                 asos_frequency INTEGER, unicom_frequency INTEGER);
+             CREATE TABLE runway (
+                 airport_id INTEGER, primary_name TEXT, secondary_name TEXT,
+                 heading REAL, length INTEGER, width INTEGER, surface TEXT,
+                 primary_lat REAL, primary_lonx REAL, secondary_lat REAL, secondary_lonx REAL);
+             CREATE TABLE parking (
+                 airport_id INTEGER, name TEXT, type TEXT, airline_codes TEXT, number INTEGER,
+                 radius REAL, heading REAL, lat REAL, lonx REAL);
+             CREATE TABLE approach (
+                 approach_id INTEGER PRIMARY KEY, airport_id INTEGER, airport_ident TEXT, arinc_name TEXT,
+                 fix_ident TEXT, runway_name TEXT, type TEXT, suffix TEXT);
+             CREATE TABLE transition (transition_id INTEGER PRIMARY KEY, approach_id INTEGER, fix_ident TEXT, type TEXT);
+             CREATE TABLE approach_leg (
+                 approach_leg_id INTEGER PRIMARY KEY, approach_id INTEGER, type TEXT, fix_ident TEXT,
+                 fix_type TEXT, fix_lat REAL, fix_lonx REAL, course REAL, distance REAL,
+                 alt_descriptor TEXT, altitude1 REAL, altitude2 REAL, speed_limit_type TEXT,
+                 speed_limit REAL, turn_direction TEXT, is_flyover INTEGER, is_missed INTEGER);
+             CREATE TABLE transition_leg (
+                 transition_leg_id INTEGER PRIMARY KEY, transition_id INTEGER, type TEXT, fix_ident TEXT,
+                 fix_type TEXT, fix_lat REAL, fix_lonx REAL, course REAL, distance REAL,
+                 alt_descriptor TEXT, altitude1 REAL, altitude2 REAL, speed_limit_type TEXT,
+                 speed_limit REAL, turn_direction TEXT, is_flyover INTEGER);
+             
+             INSERT INTO airport VALUES
+                 (1, 'ZUUU', 'Chengdu Shuangliu', 'Chengdu', 'China', 30.5785, 103.9471, 1625, 2,
+                  118350, 128600, NULL, NULL, NULL),
+                 (2, 'ZU01', 'Chengdu Heliport', 'Chengdu', 'China', 30.5790, 103.9470, 1600, 0,
+                  NULL, NULL, NULL, NULL, 123450),
+                 (3, 'NFFN', 'Nadi', 'Nadi', 'Fiji', -17.7554, 177.4434, 59, 2,
+                  NULL, NULL, NULL, NULL, 122800);
+             INSERT INTO runway VALUES
+                 (1, '02L', '20R', 20.1, 11811, 148, 'C', 30.5500, 103.9440, 30.5600, 103.9450),
+                 (1, '02R', '20L', 20.1, 11811, 197, 'C', 30.5500, 103.9600, 30.5600, 103.9610);
+             INSERT INTO parking VALUES
+                 (1, 'GATE', 'GATE_MEDIUM', 'CCA', 12, 18.0, 90.0, 30.57, 103.95),
+                 (1, 'Stand 201', 'RAMP_GA', '', 0, 12.0, 90.0, 30.57, 103.95);
+             INSERT INTO approach VALUES
+                 (1, 1, 'ZUUU', 'DOGA1A', 'DOGAR1A', '02L', 'GPS', 'D'),
+                 (2, 1, 'ZUUU', 'DOGA1A', 'DOGAR1A', '02R', 'GPS', 'D'),
+                 (3, 1, 'ZUUU', 'ATVA1A', 'ATVAX1A', '20R', 'GPS', 'A'),
+                 (4, 1, 'ZUUU', 'I02L', 'CD02L', '02L', 'ILS', NULL);
+             INSERT INTO approach_leg VALUES
+                 (1, 1, 'VA', NULL, NULL, NULL, NULL, 20.0, NULL, '+', 5200, NULL, NULL, NULL, NULL, 0, 0),
+                 (2, 1, 'DF', 'SHL', 'V', 30.5650, 103.9450, NULL, NULL, NULL, NULL, NULL, NULL, NULL, 'R', 0, 0),
+                 (3, 1, 'TF', 'DOGAR', 'W', 30.8000, 104.1000, NULL, 14.2, 'A', 6000, NULL, '-', 250, NULL, 0, 0),
+                 (4, 2, 'TF', 'DOGAR', 'W', 30.8000, 104.1000, NULL, NULL, '+', 7000, NULL, NULL, NULL, NULL, 0, 0),
+                 (5, 3, 'IF', 'ATVAX', 'W', 31.2000, 104.5000, NULL, NULL, 'B', 12000, 9800, NULL, NULL, NULL, 0, 0),
+                 (6, 3, 'TF', 'SHL', 'V', 30.5650, 103.9450, NULL, NULL, 'A', 6000, NULL, NULL, 210, NULL, 1, 0),
+                 (7, 4, 'IF', 'CD02L', 'W', 30.4500, 103.9000, NULL, NULL, '+', 4000, NULL, NULL, NULL, NULL, 0, 0),
+                 (8, 4, 'CF', 'RW02L', 'R', 30.5500, 103.9400, 20.0, 7.5, 'A', 1680, NULL, NULL, NULL, NULL, 0, 0),
+                 (9, 4, 'CA', NULL, NULL, NULL, NULL, 20.0, NULL, '+', 4000, NULL, NULL, NULL, NULL, 0, 1);
+             INSERT INTO transition VALUES (1, 3, 'BOKIR', 'F');
+             INSERT INTO transition_leg VALUES
+                 (1, 1, 'IF', 'BOKIR', 'W', 31.6000, 104.9000, NULL, NULL, '+', 14000, NULL, NULL, NULL, NULL, 0),
+                 (2, 1, 'TF', 'ATVAX', 'W', 31.2000, 104.5000, NULL, 30.1, NULL, NULL, NULL, NULL, NULL, NULL, 0);
+             
+             CREATE TABLE vor (ident TEXT, name TEXT, region TEXT, frequency INTEGER, lat REAL, lonx REAL);
+             CREATE TABLE ndb (ident TEXT, name TEXT, region TEXT, frequency INTEGER, lat REAL, lonx REAL);
+             CREATE TABLE waypoint (waypoint_id INTEGER PRIMARY KEY, ident TEXT, region TEXT, lat REAL, lonx REAL);
+             CREATE TABLE airway (
+                 airway_name TEXT, airway_type TEXT, airway_fragment_no INTEGER, sequence_no INTEGER,
+                 from_waypoint_id INTEGER, to_waypoint_id INTEGER, direction TEXT,
+                 minimum_altitude INTEGER, maximum_altitude INTEGER,
+                 from_lat REAL, from_lonx REAL, to_lat REAL, to_lonx REAL);
+             CREATE TABLE ils (
+                 ident TEXT, name TEXT, frequency INTEGER, loc_airport_ident TEXT, loc_runway_name TEXT,
+                 loc_heading REAL, gs_pitch REAL, lat REAL, lonx REAL);
+             
+             INSERT INTO vor VALUES ('SHL', 'Shuangliu', 'ZU', 115600, 30.5650, 103.9450);
+             INSERT INTO ndb VALUES ('QU', 'Qionglai', 'ZU', 35000, 30.4200, 103.4600);
+             INSERT INTO waypoint VALUES
+                 (1, 'SHL', 'ZU', 30.5650, 103.9450),
+                 (2, 'DOGAR', 'ZU', 30.8000, 104.1000),
+                 (3, 'ATVAX', 'ZU', 31.2000, 104.5000),
+                 (4, 'BOKIR', 'ZU', 31.6000, 104.9000),
+                 (5, 'DOGAR', 'K7', 40.0000, -100.0000);
+             INSERT INTO airway VALUES
+                 ('W45', 'B', 1, 1, 1, 2, 'N', 8900, 0, 30.5650, 103.9450, 30.8000, 104.1000),
+                 ('W45', 'B', 1, 2, 2, 3, 'N', 9800, 0, 30.8000, 104.1000, 31.2000, 104.5000),
+                 ('W45', 'B', 1, 3, 3, 4, 'F', 9800, 0, 31.2000, 104.5000, 31.6000, 104.9000);
+             INSERT INTO ils VALUES
+                 ('IOO', 'ILS 02L', 110300, 'ZUUU', '02L', 20.1, 3.0, 30.5500, 103.9400),
+                 ('IUU', 'LOC 20R', 109900, 'ZUUU', '20R', 200.1, 0.0, 30.6100, 103.9600);
+             
+             CREATE TABLE taxi_path (
+                 airport_id INTEGER, type TEXT, name TEXT, width REAL,
+                 start_lat REAL, start_lonx REAL, end_lat REAL, end_lonx REAL);
+             INSERT INTO taxi_path VALUES
+                 (1, 'T', 'A', 75, 30.5700, 103.9500, 30.5700, 103.9450),
+                 (1, 'T', 'A', 75, 30.5700, 103.9450, 30.5600, 103.9450),
+                 (1, 'R', '02L', 148, 30.5600, 103.9450, 30.5500, 103.9440),
+                 (1, 'V', '', 20, 30.5700, 103.9500, 30.5500, 103.9440),
+                 (1, 'T', 'B', 30, 30.5700, 103.9450, 30.5750, 103.9450);",
+        ).unwrap();
+        LittleNavmapDB { conn, db_type: DatabaseType::XPlane, path: PathBuf::new() }
+    }
+    
+    #[test]
+    fn test_find_nearest_airport() {
+        let db = sample_db();
+        // 直升机场更近但不计入
+        assert_eq!(db.find_nearest_airport(30.579, 103.947, 50.0).unwrap(), Some("ZUUU".to_string()));
+        assert_eq!(db.find_nearest_airport(40.0, 116.0, 50.0).unwrap(), None);
+        // 跨越 180° 经线
+        assert_eq!(db.find_nearest_airport(-17.7, -179.9, 300.0).unwrap(), Some("NFFN".to_string()));
+    }
+    
+    #[test]
+    fn test_convert_to_atc_airport() {
+        let db = sample_db();
+        let airport = db.get_airport_full("ZUUU").unwrap().to_atc_airport();
+        
+        assert_eq!(airport.elevation, 1625);
+        assert_eq!(airport.runways[0].name, "02L/20R");
+        assert_eq!((airport.runways[0].heading_1, airport.runways[0].heading_2), (20, 200));
+        assert_eq!(airport.runways[0].length, 3600);
+        assert_eq!(airport.runways[1].width, 60);
+        assert_eq!(airport.frequencies.tower, vec![118.35]);
+        assert_eq!(airport.frequencies.atis, vec![128.6]);
+        assert_eq!(airport.gates.iter().map(|g| g.number.as_str()).collect::<Vec<_>>(), vec!["GATE 12", "Stand 201"]);
+        assert_eq!(airport.sids, vec!["DOGAR1A"]);
+        assert_eq!(airport.stars, vec!["ATVAX1A"]);
+        
+        let context = db.format_for_llm(&db.get_airport_full("ZUUU").unwrap(), "zh");
+        assert!(context.contains("塔台：118.350 MHz"));
+        
+        // 无塔台机场使用 UNICOM
+        let nadi = db.get_airport_full("NFFN").unwrap().to_atc_airport();
+        assert_eq!(nadi.frequencies.tower, vec![122.8]);
+    }
+    
+    #[test]
+    fn test_fix_queries() {
+        let db = sample_db();
+        
+        // 与 VOR 重合的航路点不重复出现
+        let nearby = db.find_nearby_fixes(30.5785, 103.9471, 60.0, 10).unwrap();
+        assert_eq!(nearby.iter().map(|f| f.ident.as_str()).collect::<Vec<_>>(), vec!["SHL", "DOGAR", "QU"]);
+        assert_eq!(nearby[0].fix_type, FixType::Vor);
+        assert_eq!(nearby[0].frequency, Some(115.6));
+        assert_eq!(nearby[2].frequency, Some(350.0));
+        
+        let nearest = db.find_nearest_fix(30.79, 104.09, 50.0).unwrap().unwrap();
+        assert_eq!(nearest.ident, "DOGAR");
+        
+        // 同名航路点取离参考点最近的
+        let dogar = db.get_fix("dogar", 30.5, 104.0).unwrap().unwrap();
+        assert_eq!(dogar.region, "ZU");
+        assert!(db.get_fix("NONE", 30.5, 104.0).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_airway_segment() {
+        let db = sample_db();
+        assert_eq!(db.get_airway("w45").unwrap().len(), 3);
+        
+        let forward = db.get_airway_segment("W45", "SHL", "BOKIR").unwrap();
+        assert_eq!(forward.iter().map(|s| s.to_ident.as_str()).collect::<Vec<_>>(), vec!["DOGAR", "ATVAX", "BOKIR"]);
+        
+        let backward = db.get_airway_segment("W45", "ATVAX", "SHL").unwrap();
+        assert_eq!(backward.iter().map(|s| (s.from_ident.as_str(), s.to_ident.as_str())).collect::<Vec<_>>(),
+            vec![("ATVAX", "DOGAR"), ("DOGAR", "SHL")]);
+        assert_eq!(backward[0].minimum_altitude, 9800);
+        
+        // ATVAX-BOKIR 只能正向飞行
+        assert!(db.get_airway_segment("W45", "BOKIR", "DOGAR").unwrap().is_empty());
+        assert!(db.get_airway_segment("W45", "SHL", "QU").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_ils_by_runway() {
+        let db = sample_db();
+        assert!(db.get_ils("ZUUU", "RWY02L").unwrap().is_some());
+        assert!(db.get_ils("ZUUU", "2l").unwrap().is_some());
+        let ils = db.get_ils("ZUUU", "RW02L").unwrap().unwrap();
+        assert_eq!(ils.ident, "IOO");
+        assert!((ils.frequency - 110.3).abs() < 1e-9);
+        assert_eq!(ils.glideslope, Some(3.0));
+        
+        let loc = db.get_ils("zuuu", "20R").unwrap().unwrap();
+        assert_eq!(loc.glideslope, None);
+        assert!(db.get_ils("ZUUU", "02R").unwrap().is_none());
+        
+        let context = LittleNavmapDB::format_navaids_for_llm(&db.get_airport_ils("ZUUU").unwrap(), &[], "en");
+        assert!(context.contains("Runway 02L: IOO 110.30 MHz, course 20°, glideslope 3.0°"));
+    }
+    
+    #[test]
+    fn test_sid_legs() {
+        let db = sample_db();
+        assert_eq!(db.get_procedures("ZUUU", ProcedureKind::Sid).unwrap().len(), 2);
+        
+        let sid = db.get_procedure("ZUUU", ProcedureKind::Sid, "dogar1a", Some("RWY02L")).unwrap().unwrap();
+        assert_eq!(sid.runway, "02L");
+        assert_eq!(sid.legs.len(), 3);
+        assert_eq!(sid.fixes(), vec!["SHL", "DOGAR"]);
+        assert_eq!(sid.legs[0].altitude, Some(AltitudeConstraint::AtOrAbove(5200)));
+        assert_eq!(sid.legs[2].speed, Some(SpeedConstraint::AtOrBelow(250)));
+        assert_eq!(sid.initial_climb_altitude(), Some(6000));
+        
+        // 只有"不低于"限制时取最高值
+        let sid_02r = db.get_procedure("ZUUU", ProcedureKind::Sid, "DOGAR1A", Some("02R")).unwrap().unwrap();
+        assert_eq!(sid_02r.initial_climb_altitude(), Some(7000));
+        assert!(db.get_procedure("ZUUU", ProcedureKind::Sid, "DOGAR1A", Some("20L")).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_star_transitions_and_approach() {
+        let db = sample_db();
+        let star = db.get_procedure("ZUUU", ProcedureKind::Star, "ATVAX1A", None).unwrap().unwrap();
+        assert_eq!(star.legs[0].altitude, Some(AltitudeConstraint::Between { lower: 9800, upper: 12000 }));
+        assert!(star.legs[1].flyover);
+        assert_eq!(star.initial_climb_altitude(), None);
+        
+        let transition = star.get_transition("bokir").unwrap();
+        assert_eq!(transition.legs.len(), 2);
+        assert_eq!(transition.legs[0].altitude, Some(AltitudeConstraint::AtOrAbove(14000)));
+        
+        // 复飞航段不计入定位点
+        let approaches = db.get_procedures("ZUUU", ProcedureKind::Approach).unwrap();
+        assert_eq!(approaches.len(), 1);
+        assert_eq!(approaches[0].name, "I02L");
+        assert_eq!(approaches[0].approach_type, "ILS");
+        assert_eq!(approaches[0].fixes(), vec!["CD02L", "RW02L"]);
+        assert!(approaches[0].legs[2].missed);
+        
+        let sids = db.get_procedures("ZUUU", ProcedureKind::Sid).unwrap();
+        let stars = db.get_procedures("ZUUU", ProcedureKind::Star).unwrap();
+        let context = LittleNavmapDB::format_procedures_for_llm(&sids, &stars, "zh");
+        assert!(context.contains("- DOGAR1A（跑道 02L）：SHL DOGAR；初始爬升 6000 英尺"), "{}", context);
+        assert!(context.contains("- ATVAX1A（跑道 20R）：ATVAX SHL；过渡：BOKIR"), "{}", context);
+    }
+    
+    #[test]
+    fn test_database_selection_order() {
+        let candidates = vec![
+            (DatabaseType::Navigraph, PathBuf::from("little_navmap_navigraph.sqlite")),
+            (DatabaseType::XPlane, PathBuf::from("little_navmap_xp12.sqlite")),
+            (DatabaseType::XPlane, PathBuf::from("little_navmap_xp11.sqlite")),
+            (DatabaseType::MSFS, PathBuf::from("little_navmap_msfs.sqlite")),
+        ];
+        let paths = |ordered: Vec<(DatabaseType, PathBuf)>| ordered.into_iter().map(|(_, p)| p).collect::<Vec<_>>();
+        
+        // 连接 X-Plane 时优先 X-Plane 数据库，同类型保持 XP12 在前
+        let ordered = paths(LittleNavmapDB::order_candidates(candidates.clone(), None, Some(DatabaseType::XPlane)));
+        assert_eq!(ordered[0], PathBuf::from("little_navmap_xp12.sqlite"));
+        assert_eq!(ordered[1], PathBuf::from("little_navmap_xp11.sqlite"));
+        assert_eq!(ordered[2], PathBuf::from("little_navmap_navigraph.sqlite"));
+        
+        // 未连接模拟器时保持 Navigraph 优先；指定类型时不回退到其他类型
+        assert_eq!(LittleNavmapDB::order_candidates(candidates.clone(), None, None), candidates);
+        let ordered = paths(LittleNavmapDB::order_candidates(candidates, Some(DatabaseType::MSFS), Some(DatabaseType::XPlane)));
+        assert_eq!(ordered, vec![PathBuf::from("little_navmap_msfs.sqlite")]);
+        
+        assert_eq!(DatabaseType::from_file_name(Path::new("/db/little_navmap_msfs24.sqlite")), Some(DatabaseType::MSFS));
+        assert_eq!(DatabaseType::from_file_name(Path::new("custom.sqlite")), None);
+        assert_eq!(DatabaseType::for_simulator("replay"), None);
+    }
+    
+    #[test]
+    fn test_open_with_metadata() {
+        let path = std::env::temp_dir().join(format!("vatc_little_navmap_xp12_{}.sqlite", std::process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE airport (ident TEXT);
+                 CREATE TABLE metadata (
+                     db_version_major INTEGER, db_version_minor INTEGER, last_load_timestamp TEXT,
+                     airac_cycle TEXT, valid_through TEXT, data_source TEXT);
+                 INSERT INTO metadata VALUES (15, 2, '2024-05-20T10:00:00', '2405', '20240613', 'XP12');",
+            ).unwrap();
+        }
+        
+        let config = NavmapConfig { path: Some(path.to_string_lossy().to_string()), db_type: None };
+        assert!(config.validate().is_ok());
+        let db = LittleNavmapDB::select(&config, Some(DatabaseType::MSFS)).unwrap();
+        assert_eq!(db.db_type(), DatabaseType::XPlane, "按文件名推断类型");
+        
+        let info = db.info();
+        assert_eq!(info.airac_cycle.as_deref(), Some("2405"));
+        assert_eq!(info.valid_through.as_deref(), Some("20240613"));
+        assert_eq!(info.data_source.as_deref(), Some("XP12"));
+        
+        let listed = LittleNavmapDB::list_databases(&config, Some(db.path()));
+        assert!(listed[0].active);
+        assert_eq!(listed[0].airac_cycle.as_deref(), Some("2405"));
+        
+        drop(db);
+        let _ = fs::remove_file(&path);
+        assert!(config.validate().is_err());
+        assert!(LittleNavmapDB::open(&path, DatabaseType::XPlane).is_err());
+    }
+    
+    #[test]
+    fn test_taxi_network() {
+        let db = sample_db();
+        let airport = db.get_airport_full("ZUUU").unwrap().to_atc_airport();
+        let network = &airport.taxi_network;
+        assert_eq!(network.segments.len(), 4, "车辆通道不导入");
+        assert_eq!(network.nodes.len(), 5);
+        assert_eq!(network.segments[3].max_size, Some('A'));
+        
+        // 从停机位经 A 滑行到 20R（跑道段名称为对端 02L）
+        let start = TaxiStart::Gate("GATE 12".to_string());
+        let route = airport.plan_taxi_route(&start, &TaxiTarget::Runway("20R".to_string()), &RouteOptions::default()).unwrap();
+        assert_eq!(route.route, vec!["A"]);
+        assert!(route.distance > 1500 && route.distance < 1700, "{}", route.distance);
+        
+        // 02L 入口在跑道另一端，只能沿跑道滑行过去
+        assert_eq!(network.runway_ends.len(), 4);
+        let route = airport.plan_taxi_route(&start, &TaxiTarget::Runway("02L".to_string()), &RouteOptions::default()).unwrap();
+        assert_eq!(route.route, vec!["A"]);
+        assert!(route.distance > 2500, "{}", route.distance);
+    }
+}
@@ -75,19 +75,19 @@ impl LLMClient {
             let vertical_speed_mpm = data.vertical_speed * 0.3048;  // 英尺/分钟转米/分钟
             
             // 判断飞行阶段（简化版）
-            let phase_hint = if data.altitude < 10.0 && data.speed < 5.0 {
+            let phase_hint = if data.on_ground && data.speed < 5.0 {
                 if language == "zh" {
                     "飞机在停机位，可能需要推出许可或滑行指令"
                 } else {
                     "Aircraft is parked, may need pushback clearance or taxi instructions"
                 }
-            } else if data.altitude < 10.0 && data.speed < 40.0 {
+            } else if data.on_ground && data.speed < 40.0 {
                 if language == "zh" {
                     "飞机正在滑行，可能需要跑道进入许可或起飞许可"
                 } else {
                     "Aircraft is taxiing, may need runway entry or takeoff clearance"
                 }
-            } else if data.altitude < 10.0 && data.speed >= 40.0 {
+            } else if data.on_ground && data.speed >= 40.0 {
                 if language == "zh" {
                     "飞机正在起飞滑跑，给予起飞许可"
                 } else {
@@ -1,9 +1,22 @@
+pub mod data_source;
+pub mod config_store;
+pub mod callsign;
+pub mod aircraft;
 pub mod simulator;
 pub mod whisper;
 pub mod llm;
 pub mod tts;
 pub mod msfs;
+pub mod msfs_bridge;
+pub mod msfs_supervisor;
+pub mod replay;
+pub mod recorder;
+pub mod traffic;
 pub mod flight_phase;
 pub mod atc_database;
+pub mod taxi_network;
+pub mod apt_dat;
+pub mod our_airports;
+pub mod airport_store;
 pub mod little_navmap;
 pub mod model_manager;
@@ -1,13 +1,75 @@
 use std::error::Error;
+use std::net::{SocketAddr, UdpSocket};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use super::config_store;
+use super::data_source::{ConnectionHealth, ConnectionStatus, FlightDataSource};
+use super::msfs_bridge::BridgeSession;
+use super::msfs_supervisor::{self, BridgeCommand, BridgeProcessState, BridgeSupervisor};
+use super::simulator::FlightData;
+use super::recorder::{self, SharedRecorder};
+use super::traffic::SharedTraffic;
+
+/// 桥接脚本发送数据的本地端口
+pub const MSFS_BRIDGE_PORT: u16 = 49001;
+
+/// 超过该时间未收到桥接消息（包括心跳）视为中断
+const BRIDGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// MSFS 桥接配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MSFSConfig {
+    pub python_path: String,    // Python 解释器（PATH 中的命令名或完整路径）
+    pub script_path: String,    // 桥接脚本（相对路径依次在工作目录和程序目录下查找）
+    pub auto_restart: bool,     // 桥接进程退出后自动重启
+}
+
+impl Default for MSFSConfig {
+    fn default() -> Self {
+        MSFSConfig {
+            python_path: "python".to_string(),
+            script_path: "scripts/msfs_bridge.py".to_string(),
+            auto_restart: true,
+        }
+    }
+}
+
+impl MSFSConfig {
+    /// 读取上次保存的配置（不存在或无法解析时使用默认值）
+    ///
+    /// 不在此处校验：桥接脚本可能暂时不在，连接时再由 `validate` 报错，避免丢失用户设置
+    pub fn load() -> Self {
+        config_store::load_json("msfs_config.json").unwrap_or_default()
+    }
+    
+    /// 保存配置
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        config_store::save_json("msfs_config.json", self)
+    }
+    
+    /// 校验配置并解析桥接脚本路径
+    pub fn validate(&self) -> Result<PathBuf, String> {
+        if self.python_path.trim().is_empty() {
+            return Err("Python 解释器路径不能为空".to_string());
+        }
+        
+        msfs_supervisor::resolve_path(self.script_path.trim())
+            .ok_or_else(|| format!("找不到 MSFS 桥接脚本：{}", self.script_path))
+    }
+}
 
 // SimConnect 数据结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MSFSData {
-    pub callsign: String,
+    pub callsign: String,   // 模拟器报告的机尾号/ATC ID（播报用呼号由 callsign 模块解析）
+    pub atc_airline: String,        // 模拟器中设置的航空公司（MSFS ATC_AIRLINE）
+    pub atc_flight_number: String,  // 模拟器中设置的航班号（MSFS ATC_FLIGHT_NUMBER）
+    pub aircraft_type: String,      // ICAO 机型代码（MSFS ATC_MODEL）
     pub altitude: f64,      // 英尺
     pub speed: f64,         // 节
     pub heading: f64,       // 度
@@ -15,12 +77,30 @@ pub struct MSFSData {
     pub latitude: f64,      // 度
     pub longitude: f64,     // 度
     pub on_ground: bool,    // 是否在地面
+    pub altitude_agl: f64,  // 离地高度（英尺）
+    pub ground_speed: f64,  // 地速（节）
+    pub gear_ratio: f64,    // 起落架放出比例
+    pub flap_ratio: f64,    // 襟翼手柄位置
+    pub com1_active: f64,   // MHz
+    pub com1_standby: f64,  // MHz
+    pub com2_active: f64,   // MHz
+    pub com2_standby: f64,  // MHz
+    pub nav1_active: f64,   // MHz
+    pub nav1_standby: f64,  // MHz
+    pub nav2_active: f64,   // MHz
+    pub nav2_standby: f64,  // MHz
+    pub transponder_code: u16,
+    pub transponder_mode: u8,
+    pub parking_brake: f64, // 停机刹车
 }
 
 impl Default for MSFSData {
     fn default() -> Self {
         MSFSData {
             callsign: String::new(),
+            atc_airline: String::new(),
+            atc_flight_number: String::new(),
+            aircraft_type: String::new(),
             altitude: 0.0,
             speed: 0.0,
             heading: 0.0,
@@ -28,6 +108,21 @@ impl Default for MSFSData {
             latitude: 0.0,
             longitude: 0.0,
             on_ground: true,
+            altitude_agl: 0.0,
+            ground_speed: 0.0,
+            gear_ratio: 1.0,
+            flap_ratio: 0.0,
+            com1_active: 0.0,
+            com1_standby: 0.0,
+            com2_active: 0.0,
+            com2_standby: 0.0,
+            nav1_active: 0.0,
+            nav1_standby: 0.0,
+            nav2_active: 0.0,
+            nav2_standby: 0.0,
+            transponder_code: 0,
+            transponder_mode: 0,
+            parking_brake: 0.0,
         }
     }
 }
@@ -35,73 +130,100 @@ impl Default for MSFSData {
 pub struct MSFSConnection {
     flight_data: Arc<Mutex<MSFSData>>,
     running: Arc<Mutex<bool>>,
-    python_process: Option<std::process::Child>,
+    receiver: Option<JoinHandle<()>>,
+    supervisor: Option<BridgeSupervisor>,
+    recorder: SharedRecorder,
+    traffic: SharedTraffic,
+    session: Arc<Mutex<BridgeSession>>,
 }
 
 impl MSFSConnection {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
+    pub fn new(recorder: SharedRecorder, traffic: SharedTraffic) -> Result<Self, Box<dyn Error>> {
         let flight_data = Arc::new(Mutex::new(MSFSData::default()));
         let running = Arc::new(Mutex::new(false));
         
         Ok(MSFSConnection {
             flight_data,
             running,
-            python_process: None,
+            receiver: None,
+            supervisor: None,
+            recorder,
+            traffic,
+            session: Arc::new(Mutex::new(BridgeSession::new())),
         })
     }
     
-    pub fn connect(&mut self) -> Result<(), Box<dyn Error>> {
-        // 检查 Python 脚本是否存在
-        let script_path = "scripts/msfs_bridge.py";
-        if !std::path::Path::new(script_path).exists() {
-            return Err("MSFS bridge script not found. Please run setup first.".into());
-        }
+    pub fn connect(&mut self, config: &MSFSConfig) -> Result<(), Box<dyn Error>> {
+        let script_path = config.validate()?;
         
-        // 启动 Python 桥接进程
-        let process = std::process::Command::new("python")
-            .arg(script_path)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()?;
+        // 先绑定端口再启动桥接，避免丢失握手消息
+        self.listen(MSFS_BRIDGE_PORT)?;
         
-        self.python_process = Some(process);
-        *self.running.lock().unwrap() = true;
+        // 启动 Python 桥接进程（-u 关闭输出缓冲，日志实时显示）
+        let supervisor = BridgeSupervisor::start(BridgeCommand {
+            program: config.python_path.trim().to_string(),
+            args: vec!["-u".to_string(), script_path.to_string_lossy().to_string()],
+            auto_restart: config.auto_restart,
+        });
         
-        // 启动数据接收线程
-        self.start_receiver()?;
+        match supervisor {
+            Ok(supervisor) => self.supervisor = Some(supervisor),
+            Err(e) => {
+                *self.running.lock().unwrap() = false;
+                return Err(e);
+            }
+        }
         
-        println!("MSFS SimConnect bridge started");
+        println!("MSFS SimConnect bridge started: {:?}", script_path);
         Ok(())
     }
     
-    fn start_receiver(&self) -> Result<(), Box<dyn Error>> {
+    /// 绑定本地端口并启动数据接收线程，返回实际监听地址（port 为 0 时由系统分配）
+    pub fn listen(&mut self, port: u16) -> Result<SocketAddr, Box<dyn Error>> {
+        let socket = UdpSocket::bind(("127.0.0.1", port))?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+        let local_addr = socket.local_addr()?;
+        
+        *self.session.lock().unwrap() = BridgeSession::new();
+        *self.running.lock().unwrap() = true;
+        self.start_receiver(socket);
+        
+        println!("MSFS bridge listener started on {}", local_addr);
+        Ok(local_addr)
+    }
+    
+    fn start_receiver(&mut self, socket: UdpSocket) {
         let flight_data = self.flight_data.clone();
         let running = self.running.clone();
+        let recorder = self.recorder.clone();
+        let session = self.session.clone();
+        let traffic = self.traffic.clone();
         
-        thread::spawn(move || {
-            use std::net::UdpSocket;
-            
-            // 监听 Python 桥接发送的数据
-            let socket = match UdpSocket::bind("127.0.0.1:49001") {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Failed to bind UDP socket: {}", e);
-                    return;
-                }
-            };
-            
-            socket.set_read_timeout(Some(Duration::from_secs(1))).ok();
-            
-            let mut buf = [0u8; 1024];
+        self.receiver = Some(thread::spawn(move || {
+            // 交通快照可能较大，按 UDP 数据报上限分配
+            let mut buf = vec![0u8; 65536];
             
             while *running.lock().unwrap() {
-                match socket.recv(&mut buf) {
-                    Ok(size) => {
-                        if let Ok(json_str) = std::str::from_utf8(&buf[..size]) {
-                            if let Ok(data) = serde_json::from_str::<MSFSData>(json_str) {
-                                *flight_data.lock().unwrap() = data;
+                match socket.recv_from(&mut buf) {
+                    Ok((size, src)) => {
+                        let output = session.lock().unwrap().handle(&buf[..size]);
+                        
+                        // 握手回复发回给桥接
+                        if let Some(reply) = output.reply {
+                            if let Err(e) = socket.send_to(&reply.to_bytes(), src) {
+                                eprintln!("Failed to reply to MSFS bridge: {}", e);
                             }
                         }
+                        
+                        if let Some(data) = output.telemetry {
+                            // 写入飞行记录（如果已开启）
+                            recorder::record_sample(&recorder, &FlightData::from(data.clone()));
+                            *flight_data.lock().unwrap() = data;
+                        }
+                        
+                        if let Some(targets) = output.traffic {
+                            traffic.lock().unwrap().replace("msfs-", targets);
+                        }
                     }
                     Err(e) => {
                         if e.kind() != std::io::ErrorKind::WouldBlock 
@@ -113,89 +235,202 @@ impl MSFSConnection {
             }
             
             println!("MSFS data receiver stopped");
-        });
-        
-        Ok(())
+        }));
     }
     
     pub fn get_flight_data(&self) -> Result<MSFSData, Box<dyn Error>> {
-        let mut data = self.flight_data.lock().unwrap().clone();
-        
-        // 如果没有呼号，生成一个
-        if data.callsign.is_empty() {
-            data.callsign = format!("MSFS{:03}", (data.altitude / 100.0) as u32 % 1000);
+        // 协议不兼容时不返回过期数据，而是明确报错
+        if let Some(reason) = self.session.lock().unwrap().incompatibility() {
+            return Err(reason.into());
         }
         
-        Ok(data)
+        Ok(self.flight_data.lock().unwrap().clone())
     }
     
     pub fn disconnect(&mut self) {
         *self.running.lock().unwrap() = false;
         
-        // 终止 Python 进程
-        if let Some(mut process) = self.python_process.take() {
-            let _ = process.kill();
-            let _ = process.wait();
+        // 停止监管并终止 Python 进程
+        if let Some(mut supervisor) = self.supervisor.take() {
+            supervisor.stop();
+        }
+        
+        // 等待接收线程退出（最多一个读超时），线程持有的套接字随之关闭
+        if let Some(receiver) = self.receiver.take() {
+            let _ = receiver.join();
         }
         
         println!("MSFS SimConnect disconnected");
     }
 }
 
+impl From<MSFSData> for FlightData {
+    fn from(data: MSFSData) -> Self {
+        FlightData {
+            callsign: data.callsign,
+            atc_airline: data.atc_airline,
+            atc_flight_number: data.atc_flight_number,
+            aircraft_type: data.aircraft_type,
+            altitude: data.altitude,
+            speed: data.speed,
+            heading: data.heading,
+            vertical_speed: data.vertical_speed,
+            latitude: data.latitude,
+            longitude: data.longitude,
+            on_ground: data.on_ground,
+            altitude_agl: data.altitude_agl,
+            ground_speed: data.ground_speed,
+            gear_ratio: data.gear_ratio,
+            flap_ratio: data.flap_ratio,
+            com1_active: data.com1_active,
+            com1_standby: data.com1_standby,
+            com2_active: data.com2_active,
+            com2_standby: data.com2_standby,
+            nav1_active: data.nav1_active,
+            nav1_standby: data.nav1_standby,
+            nav2_active: data.nav2_active,
+            nav2_standby: data.nav2_standby,
+            transponder_code: data.transponder_code,
+            transponder_mode: data.transponder_mode,
+            parking_brake: data.parking_brake,
+        }
+    }
+}
+
+impl FlightDataSource for MSFSConnection {
+    fn sim_type(&self) -> &'static str {
+        "msfs"
+    }
+    
+    fn display_name(&self) -> &'static str {
+        "MSFS"
+    }
+    
+    fn get_flight_data(&self) -> Result<FlightData, Box<dyn Error>> {
+        MSFSConnection::get_flight_data(self).map(FlightData::from)
+    }
+    
+    fn status(&self) -> ConnectionStatus {
+        let session = self.session.lock().unwrap();
+        let bridge = self.supervisor.as_ref().map(|supervisor| supervisor.status());
+        let bridge_failure = bridge.as_ref().and_then(|bridge| match &bridge.state {
+            BridgeProcessState::Failed { reason } => Some(reason.clone()),
+            _ => None,
+        });
+        
+        let health = if !*self.running.lock().unwrap() {
+            ConnectionHealth::Disconnected
+        } else if session.incompatibility().is_some() {
+            ConnectionHealth::Incompatible
+        } else {
+            session.health(BRIDGE_TIMEOUT)
+        };
+        
+        ConnectionStatus {
+            sim_type: self.sim_type().to_string(),
+            health,
+            last_data_age_secs: session.last_message_age().map(|age| age.as_secs_f64()),
+            resubscribe_count: 0,
+            error: session.incompatibility()
+                .map(String::from)
+                .or(bridge_failure)
+                .or_else(|| session.last_error().map(String::from)),
+            bridge,
+        }
+    }
+    
+    fn disconnect(&mut self) {
+        MSFSConnection::disconnect(self);
+    }
+}
+
 impl Drop for MSFSConnection {
     fn drop(&mut self) {
         self.disconnect();
     }
 }
 
-// 为了兼容性，实现 Serialize
-impl serde::Serialize for MSFSData {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("MSFSData", 8)?;
-        state.serialize_field("callsign", &self.callsign)?;
-        state.serialize_field("altitude", &self.altitude)?;
-        state.serialize_field("speed", &self.speed)?;
-        state.serialize_field("heading", &self.heading)?;
-        state.serialize_field("vertical_speed", &self.vertical_speed)?;
-        state.serialize_field("latitude", &self.latitude)?;
-        state.serialize_field("longitude", &self.longitude)?;
-        state.serialize_field("on_ground", &self.on_ground)?;
-        state.end()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::msfs_bridge::harness::FakeBridge;
+    use crate::modules::msfs_bridge::{BridgeMessage, PROTOCOL_VERSION};
+    use crate::modules::traffic::{TrafficTable, TrafficTarget};
+    
+    fn listening_connection() -> (MSFSConnection, SocketAddr) {
+        let traffic = Arc::new(Mutex::new(TrafficTable::new()));
+        let mut connection = MSFSConnection::new(Arc::new(Mutex::new(None)), traffic).unwrap();
+        let addr = connection.listen(0).unwrap();
+        (connection, addr)
     }
-}
-
-// 为了兼容性，实现 Deserialize
-impl<'de> serde::Deserialize<'de> for MSFSData {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(serde::Deserialize)]
-        struct Helper {
-            callsign: String,
-            altitude: f64,
-            speed: f64,
-            heading: f64,
-            vertical_speed: f64,
-            latitude: f64,
-            longitude: f64,
-            on_ground: bool,
+    
+    /// 等待接收线程处理完数据报
+    fn wait_for<F: Fn() -> bool>(condition: F) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
         }
+        panic!("condition not met in time");
+    }
+    
+    #[test]
+    fn test_fake_bridge_end_to_end() {
+        let (connection, addr) = listening_connection();
+        let mut bridge = FakeBridge::new(addr);
         
-        let helper = Helper::deserialize(deserializer)?;
-        Ok(MSFSData {
-            callsign: helper.callsign,
-            altitude: helper.altitude,
-            speed: helper.speed,
-            heading: helper.heading,
-            vertical_speed: helper.vertical_speed,
-            latitude: helper.latitude,
-            longitude: helper.longitude,
-            on_ground: helper.on_ground,
-        })
+        assert_eq!(connection.status().health, ConnectionHealth::Waiting);
+        assert_eq!(
+            bridge.handshake(PROTOCOL_VERSION),
+            Some(BridgeMessage::Welcome { protocol_version: PROTOCOL_VERSION })
+        );
+        
+        bridge.telemetry(MSFSData { callsign: "CES5678".to_string(), altitude: 3500.0, ..MSFSData::default() });
+        bridge.skip(2);
+        bridge.heartbeat();
+        wait_for(|| connection.session.lock().unwrap().dropped() == 2);
+        
+        bridge.traffic(vec![TrafficTarget { id: "msfs-12".to_string(), latitude: 1.0, ..TrafficTarget::default() }]);
+        wait_for(|| !connection.traffic.lock().unwrap().targets().is_empty());
+        
+        let data = FlightDataSource::get_flight_data(&connection).unwrap();
+        assert_eq!(data.callsign, "CES5678");
+        assert_eq!(data.altitude, 3500.0);
+        assert_eq!(connection.status().health, ConnectionHealth::Receiving);
+    }
+    
+    #[test]
+    fn test_fake_bridge_version_mismatch() {
+        let (connection, addr) = listening_connection();
+        let mut bridge = FakeBridge::new(addr);
+        
+        let reply = bridge.handshake(PROTOCOL_VERSION + 1);
+        assert!(matches!(reply, Some(BridgeMessage::Reject { protocol_version: PROTOCOL_VERSION, .. })));
+        
+        let status = connection.status();
+        assert_eq!(status.health, ConnectionHealth::Incompatible);
+        assert!(status.error.unwrap().contains("scripts/msfs_bridge.py"));
+        assert!(connection.get_flight_data().is_err());
+    }
+    
+    #[test]
+    fn test_legacy_bridge_is_reported() {
+        let (connection, addr) = listening_connection();
+        let bridge = FakeBridge::new(addr);
+        
+        bridge.send_raw(&serde_json::to_vec(&MSFSData::default()).unwrap());
+        wait_for(|| connection.status().health == ConnectionHealth::Incompatible);
+        assert!(connection.get_flight_data().is_err());
+    }
+    
+    #[test]
+    fn test_disconnect_releases_port() {
+        let (mut connection, addr) = listening_connection();
+        connection.disconnect();
+        
+        // disconnect 返回后同一端口可立即重新绑定
+        let mut next = MSFSConnection::new(Arc::new(Mutex::new(None)), connection.traffic.clone()).unwrap();
+        assert_eq!(next.listen(addr.port()).unwrap(), addr);
     }
 }
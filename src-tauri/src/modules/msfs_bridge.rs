@@ -0,0 +1,369 @@
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use super::data_source::ConnectionHealth;
+use super::msfs::MSFSData;
+use super::traffic::TrafficTarget;
+
+/// 桥接协议版本，桥接脚本与应用必须一致
+///
+/// 协议说明见 docs/MSFS_INTEGRATION.md，修改消息格式时必须递增。
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// 桥接协议消息（UDP 数据报，每个数据报一个 JSON 对象，以 `type` 字段区分）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeMessage {
+    /// 桥接 → 应用：握手，桥接启动后周期性发送直到收到 Welcome
+    Hello {
+        protocol_version: u32,
+        #[serde(default)]
+        bridge_version: String,
+    },
+    /// 应用 → 桥接：握手成功
+    Welcome { protocol_version: u32 },
+    /// 应用 → 桥接：协议版本不兼容，桥接应退出
+    Reject { protocol_version: u32, reason: String },
+    /// 桥接 → 应用：飞行数据
+    Telemetry { seq: u64, data: Box<MSFSData> },
+    /// 桥接 → 应用：AI/联机飞机的完整快照（v2）
+    Traffic { seq: u64, targets: Vec<TrafficTarget> },
+    /// 桥接 → 应用：心跳（模拟器暂停、无数据时保持连接）
+    Heartbeat { seq: u64 },
+    /// 桥接 → 应用：桥接端错误（如 SimConnect 连接中断）
+    Error { message: String },
+}
+
+impl BridgeMessage {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("bridge message is always serializable")
+    }
+}
+
+/// 会话状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionState {
+    AwaitingHello,          // 等待桥接握手
+    Established,            // 握手成功
+    Incompatible(String),   // 协议不兼容（附带错误说明）
+}
+
+/// 处理一个数据报的结果
+#[derive(Debug, Default)]
+pub struct SessionOutput {
+    pub reply: Option<BridgeMessage>,   // 需要回复给桥接的消息
+    pub telemetry: Option<MSFSData>,    // 新的飞行数据
+    pub traffic: Option<Vec<TrafficTarget>>, // 新的交通快照
+}
+
+/// 桥接会话 - 协议状态机（与网络无关，便于测试）
+#[derive(Debug)]
+pub struct BridgeSession {
+    state: SessionState,
+    bridge_version: String,
+    last_seq: Option<u64>,
+    dropped: u64,
+    started_at: Instant,
+    last_message: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl BridgeSession {
+    pub fn new() -> Self {
+        BridgeSession {
+            state: SessionState::AwaitingHello,
+            bridge_version: String::new(),
+            last_seq: None,
+            dropped: 0,
+            started_at: Instant::now(),
+            last_message: None,
+            last_error: None,
+        }
+    }
+
+    /// 处理一个收到的数据报
+    pub fn handle(&mut self, payload: &[u8]) -> SessionOutput {
+        let message = match serde_json::from_slice::<BridgeMessage>(payload) {
+            Ok(message) => message,
+            Err(e) => {
+                self.handle_unknown(payload, e);
+                return SessionOutput::default();
+            }
+        };
+
+        self.last_message = Some(Instant::now());
+
+        match message {
+            BridgeMessage::Hello { protocol_version, bridge_version } => {
+                self.handle_hello(protocol_version, bridge_version)
+            }
+            BridgeMessage::Telemetry { seq, data } => {
+                if self.accept_seq(seq) {
+                    SessionOutput { telemetry: Some(*data), ..SessionOutput::default() }
+                } else {
+                    SessionOutput::default()
+                }
+            }
+            BridgeMessage::Traffic { seq, targets } => {
+                if self.accept_seq(seq) {
+                    SessionOutput { traffic: Some(targets), ..SessionOutput::default() }
+                } else {
+                    SessionOutput::default()
+                }
+            }
+            BridgeMessage::Heartbeat { seq } => {
+                self.accept_seq(seq);
+                SessionOutput::default()
+            }
+            BridgeMessage::Error { message } => {
+                eprintln!("MSFS bridge error: {}", message);
+                self.last_error = Some(message);
+                SessionOutput::default()
+            }
+            // 只有应用会发送以下消息，忽略
+            BridgeMessage::Welcome { .. } | BridgeMessage::Reject { .. } => SessionOutput::default(),
+        }
+    }
+
+    fn handle_hello(&mut self, protocol_version: u32, bridge_version: String) -> SessionOutput {
+        if protocol_version != PROTOCOL_VERSION {
+            let reason = format!(
+                "MSFS 桥接协议版本不兼容：桥接脚本 v{}（{}），应用需要 v{}。请更新 scripts/msfs_bridge.py",
+                protocol_version, bridge_version, PROTOCOL_VERSION
+            );
+            eprintln!("{}", reason);
+            self.state = SessionState::Incompatible(reason.clone());
+            return SessionOutput {
+                reply: Some(BridgeMessage::Reject { protocol_version: PROTOCOL_VERSION, reason }),
+                ..SessionOutput::default()
+            };
+        }
+
+        // 桥接重启后会重新握手，序号从头开始
+        if self.state != SessionState::Established {
+            println!("MSFS bridge handshake complete (protocol v{}, bridge {})", protocol_version, bridge_version);
+        }
+        self.state = SessionState::Established;
+        self.bridge_version = bridge_version;
+        self.last_seq = None;
+        self.last_error = None;
+
+        SessionOutput {
+            reply: Some(BridgeMessage::Welcome { protocol_version: PROTOCOL_VERSION }),
+            ..SessionOutput::default()
+        }
+    }
+
+    /// 检查序号：丢弃握手前、重复或乱序的消息，统计丢包
+    fn accept_seq(&mut self, seq: u64) -> bool {
+        if self.state != SessionState::Established {
+            return false;
+        }
+
+        if let Some(last) = self.last_seq {
+            if seq <= last {
+                return false;
+            }
+            self.dropped += seq - last - 1;
+        }
+
+        self.last_seq = Some(seq);
+        true
+    }
+
+    fn handle_unknown(&mut self, payload: &[u8], error: serde_json::Error) {
+        // 旧版桥接脚本直接发送飞行数据 JSON，没有 type 字段
+        let legacy = serde_json::from_slice::<serde_json::Value>(payload)
+            .map(|value| value.get("type").is_none() && value.get("altitude").is_some())
+            .unwrap_or(false);
+
+        if legacy {
+            if !matches!(self.state, SessionState::Incompatible(_)) {
+                let reason = "MSFS 桥接脚本版本过旧（未使用版本化协议），请更新 scripts/msfs_bridge.py".to_string();
+                eprintln!("{}", reason);
+                self.state = SessionState::Incompatible(reason);
+            }
+        } else {
+            eprintln!("Invalid MSFS bridge message: {}", error);
+        }
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    /// 不兼容时的错误说明
+    pub fn incompatibility(&self) -> Option<&str> {
+        match &self.state {
+            SessionState::Incompatible(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    pub fn bridge_version(&self) -> &str {
+        &self.bridge_version
+    }
+
+    /// 累计丢失的消息数
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// 桥接端最近一次报告的错误
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// 距离上次收到消息的时间
+    pub fn last_message_age(&self) -> Option<Duration> {
+        self.last_message.map(|t| t.elapsed())
+    }
+
+    pub fn health(&self, timeout: Duration) -> ConnectionHealth {
+        let silence = self.last_message.unwrap_or(self.started_at).elapsed();
+
+        match self.state {
+            _ if silence >= timeout => ConnectionHealth::Stale,
+            SessionState::Established => ConnectionHealth::Receiving,
+            _ => ConnectionHealth::Waiting,
+        }
+    }
+}
+
+impl Default for BridgeSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 测试用的模拟桥接，替代 Python 脚本，在没有 MSFS 的环境中验证协议
+#[cfg(test)]
+pub mod harness {
+    use super::*;
+    use std::net::{SocketAddr, UdpSocket};
+
+    pub struct FakeBridge {
+        socket: UdpSocket,
+        target: SocketAddr,
+        seq: u64,
+    }
+
+    impl FakeBridge {
+        pub fn new(target: SocketAddr) -> Self {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+            FakeBridge { socket, target, seq: 0 }
+        }
+
+        pub fn send(&self, message: &BridgeMessage) {
+            self.send_raw(&message.to_bytes());
+        }
+
+        pub fn send_raw(&self, payload: &[u8]) {
+            self.socket.send_to(payload, self.target).unwrap();
+        }
+
+        /// 等待应用的回复
+        pub fn recv(&self) -> Option<BridgeMessage> {
+            let mut buf = [0u8; 4096];
+            let size = self.socket.recv(&mut buf).ok()?;
+            serde_json::from_slice(&buf[..size]).ok()
+        }
+
+        /// 发送握手并返回应用的回复
+        pub fn handshake(&mut self, protocol_version: u32) -> Option<BridgeMessage> {
+            self.seq = 0;
+            self.send(&BridgeMessage::Hello {
+                protocol_version,
+                bridge_version: "fake-bridge".to_string(),
+            });
+            self.recv()
+        }
+
+        pub fn telemetry(&mut self, data: MSFSData) {
+            self.seq += 1;
+            self.send(&BridgeMessage::Telemetry { seq: self.seq, data: Box::new(data) });
+        }
+
+        pub fn traffic(&mut self, targets: Vec<TrafficTarget>) {
+            self.seq += 1;
+            self.send(&BridgeMessage::Traffic { seq: self.seq, targets });
+        }
+
+        pub fn heartbeat(&mut self) {
+            self.seq += 1;
+            self.send(&BridgeMessage::Heartbeat { seq: self.seq });
+        }
+
+        /// 模拟丢包：跳过若干序号
+        pub fn skip(&mut self, count: u64) {
+            self.seq += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(version: u32) -> Vec<u8> {
+        BridgeMessage::Hello { protocol_version: version, bridge_version: "test".to_string() }.to_bytes()
+    }
+
+    fn telemetry(seq: u64, altitude: f64) -> Vec<u8> {
+        BridgeMessage::Telemetry { seq, data: Box::new(MSFSData { altitude, ..MSFSData::default() }) }.to_bytes()
+    }
+
+    #[test]
+    fn test_handshake_and_sequence() {
+        let mut session = BridgeSession::new();
+
+        // 握手前的数据被丢弃
+        assert!(session.handle(&telemetry(1, 1000.0)).telemetry.is_none());
+
+        let output = session.handle(&hello(PROTOCOL_VERSION));
+        assert_eq!(output.reply, Some(BridgeMessage::Welcome { protocol_version: PROTOCOL_VERSION }));
+        assert_eq!(session.state(), &SessionState::Established);
+
+        assert_eq!(session.handle(&telemetry(1, 1000.0)).telemetry.unwrap().altitude, 1000.0);
+        assert_eq!(session.handle(&telemetry(4, 1200.0)).telemetry.unwrap().altitude, 1200.0);
+        assert_eq!(session.dropped(), 2);
+
+        // 重复和乱序的数据被丢弃
+        assert!(session.handle(&telemetry(4, 1200.0)).telemetry.is_none());
+        assert!(session.handle(&telemetry(3, 1100.0)).telemetry.is_none());
+
+        // 桥接重启后重新握手，序号重新开始
+        session.handle(&hello(PROTOCOL_VERSION));
+        assert!(session.handle(&telemetry(1, 0.0)).telemetry.is_some());
+    }
+
+    #[test]
+    fn test_version_mismatch_is_rejected() {
+        let mut session = BridgeSession::new();
+
+        let output = session.handle(&hello(PROTOCOL_VERSION + 1));
+        assert!(matches!(output.reply, Some(BridgeMessage::Reject { .. })));
+        assert!(session.incompatibility().unwrap().contains("版本不兼容"));
+        assert!(session.handle(&telemetry(1, 1000.0)).telemetry.is_none());
+    }
+
+    #[test]
+    fn test_legacy_bridge_is_reported() {
+        let mut session = BridgeSession::new();
+
+        session.handle(br#"{"callsign": "", "altitude": 1000.0, "speed": 120.0}"#);
+        assert!(session.incompatibility().unwrap().contains("版本过旧"));
+    }
+
+    #[test]
+    fn test_heartbeat_and_errors() {
+        let mut session = BridgeSession::new();
+        session.handle(&hello(PROTOCOL_VERSION));
+        session.handle(&BridgeMessage::Heartbeat { seq: 1 }.to_bytes());
+        session.handle(&BridgeMessage::Error { message: "SimConnect closed".to_string() }.to_bytes());
+
+        assert_eq!(session.last_error(), Some("SimConnect closed"));
+        assert_eq!(session.health(Duration::from_secs(5)), ConnectionHealth::Receiving);
+        assert_eq!(session.health(Duration::ZERO), ConnectionHealth::Stale);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+
+/// 保留的桥接 stderr 行数（用于在界面上显示最近的错误）
+const STDERR_HISTORY: usize = 20;
+
+/// 重启等待时间：从 1 秒开始翻倍，最长 30 秒
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 进程稳定运行超过该时间后，重启等待时间恢复为初始值
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// 桥接脚本因协议版本不兼容而退出时的退出码（见 scripts/msfs_bridge.py）
+const EXIT_REJECTED: i32 = 2;
+
+/// 桥接进程状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BridgeProcessState {
+    Running { pid: u32 },
+    Restarting { attempt: u32, retry_in_secs: u64 },   // 进程已退出，等待重启
+    Failed { reason: String },                         // 无法启动或不应重启
+    Stopped,
+}
+
+/// 桥接进程状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeStatus {
+    #[serde(flatten)]
+    pub state: BridgeProcessState,
+    pub restart_count: u32,
+    pub last_exit: Option<String>,      // 上次退出原因
+    pub recent_stderr: Vec<String>,     // 最近的 stderr 输出
+}
+
+/// 启动参数
+#[derive(Debug, Clone)]
+pub struct BridgeCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub auto_restart: bool,
+}
+
+impl BridgeCommand {
+    fn spawn(&self) -> Result<Child, Box<dyn Error>> {
+        let child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("无法启动 {}: {}", self.program, e))?;
+        Ok(child)
+    }
+}
+
+struct Shared {
+    state: BridgeProcessState,
+    restart_count: u32,
+    last_exit: Option<String>,
+    recent_stderr: VecDeque<String>,
+}
+
+/// 桥接进程监管 - 读取输出、检测退出并按退避策略重启
+pub struct BridgeSupervisor {
+    shared: Arc<Mutex<Shared>>,
+    running: Arc<Mutex<bool>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BridgeSupervisor {
+    /// 启动桥接进程（首次启动失败时直接返回错误）
+    pub fn start(command: BridgeCommand) -> Result<Self, Box<dyn Error>> {
+        let child = command.spawn()?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            state: BridgeProcessState::Running { pid: child.id() },
+            restart_count: 0,
+            last_exit: None,
+            recent_stderr: VecDeque::new(),
+        }));
+        let running = Arc::new(Mutex::new(true));
+
+        let handle = {
+            let shared = shared.clone();
+            let running = running.clone();
+            thread::spawn(move || supervise(command, child, shared, running))
+        };
+
+        Ok(BridgeSupervisor {
+            shared,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn status(&self) -> BridgeStatus {
+        let shared = self.shared.lock().unwrap();
+        BridgeStatus {
+            state: shared.state.clone(),
+            restart_count: shared.restart_count,
+            last_exit: shared.last_exit.clone(),
+            recent_stderr: shared.recent_stderr.iter().cloned().collect(),
+        }
+    }
+
+    /// 停止监管并终止桥接进程
+    pub fn stop(&mut self) {
+        *self.running.lock().unwrap() = false;
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for BridgeSupervisor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn supervise(command: BridgeCommand, mut child: Child, shared: Arc<Mutex<Shared>>, running: Arc<Mutex<bool>>) {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let started_at = Instant::now();
+        let drains = drain_output(&mut child, &shared);
+
+        // 等待进程退出或收到停止请求
+        let exit = loop {
+            if !*running.lock().unwrap() {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) => thread::sleep(Duration::from_millis(200)),
+                Err(e) => {
+                    eprintln!("Failed to poll MSFS bridge process: {}", e);
+                    let _ = child.kill();
+                    break child.wait().ok();
+                }
+            }
+        };
+
+        let status = match exit {
+            Some(status) => status,
+            None => break,
+        };
+
+        // 读完剩余输出，保证退出前的 stderr 都已记录
+        for drain in drains {
+            let _ = drain.join();
+        }
+
+        let reason = match status.code() {
+            Some(code) => format!("退出码 {}", code),
+            None => "被信号终止".to_string(),
+        };
+        eprintln!("MSFS bridge exited ({})", reason);
+
+        {
+            let mut shared = shared.lock().unwrap();
+            shared.last_exit = Some(reason.clone());
+
+            if status.code() == Some(EXIT_REJECTED) {
+                shared.state = BridgeProcessState::Failed {
+                    reason: "桥接脚本协议版本不兼容，请更新 scripts/msfs_bridge.py".to_string(),
+                };
+                return;
+            }
+            if !command.auto_restart {
+                shared.state = BridgeProcessState::Failed { reason: format!("桥接进程已退出（{}）", reason) };
+                return;
+            }
+        }
+
+        if started_at.elapsed() >= STABLE_RUN {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        {
+            let mut shared = shared.lock().unwrap();
+            shared.restart_count += 1;
+            shared.state = BridgeProcessState::Restarting {
+                attempt: shared.restart_count,
+                retry_in_secs: backoff.as_secs(),
+            };
+        }
+
+        if !sleep_while_running(backoff, &running) {
+            break;
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+
+        child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Failed to restart MSFS bridge: {}", e);
+                shared.lock().unwrap().state = BridgeProcessState::Failed { reason: e.to_string() };
+                return;
+            }
+        };
+
+        println!("MSFS bridge restarted (pid {})", child.id());
+        shared.lock().unwrap().state = BridgeProcessState::Running { pid: child.id() };
+    }
+
+    shared.lock().unwrap().state = BridgeProcessState::Stopped;
+}
+
+/// 启动读取线程，持续读取 stdout/stderr，避免管道缓冲区写满导致桥接阻塞
+fn drain_output(child: &mut Child, shared: &Arc<Mutex<Shared>>) -> Vec<JoinHandle<()>> {
+    let mut drains = Vec::new();
+
+    if let Some(stdout) = child.stdout.take() {
+        drains.push(thread::spawn(move || {
+            for line in lines(stdout) {
+                println!("[msfs bridge] {}", line);
+            }
+        }));
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        let shared = shared.clone();
+        drains.push(thread::spawn(move || {
+            for line in lines(stderr) {
+                eprintln!("[msfs bridge] {}", line);
+
+                let mut shared = shared.lock().unwrap();
+                if shared.recent_stderr.len() == STDERR_HISTORY {
+                    shared.recent_stderr.pop_front();
+                }
+                shared.recent_stderr.push_back(line);
+            }
+        }));
+    }
+
+    drains
+}
+
+/// 按行读取（容忍非 UTF-8 输出，例如 Windows 控制台代码页）
+fn lines<R: Read>(reader: R) -> impl Iterator<Item = String> {
+    BufReader::new(reader)
+        .split(b'\n')
+        .map_while(Result::ok)
+        .map(|line| String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// 等待指定时间，期间收到停止请求时返回 false
+fn sleep_while_running(duration: Duration, running: &Arc<Mutex<bool>>) -> bool {
+    let deadline = Instant::now() + duration;
+
+    while Instant::now() < deadline {
+        if !*running.lock().unwrap() {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    *running.lock().unwrap()
+}
+
+/// 按以下顺序查找相对路径：工作目录、程序所在目录及其上级目录（开发模式下程序位于 target/debug）
+pub fn resolve_path(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path);
+    if path.is_absolute() || path.exists() {
+        return Some(path).filter(|p| p.exists());
+    }
+
+    let exe = std::env::current_exe().ok()?;
+    exe.ancestors()
+        .skip(1)
+        .take(5)
+        .map(|dir| dir.join(&path))
+        .find(|candidate| candidate.exists())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str, auto_restart: bool) -> BridgeCommand {
+        BridgeCommand {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            auto_restart,
+        }
+    }
+
+    fn wait_for<F: Fn(&BridgeStatus) -> bool>(supervisor: &BridgeSupervisor, condition: F) -> BridgeStatus {
+        for _ in 0..100 {
+            let status = supervisor.status();
+            if condition(&status) {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("unexpected bridge status: {:?}", supervisor.status());
+    }
+
+    #[test]
+    fn test_exit_is_detected_and_restarted() {
+        let supervisor = BridgeSupervisor::start(shell("echo 'SimConnect not found' >&2; exit 1", true)).unwrap();
+
+        let status = wait_for(&supervisor, |s| matches!(s.state, BridgeProcessState::Restarting { .. }));
+        assert_eq!(status.last_exit.as_deref(), Some("退出码 1"));
+        assert_eq!(status.recent_stderr, vec!["SimConnect not found".to_string()]);
+        assert_eq!(status.state, BridgeProcessState::Restarting { attempt: 1, retry_in_secs: 1 });
+
+        // 等待后重新启动
+        wait_for(&supervisor, |s| s.restart_count == 2);
+    }
+
+    #[test]
+    fn test_rejected_bridge_is_not_restarted() {
+        let supervisor = BridgeSupervisor::start(shell("exit 2", true)).unwrap();
+
+        let status = wait_for(&supervisor, |s| matches!(s.state, BridgeProcessState::Failed { .. }));
+        assert_eq!(status.restart_count, 0);
+    }
+
+    #[test]
+    fn test_stop_kills_process() {
+        let mut supervisor = BridgeSupervisor::start(shell("sleep 30", true)).unwrap();
+        assert!(matches!(supervisor.status().state, BridgeProcessState::Running { .. }));
+
+        supervisor.stop();
+        assert_eq!(supervisor.status().state, BridgeProcessState::Stopped);
+    }
+
+    #[test]
+    fn test_missing_interpreter_fails_to_start() {
+        let command = BridgeCommand {
+            program: "virtual-atc-no-such-python".to_string(),
+            args: Vec::new(),
+            auto_restart: true,
+        };
+        assert!(BridgeSupervisor::start(command).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use super::atc_database::{Airport, Frequencies, Runway};
+use super::taxi_network::TaxiNetwork;
+
+/// 英尺换算为米
+const FT_TO_M: f64 = 0.3048;
+
+/// 导入的机场类型（直升机场、水上机场、已关闭机场不导入）
+const AIRPORT_TYPES: [&str; 3] = ["large_airport", "medium_airport", "small_airport"];
+
+/// 默认数据目录：放入 airports.csv、runways.csv、airport-frequencies.csv 后启动时自动加载
+pub fn default_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("VirtualATC")
+        .join("ourairports")
+}
+
+/// 从目录读取 OurAirports 数据（https://ourairports.com/data/）
+///
+/// airports.csv 必须存在；runways.csv 和 airport-frequencies.csv 缺失时只导入机场位置
+pub fn load_dir(dir: &Path) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let airports = File::open(dir.join("airports.csv"))
+        .map_err(|e| format!("无法打开 {}: {}", dir.join("airports.csv").display(), e))?;
+    let runways = File::open(dir.join("runways.csv")).ok();
+    let frequencies = File::open(dir.join("airport-frequencies.csv")).ok();
+
+    let airports = parse(airports, runways, frequencies)?;
+    println!("✓ 从 OurAirports 读取 {} 个机场", airports.len());
+    Ok(airports)
+}
+
+/// 解析三个 CSV 文件（列按表头名称匹配，兼容不同版本的列顺序）
+pub fn parse<A: Read, R: Read, F: Read>(
+    airports: A,
+    runways: Option<R>,
+    frequencies: Option<F>,
+) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let mut runways_by_airport: HashMap<String, Vec<Runway>> = HashMap::new();
+    if let Some(runways) = runways {
+        for row in csv::Reader::from_reader(runways).deserialize::<RunwayRow>() {
+            let row = row?;
+            if let Some(runway) = row.to_runway() {
+                runways_by_airport.entry(row.airport_ident).or_default().push(runway);
+            }
+        }
+    }
+
+    let mut frequencies_by_airport: HashMap<String, FrequencyList> = HashMap::new();
+    if let Some(frequencies) = frequencies {
+        for row in csv::Reader::from_reader(frequencies).deserialize::<FrequencyRow>() {
+            let row = row?;
+            if let Some(mhz) = row.frequency_mhz {
+                frequencies_by_airport.entry(row.airport_ident).or_default().add(&row.frequency_type, mhz);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    for row in csv::Reader::from_reader(airports).deserialize::<AirportRow>() {
+        let row = row?;
+        if !AIRPORT_TYPES.contains(&row.airport_type.as_str()) {
+            continue;
+        }
+        let (Some(latitude), Some(longitude)) = (row.latitude_deg, row.longitude_deg) else { continue };
+
+        let ident = row.ident.trim().to_uppercase();
+        let runways = runways_by_airport.remove(&row.ident).unwrap_or_default();
+        let frequencies = frequencies_by_airport.remove(&row.ident).unwrap_or_default().into_frequencies();
+
+        result.push(Airport {
+            icao: ident,
+            name: row.name.clone(),
+            name_en: row.name,
+            latitude,
+            longitude,
+            elevation: row.elevation_ft.map(|e| e.round() as i32).unwrap_or(0),
+            runways,
+            taxiways: vec![],
+            gates: vec![],
+            taxi_routes: vec![],
+            taxi_network: TaxiNetwork::default(),
+            hotspots: vec![],
+            frequencies,
+            sids: vec![],
+            stars: vec![],
+        });
+    }
+
+    Ok(result)
+}
+
+/// airports.csv 的一行
+#[derive(Debug, Deserialize)]
+struct AirportRow {
+    ident: String,
+    #[serde(rename = "type")]
+    airport_type: String,
+    name: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    latitude_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    longitude_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    elevation_ft: Option<f64>,
+}
+
+/// runways.csv 的一行（le = 低编号端，he = 高编号端）
+#[derive(Debug, Deserialize)]
+struct RunwayRow {
+    airport_ident: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    length_ft: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    width_ft: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    closed: Option<u8>,
+    le_ident: String,
+    #[serde(rename = "le_heading_degT", deserialize_with = "csv::invalid_option")]
+    le_heading: Option<f64>,
+    he_ident: String,
+    #[serde(rename = "he_heading_degT", deserialize_with = "csv::invalid_option")]
+    he_heading: Option<f64>,
+}
+
+impl RunwayRow {
+    fn to_runway(&self) -> Option<Runway> {
+        if self.closed == Some(1) || self.le_ident.is_empty() {
+            return None;
+        }
+
+        let heading_1 = Runway::designator_heading(&self.le_ident)
+            .or(self.le_heading.map(|h| h.round() as u16))
+            .unwrap_or(0);
+        let heading_2 = Runway::designator_heading(&self.he_ident)
+            .or(self.he_heading.map(|h| h.round() as u16))
+            .unwrap_or((heading_1 + 180) % 360);
+
+        Some(Runway {
+            name: if self.he_ident.is_empty() {
+                self.le_ident.clone()
+            } else {
+                format!("{}/{}", self.le_ident, self.he_ident)
+            },
+            heading_1,
+            heading_2,
+            length: self.length_ft.map(|ft| (ft * FT_TO_M).round() as u32).unwrap_or(0),
+            width: self.width_ft.map(|ft| (ft * FT_TO_M).round() as u32).unwrap_or(0),
+        })
+    }
+}
+
+/// airport-frequencies.csv 的一行
+#[derive(Debug, Deserialize)]
+struct FrequencyRow {
+    airport_ident: String,
+    #[serde(rename = "type")]
+    frequency_type: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    frequency_mhz: Option<f32>,
+}
+
+/// 按类型归类的频率
+#[derive(Default)]
+struct FrequencyList {
+    frequencies: Frequencies,
+    unicom: Vec<f32>,
+}
+
+impl FrequencyList {
+    fn add(&mut self, frequency_type: &str, mhz: f32) {
+        let list = match frequency_type.trim().to_uppercase().as_str() {
+            "TWR" | "TOWER" => &mut self.frequencies.tower,
+            "GND" | "GROUND" => &mut self.frequencies.ground,
+            "DEL" | "CLD" | "CLNC" | "CD" => &mut self.frequencies.clearance,
+            "APP" | "APPR" | "ARR" => &mut self.frequencies.approach,
+            "DEP" => &mut self.frequencies.departure,
+            "ATIS" | "D-ATIS" => &mut self.frequencies.atis,
+            "CTAF" | "UNIC" | "UNICOM" | "AFIS" => &mut self.unicom,
+            _ => return,
+        };
+        if !list.contains(&mhz) {
+            list.push(mhz);
+        }
+    }
+
+    /// 无塔台机场使用 CTAF / UNICOM
+    fn into_frequencies(mut self) -> Frequencies {
+        if self.frequencies.tower.is_empty() {
+            self.frequencies.tower = self.unicom;
+        }
+        self.frequencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIRPORTS: &str = r#""id","ident","type","name","latitude_deg","longitude_deg","elevation_ft","continent","iso_country","iso_region","municipality","scheduled_service","icao_code","iata_code","gps_code","local_code","home_link","wikipedia_link","keywords"
+27232,"ZLXY","large_airport","Xi'an Xianyang International Airport",34.447102,108.751999,1572,"AS","CN","CN-61","Xi'an","yes","ZLXY","XIY","ZLXY",,,,
+6523,"00A","heliport","Total RF Heliport",40.070985,-74.933689,11,"NA","US","US-PA","Bensalem","no",,,"K00A","00A",,,
+3422,"KHAF","small_airport","Half Moon Bay Airport",37.513401,-122.500999,66,"NA","US","US-CA","Half Moon Bay","no","KHAF","HAF","KHAF","HAF",,,
+"#;
+
+    const RUNWAYS: &str = r#""id","airport_ref","airport_ident","length_ft","width_ft","surface","lighted","closed","le_ident","le_latitude_deg","le_longitude_deg","le_elevation_ft","le_heading_degT","le_displaced_threshold_ft","he_ident","he_latitude_deg","he_longitude_deg","he_elevation_ft","he_heading_degT","he_displaced_threshold_ft"
+1,27232,"ZLXY",9843,148,"CON",1,0,"05L",34.43,108.74,1572,47.6,,"23R",34.46,108.77,1572,227.6,
+2,27232,"ZLXY",12467,197,"CON",1,0,"05R",34.43,108.75,1572,47.6,,"23L",34.46,108.78,1572,227.6,
+3,3422,"KHAF",5000,150,"ASP",1,0,"12",37.52,-122.51,66,,,"30",37.51,-122.49,66,,
+4,3422,"KHAF",2000,60,"ASP",0,1,"03",37.52,-122.51,66,,,"21",37.51,-122.49,66,,
+"#;
+
+    const FREQUENCIES: &str = r#""id","airport_ref","airport_ident","type","description","frequency_mhz"
+1,27232,"ZLXY","TWR","TWR",118.3
+2,27232,"ZLXY","GND","GND",121.9
+3,27232,"ZLXY","ATIS","ATIS",127.85
+4,27232,"ZLXY","DEL","CLNC DEL",121.75
+5,3422,"KHAF","CTAF","CTAF",122.975
+"#;
+
+    #[test]
+    fn test_parse_ourairports() {
+        let airports = parse(
+            AIRPORTS.as_bytes(),
+            Some(RUNWAYS.as_bytes()),
+            Some(FREQUENCIES.as_bytes()),
+        ).unwrap();
+        assert_eq!(airports.len(), 2, "直升机场不应导入");
+
+        let zlxy = &airports[0];
+        assert_eq!(zlxy.icao, "ZLXY");
+        assert_eq!(zlxy.name_en, "Xi'an Xianyang International Airport");
+        assert_eq!(zlxy.elevation, 1572);
+        assert_eq!(zlxy.runways.len(), 2);
+        assert_eq!(zlxy.runways[0].name, "05L/23R");
+        assert_eq!((zlxy.runways[0].heading_1, zlxy.runways[0].heading_2), (50, 230));
+        assert_eq!(zlxy.runways[1].length, 3800);
+        assert_eq!(zlxy.runways[1].width, 60);
+        assert_eq!(zlxy.frequencies.tower, vec![118.3]);
+        assert_eq!(zlxy.frequencies.clearance, vec![121.75]);
+        assert_eq!(zlxy.frequencies.atis, vec![127.85]);
+
+        // 已关闭的跑道不导入，无塔台机场使用 CTAF
+        let khaf = &airports[1];
+        assert_eq!(khaf.runways.len(), 1);
+        assert_eq!(khaf.frequencies.tower, vec![122.975]);
+    }
+
+    #[test]
+    fn test_airports_only() {
+        let airports = parse(AIRPORTS.as_bytes(), None::<&[u8]>, None::<&[u8]>).unwrap();
+        assert_eq!(airports.len(), 2);
+        assert!(airports[0].runways.is_empty());
+        assert!(airports[0].frequencies.tower.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use super::replay::TelemetrySample;
+use super::simulator::FlightData;
+
+/// 接收线程与 Tauri 命令共享的记录器句柄（None 表示未在记录）
+pub type SharedRecorder = Arc<Mutex<Option<FlightRecorder>>>;
+
+/// 飞行记录器 - 将实时飞行数据写入 JSONL 文件，格式与回放数据源一致
+pub struct FlightRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started_at: Instant,
+    sample_count: u64,
+}
+
+impl FlightRecorder {
+    /// 创建记录文件
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "# Virtual ATC flight log, started at unix time {}", unix_time())?;
+        writer.flush()?;
+
+        println!("Flight recording started: {:?}", path);
+
+        Ok(FlightRecorder {
+            writer,
+            path,
+            started_at: Instant::now(),
+            sample_count: 0,
+        })
+    }
+
+    /// 默认记录目录
+    pub fn default_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("VirtualATC")
+            .join("flight_logs")
+    }
+
+    /// 在默认目录下按时间生成文件名
+    pub fn default_path() -> PathBuf {
+        Self::default_dir().join(format!("flight-{}.jsonl", unix_time()))
+    }
+
+    /// 写入一个采样点（时间戳为单调时钟，相对于记录开始）
+    pub fn record(&mut self, data: &FlightData) -> Result<(), Box<dyn Error>> {
+        let sample = TelemetrySample {
+            timestamp: self.started_at.elapsed().as_secs_f64(),
+            data: data.clone(),
+        };
+
+        serde_json::to_writer(&mut self.writer, &sample)?;
+        self.writer.write_all(b"\n")?;
+        // 每个采样都刷盘，保证程序崩溃时记录仍然完整
+        self.writer.flush()?;
+
+        self.sample_count += 1;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count
+    }
+}
+
+/// 如果正在记录，则写入采样点；写入失败时停止记录
+pub fn record_sample(recorder: &SharedRecorder, data: &FlightData) {
+    let mut guard = recorder.lock().unwrap();
+
+    if let Some(active) = guard.as_mut() {
+        if let Err(e) = active.record(data) {
+            eprintln!("Flight recording failed, stopping: {}", e);
+            *guard = None;
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::replay::ReplayConnection;
+
+    #[test]
+    fn test_recording_round_trips_through_replay() {
+        let path = std::env::temp_dir().join(format!("virtual-atc-recorder-{}.jsonl", std::process::id()));
+        let recorder: SharedRecorder = Arc::new(Mutex::new(Some(FlightRecorder::create(&path).unwrap())));
+
+        let mut data = FlightData {
+            callsign: "CCA1234".to_string(),
+            ..FlightData::default()
+        };
+        record_sample(&recorder, &data);
+        data.speed = 18.0;
+        data.on_ground = false;
+        record_sample(&recorder, &data);
+
+        assert_eq!(recorder.lock().unwrap().as_ref().unwrap().sample_count(), 2);
+        recorder.lock().unwrap().take();
+
+        let replay = ReplayConnection::from_file(&path, 1.0).unwrap();
+        assert_eq!(replay.sample_at(0.0).callsign, "CCA1234");
+        assert_eq!(replay.sample_at(replay.duration()).speed, 18.0);
+
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,271 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use super::data_source::FlightDataSource;
+use super::simulator::FlightData;
+
+/// 飞行记录中的一个采样点
+///
+/// JSONL 格式每行一个对象，例如：
+/// `{"timestamp": 12.5, "callsign": "CCA1234", "altitude": 3500.0, ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetrySample {
+    pub timestamp: f64,     // 秒（相对于记录开始）
+    #[serde(flatten)]
+    pub data: FlightData,
+}
+
+/// 回放数据源 - 按时间戳回放录制的飞行数据，用于离线测试
+pub struct ReplayConnection {
+    samples: Vec<TelemetrySample>,
+    speed: f64,
+    started_at: Instant,
+}
+
+impl ReplayConnection {
+    /// 从文件加载飞行记录（根据扩展名识别 .csv，其余按 JSONL 解析）
+    pub fn from_file<P: AsRef<Path>>(path: P, speed: f64) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("无法读取飞行记录 {:?}: {}", path, e))?;
+
+        let is_csv = path.extension()
+            .map(|ext| ext.eq_ignore_ascii_case("csv"))
+            .unwrap_or(false);
+
+        let samples = if is_csv {
+            parse_csv(&content)?
+        } else {
+            parse_jsonl(&content)?
+        };
+
+        println!("Loaded flight log {:?}: {} samples", path, samples.len());
+        Self::from_samples(samples, speed)
+    }
+
+    /// 从内存中的采样点创建回放（speed 为回放倍速，1.0 为实时）
+    pub fn from_samples(mut samples: Vec<TelemetrySample>, speed: f64) -> Result<Self, Box<dyn Error>> {
+        if samples.is_empty() {
+            return Err("飞行记录为空".into());
+        }
+        if !(speed > 0.0 && speed.is_finite()) {
+            return Err(format!("无效的回放倍速: {}", speed).into());
+        }
+
+        samples.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        // 时间戳归一化，从 0 开始
+        let base = samples[0].timestamp;
+        for sample in &mut samples {
+            sample.timestamp -= base;
+        }
+
+        Ok(ReplayConnection {
+            samples,
+            speed,
+            started_at: Instant::now(),
+        })
+    }
+
+    /// 获取指定回放时间（秒）对应的采样数据
+    pub fn sample_at(&self, elapsed: f64) -> &FlightData {
+        let index = self.samples
+            .partition_point(|s| s.timestamp <= elapsed)
+            .saturating_sub(1);
+        &self.samples[index].data
+    }
+
+    /// 当前回放进度（秒）
+    pub fn elapsed(&self) -> f64 {
+        self.started_at.elapsed().as_secs_f64() * self.speed
+    }
+
+    /// 记录总时长（秒）
+    pub fn duration(&self) -> f64 {
+        self.samples.last().map(|s| s.timestamp).unwrap_or(0.0)
+    }
+
+    /// 回放是否已结束
+    pub fn is_finished(&self) -> bool {
+        self.elapsed() >= self.duration()
+    }
+
+    /// 从头开始重新回放
+    pub fn restart(&mut self) {
+        self.started_at = Instant::now();
+    }
+}
+
+impl FlightDataSource for ReplayConnection {
+    fn sim_type(&self) -> &'static str {
+        "replay"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "飞行记录回放"
+    }
+
+    fn get_flight_data(&self) -> Result<FlightData, Box<dyn Error>> {
+        Ok(self.sample_at(self.elapsed()).clone())
+    }
+}
+
+fn parse_jsonl(content: &str) -> Result<Vec<TelemetrySample>, Box<dyn Error>> {
+    let mut samples = Vec::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let sample: TelemetrySample = serde_json::from_str(line)
+            .map_err(|e| format!("第 {} 行解析失败: {}", line_no + 1, e))?;
+        samples.push(sample);
+    }
+
+    Ok(samples)
+}
+
+/// 解析 CSV 飞行记录（首行为表头，必须包含 timestamp 列；含逗号的字段用双引号括起）
+fn parse_csv(content: &str) -> Result<Vec<TelemetrySample>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let header = reader.headers()?.clone();
+    if header.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !header.iter().any(|h| h == "timestamp") {
+        return Err("CSV 缺少 timestamp 列".into());
+    }
+
+    let mut samples = Vec::new();
+
+    for record in reader.records() {
+        let record = record?;
+        let line_no = record.position().map(|p| p.line()).unwrap_or_default();
+        let mut sample = TelemetrySample {
+            timestamp: 0.0,
+            data: FlightData::default(),
+        };
+
+        for (column, value) in header.iter().zip(record.iter()) {
+            let number = || -> Result<f64, String> {
+                value.parse::<f64>()
+                    .map_err(|_| format!("第 {} 行 {} 列数值无效: {}", line_no, column, value))
+            };
+
+            match column {
+                "timestamp" => sample.timestamp = number()?,
+                "callsign" => sample.data.callsign = value.to_string(),
+                "altitude" => sample.data.altitude = number()?,
+                "speed" => sample.data.speed = number()?,
+                "heading" => sample.data.heading = number()?,
+                "vertical_speed" => sample.data.vertical_speed = number()?,
+                "latitude" => sample.data.latitude = number()?,
+                "longitude" => sample.data.longitude = number()?,
+                "on_ground" => sample.data.on_ground = matches!(value, "1" | "true" | "TRUE" | "True"),
+                _ => {}
+            }
+        }
+
+        samples.push(sample);
+    }
+
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::flight_phase::{self, FlightPhase, FlightPhaseDetector};
+    use std::time::Duration;
+
+    const DEPARTURE_JSONL: &str = r#"
+{"timestamp": 100.0, "callsign": "CCA1234", "altitude": 116.0, "speed": 0.0, "vertical_speed": 0.0, "on_ground": true}
+{"timestamp": 160.0, "callsign": "CCA1234", "altitude": 116.0, "speed": 15.0, "vertical_speed": 0.0, "on_ground": true}
+{"timestamp": 400.0, "callsign": "CCA1234", "altitude": 300.0, "speed": 150.0, "vertical_speed": 1800.0, "on_ground": false}
+{"timestamp": 700.0, "callsign": "CCA1234", "altitude": 9000.0, "speed": 280.0, "vertical_speed": 2000.0, "on_ground": false}
+{"timestamp": 1500.0, "callsign": "CCA1234", "altitude": 33000.0, "speed": 460.0, "vertical_speed": 0.0, "on_ground": false}
+"#;
+
+    #[test]
+    fn test_jsonl_replay_timeline() {
+        let replay = ReplayConnection::from_samples(parse_jsonl(DEPARTURE_JSONL).unwrap(), 1.0).unwrap();
+
+        assert_eq!(replay.duration(), 1400.0);
+        assert_eq!(replay.sample_at(0.0).speed, 0.0);
+        assert_eq!(replay.sample_at(59.9).speed, 0.0);
+        assert_eq!(replay.sample_at(60.0).speed, 15.0);
+        assert_eq!(replay.sample_at(5000.0).altitude, 33000.0);
+    }
+
+    #[test]
+    fn test_csv_replay() {
+        let csv = "timestamp,callsign,altitude,speed,heading,vertical_speed,latitude,longitude,on_ground\n\
+                   0,CES5678,13,0,340,0,31.1434,121.8052,1\n\
+                   30,CES5678,13,20,340,0,31.1440,121.8050,1\n";
+        let replay = ReplayConnection::from_samples(parse_csv(csv).unwrap(), 4.0).unwrap();
+
+        let data = replay.sample_at(30.0);
+        assert_eq!(data.callsign, "CES5678");
+        assert_eq!(data.speed, 20.0);
+        assert!(data.on_ground);
+    }
+
+    #[test]
+    fn test_csv_quoted_field_with_comma() {
+        let csv = "# exported by FDR tool\n\
+                   timestamp,callsign,altitude,speed\n\
+                   0,\"CES5678, HEAVY\",13,0\n\
+                   \n\
+                   30,\"CES5678, HEAVY\",13,20\n";
+        let samples = parse_csv(csv).unwrap();
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].data.callsign, "CES5678, HEAVY");
+        assert_eq!(samples[1].data.altitude, 13.0);
+        assert_eq!(samples[1].data.speed, 20.0);
+    }
+
+    #[test]
+    fn test_replay_drives_phase_detector() {
+        let replay = ReplayConnection::from_samples(parse_jsonl(DEPARTURE_JSONL).unwrap(), 1.0).unwrap();
+        let mut detector = FlightPhaseDetector::new();
+
+        let start = Instant::now();
+
+        // 按 1 Hz 回放，在各阶段开始后稍等片刻（平滑窗口和最短持续时间）再检查
+        let mut phases = Vec::new();
+        for t in 0..=1430u64 {
+            let phase = detector.update_at(
+                &flight_phase::FlightData::from(replay.sample_at(t as f64)),
+                start + Duration::from_secs(t),
+            );
+            if [0, 90, 320, 630, 1430].contains(&t) {
+                phases.push(phase);
+            }
+        }
+
+        assert_eq!(phases, vec![
+            FlightPhase::PreFlight,
+            FlightPhase::Taxi,
+            FlightPhase::Takeoff,
+            FlightPhase::Climb,
+            FlightPhase::Cruise,
+        ]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        assert!(ReplayConnection::from_samples(Vec::new(), 1.0).is_err());
+        assert!(ReplayConnection::from_samples(parse_jsonl(DEPARTURE_JSONL).unwrap(), 0.0).is_err());
+        assert!(parse_csv("callsign,altitude\nCCA1,100\n").is_err());
+    }
+}
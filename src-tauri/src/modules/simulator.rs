@@ -1,18 +1,124 @@
 use std::error::Error;
 use std::sync::{Arc, Mutex};
-use std::net::UdpSocket;
-use std::thread;
-use std::time::Duration;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use super::config_store;
+use super::data_source::{ConnectionHealth, ConnectionStatus, FlightDataSource};
+use super::recorder::{self, SharedRecorder};
+use super::traffic::{SharedTraffic, TrafficTarget};
+
+/// X-Plane 连接配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct XPlaneConfig {
+    pub sim_host: String,       // X-Plane 所在主机
+    pub sim_port: u16,          // X-Plane 接收端口（Settings → Network）
+    pub listen_port: u16,       // 本地监听端口（不能与同机运行的 X-Plane 冲突）
+    pub rref_frequency: i32,    // RREF 订阅频率（次/秒）
+    pub stale_timeout_secs: u64, // 超过该秒数未收到数据视为中断，自动重新订阅
+}
+
+impl Default for XPlaneConfig {
+    fn default() -> Self {
+        XPlaneConfig {
+            sim_host: "127.0.0.1".to_string(),
+            sim_port: 49000,
+            listen_port: 49010,
+            rref_frequency: 1,
+            stale_timeout_secs: 5,
+        }
+    }
+}
+
+impl XPlaneConfig {
+    /// 读取上次保存的配置（不存在或格式无效时使用默认值，不解析主机名）
+    pub fn load() -> Self {
+        config_store::load_json::<XPlaneConfig>("xplane_config.json")
+            .filter(|config| config.validate().is_ok())
+            .unwrap_or_default()
+    }
+    
+    /// 保存配置
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        config_store::save_json("xplane_config.json", self)
+    }
+    
+    /// 校验配置格式（不访问网络）
+    pub fn validate(&self) -> Result<(), String> {
+        let host = self.sim_host.trim();
+        if host.is_empty() {
+            return Err("X-Plane 主机地址不能为空".to_string());
+        }
+        let is_hostname = host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+        if host.parse::<IpAddr>().is_err() && !is_hostname {
+            return Err(format!("X-Plane 主机地址格式无效：{}", host));
+        }
+        if self.sim_port == 0 {
+            return Err("X-Plane 端口不能为 0".to_string());
+        }
+        if self.listen_port == 0 {
+            return Err("本地监听端口不能为 0".to_string());
+        }
+        if !(1..=99).contains(&self.rref_frequency) {
+            return Err(format!("RREF 频率必须在 1~99 次/秒之间，当前为 {}", self.rref_frequency));
+        }
+        if self.stale_timeout_secs < 2 {
+            return Err("数据超时时间不能少于 2 秒".to_string());
+        }
+        
+        let loopback = host.eq_ignore_ascii_case("localhost")
+            || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+        if loopback {
+            self.check_port_conflict()?;
+        }
+        
+        Ok(())
+    }
+    
+    /// 校验配置并解析 X-Plane 地址（主机名需要 DNS 解析，只在连接时调用）
+    pub fn resolve(&self) -> Result<SocketAddr, String> {
+        self.validate()?;
+        
+        let host = self.sim_host.trim();
+        let addr = (host, self.sim_port)
+            .to_socket_addrs()
+            .map_err(|e| format!("无法解析 X-Plane 主机地址 {}: {}", host, e))?
+            .find(|addr| addr.is_ipv4())  // 本地监听使用 IPv4
+            .ok_or_else(|| format!("无法解析 X-Plane 主机地址 {}", host))?;
+        
+        if addr.ip().is_loopback() {
+            self.check_port_conflict()?;
+        }
+        
+        Ok(addr)
+    }
+    
+    /// 同一台机器上 X-Plane 已占用其接收端口
+    fn check_port_conflict(&self) -> Result<(), String> {
+        if self.listen_port == self.sim_port {
+            return Err(format!(
+                "本地监听端口 {} 与本机 X-Plane 端口冲突，请使用其他端口（如 49010）",
+                self.listen_port
+            ));
+        }
+        Ok(())
+    }
+}
 
 pub struct SimulatorConnection {
     xplane: Option<XPlaneConnection>,
     flight_data: Arc<Mutex<FlightData>>,
+    link: Arc<Mutex<LinkState>>,
+    stale_timeout: Duration,
 }
 
 impl SimulatorConnection {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
-        let xplane = XPlaneConnection::new(49000)?;
+    pub fn new(config: &XPlaneConfig, recorder: SharedRecorder, traffic: SharedTraffic) -> Result<Self, Box<dyn Error>> {
+        let mut xplane = XPlaneConnection::new(config, recorder, traffic)?;
         let flight_data = xplane.flight_data.clone();
+        let link = xplane.link.clone();
         
         // 启动数据接收线程
         xplane.start()?;
@@ -26,113 +132,177 @@ impl SimulatorConnection {
         Ok(SimulatorConnection {
             xplane: Some(xplane),
             flight_data,
+            link,
+            stale_timeout: Duration::from_secs(config.stale_timeout_secs),
         })
     }
     
     pub fn get_flight_data(&self) -> Result<FlightData, Box<dyn Error>> {
-        let mut data = self.flight_data.lock().unwrap().clone();
+        Ok(self.flight_data.lock().unwrap().clone())
+    }
+}
+
+impl FlightDataSource for SimulatorConnection {
+    fn sim_type(&self) -> &'static str {
+        "xplane"
+    }
+    
+    fn display_name(&self) -> &'static str {
+        "X-Plane"
+    }
+    
+    fn get_flight_data(&self) -> Result<FlightData, Box<dyn Error>> {
+        SimulatorConnection::get_flight_data(self)
+    }
+    
+    fn status(&self) -> ConnectionStatus {
+        let link = self.link.lock().unwrap();
+        let health = if self.xplane.is_none() {
+            ConnectionHealth::Disconnected
+        } else {
+            link.health(self.stale_timeout)
+        };
         
-        // 如果没有呼号，生成一个
-        if data.callsign.is_empty() {
-            data.callsign = format!("CCA{:03}", (data.altitude / 100.0) as u32 % 1000);
+        ConnectionStatus {
+            sim_type: self.sim_type().to_string(),
+            health,
+            last_data_age_secs: link.last_packet.map(|t| t.elapsed().as_secs_f64()),
+            resubscribe_count: link.resubscribe_count,
+            error: None,
+            bridge: None,
+        }
+    }
+    
+    fn disconnect(&mut self) {
+        // 释放 XPlaneConnection，Drop 会取消订阅并等待接收线程退出，返回时端口已释放
+        self.xplane = None;
+    }
+}
+
+/// 连接链路状态，用于检测数据中断（如 X-Plane 重启后订阅丢失）
+struct LinkState {
+    connected_at: Instant,
+    last_packet: Option<Instant>,
+    last_subscribe: Instant,
+    resubscribe_count: u32,
+}
+
+impl LinkState {
+    fn new() -> Self {
+        let now = Instant::now();
+        LinkState {
+            connected_at: now,
+            last_packet: None,
+            last_subscribe: now,
+            resubscribe_count: 0,
+        }
+    }
+    
+    /// 距离上次收到数据（或建立连接）的时间
+    fn silence(&self) -> Duration {
+        self.last_packet.unwrap_or(self.connected_at).elapsed()
+    }
+    
+    fn health(&self, stale_timeout: Duration) -> ConnectionHealth {
+        if self.silence() >= stale_timeout {
+            ConnectionHealth::Stale
+        } else if self.last_packet.is_none() {
+            ConnectionHealth::Waiting
+        } else {
+            ConnectionHealth::Receiving
         }
-        
-        Ok(data)
     }
 }
 
 struct XPlaneConnection {
     socket: UdpSocket,
-    xplane_addr: String,
+    xplane_addr: SocketAddr,
+    rref_frequency: i32,
+    stale_timeout: Duration,
     flight_data: Arc<Mutex<FlightData>>,
     running: Arc<Mutex<bool>>,
+    receiver: Option<JoinHandle<()>>,
+    link: Arc<Mutex<LinkState>>,
+    recorder: SharedRecorder,
+    traffic: SharedTraffic,
 }
 
 impl XPlaneConnection {
-    fn new(port: u16) -> Result<Self, Box<dyn Error>> {
-        let socket = UdpSocket::bind(format!("0.0.0.0:{}", port))?;
+    fn new(config: &XPlaneConfig, recorder: SharedRecorder, traffic: SharedTraffic) -> Result<Self, Box<dyn Error>> {
+        let xplane_addr = config.resolve()?;
+        
+        let socket = UdpSocket::bind(("0.0.0.0", config.listen_port))
+            .map_err(|e| format!("无法绑定本地 UDP 端口 {}: {}", config.listen_port, e))?;
         socket.set_nonblocking(false)?;
         socket.set_read_timeout(Some(Duration::from_secs(1)))?;
         
-        println!("X-Plane UDP listener started on port {}", port);
+        println!("X-Plane UDP listener started on port {}, sim at {}", config.listen_port, xplane_addr);
         
         Ok(XPlaneConnection {
             socket,
-            xplane_addr: "127.0.0.1:49000".to_string(),
+            xplane_addr,
+            rref_frequency: config.rref_frequency,
+            stale_timeout: Duration::from_secs(config.stale_timeout_secs),
             flight_data: Arc::new(Mutex::new(FlightData::default())),
             running: Arc::new(Mutex::new(false)),
+            receiver: None,
+            link: Arc::new(Mutex::new(LinkState::new())),
+            recorder,
+            traffic,
         })
     }
     
     fn subscribe_data(&self) -> Result<(), Box<dyn Error>> {
-        // 发送 RREF 请求订阅数据
-        // 格式: RREF\0 + freq(4字节) + id(4字节) + dataref_path
-        
-        let datarefs = vec![
-            (1, "sim/flightmodel/position/indicated_airspeed"),  // 空速
-            (2, "sim/flightmodel/position/elevation"),           // 高度（英尺）
-            (3, "sim/flightmodel/position/psi"),                 // 航向
-            (4, "sim/flightmodel/position/vh_ind_fpm"),          // 垂直速度
-            (5, "sim/flightmodel/position/latitude"),            // 纬度
-            (6, "sim/flightmodel/position/longitude"),           // 经度
-        ];
-        
-        for (id, dataref) in datarefs {
-            let mut msg = b"RREF\0".to_vec();
-            msg.extend_from_slice(&1i32.to_le_bytes());  // 频率：每秒1次
-            msg.extend_from_slice(&id.to_le_bytes());    // ID
-            msg.extend_from_slice(dataref.as_bytes());
-            msg.push(0);  // null terminator
-            
-            // 填充到400字节
-            while msg.len() < 413 {
-                msg.push(0);
-            }
-            
-            self.socket.send_to(&msg, &self.xplane_addr)?;
-        }
+        send_subscriptions(&self.socket, self.xplane_addr, self.rref_frequency)?;
+        self.link.lock().unwrap().last_subscribe = Instant::now();
         
         println!("Subscribed to X-Plane data");
         Ok(())
     }
     
-    fn start(&self) -> Result<(), Box<dyn Error>> {
+    /// 取消订阅（频率为 0），避免 X-Plane 继续向已关闭的端口发送数据
+    fn unsubscribe_data(&self) -> Result<(), Box<dyn Error>> {
+        send_subscriptions(&self.socket, self.xplane_addr, 0)?;
+        
+        println!("Unsubscribed from X-Plane data");
+        Ok(())
+    }
+    
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
         *self.running.lock().unwrap() = true;
         
         let socket = self.socket.try_clone()?;
         let flight_data = self.flight_data.clone();
         let running = self.running.clone();
+        let link = self.link.clone();
+        let recorder = self.recorder.clone();
+        let traffic = self.traffic.clone();
+        let xplane_addr = self.xplane_addr;
+        let rref_frequency = self.rref_frequency;
+        let stale_timeout = self.stale_timeout;
         
-        thread::spawn(move || {
-            let mut buf = [0u8; 1024];
+        self.receiver = Some(thread::spawn(move || {
+            let mut buf = [0u8; 2048];
+            let mut tcas = TcasTargets::new();
             
             println!("X-Plane data receiver thread started");
             
             while *running.lock().unwrap() {
                 match socket.recv(&mut buf) {
                     Ok(size) => {
-                        if let Some(data) = parse_xplane_packet(&buf[..size]) {
+                        if let Some(update) = parse_xplane_packet(&buf[..size]) {
+                            link.lock().unwrap().last_packet = Some(Instant::now());
+                            
+                            // 其他航空器写入交通表
+                            tcas.apply(&update.tcas, &traffic);
+                            
                             let mut current = flight_data.lock().unwrap();
                             
-                            // 更新数据
-                            if data.altitude > 0.0 {
-                                current.altitude = data.altitude;
-                            }
-                            if data.speed > 0.0 {
-                                current.speed = data.speed;
-                            }
-                            if data.heading >= 0.0 {
-                                current.heading = data.heading;
-                            }
-                            if data.vertical_speed != 0.0 {
-                                current.vertical_speed = data.vertical_speed;
-                            }
-                            if data.latitude != 0.0 {
-                                current.latitude = data.latitude;
-                            }
-                            if data.longitude != 0.0 {
-                                current.longitude = data.longitude;
-                            }
+                            // 只更新数据包中实际包含的字段（0 也是有效值）
+                            update.apply_to(&mut current);
+                            
+                            // 写入飞行记录（如果已开启）
+                            recorder::record_sample(&recorder, &current);
                             
                             // 调试输出
                             if current.altitude > 0.0 {
@@ -148,10 +318,25 @@ impl XPlaneConnection {
                         }
                     }
                 }
+                
+                // 看门狗：长时间没有数据（X-Plane 重启或订阅丢失）时重新发送订阅
+                let mut state = link.lock().unwrap();
+                if state.silence() >= stale_timeout
+                    && state.last_subscribe.elapsed() >= stale_timeout
+                    && *running.lock().unwrap()
+                {
+                    println!("No X-Plane data for {:.0}s, re-sending RREF subscriptions", state.silence().as_secs_f64());
+                    
+                    if let Err(e) = send_subscriptions(&socket, xplane_addr, rref_frequency) {
+                        eprintln!("Failed to re-subscribe to X-Plane: {}", e);
+                    }
+                    state.last_subscribe = Instant::now();
+                    state.resubscribe_count += 1;
+                }
             }
             
             println!("X-Plane data receiver thread stopped");
-        });
+        }));
         
         Ok(())
     }
@@ -160,35 +345,362 @@ impl XPlaneConnection {
 impl Drop for XPlaneConnection {
     fn drop(&mut self) {
         *self.running.lock().unwrap() = false;
+        
+        if let Err(e) = self.unsubscribe_data() {
+            eprintln!("Failed to unsubscribe from X-Plane: {}", e);
+        }
+        
+        // 等待接收线程退出（最多一个读超时），释放其持有的端口副本
+        if let Some(receiver) = self.receiver.take() {
+            let _ = receiver.join();
+        }
     }
 }
 
-#[derive(serde::Serialize, Clone, Debug)]
+/// 统一的飞行数据格式（所有模拟器数据源都转换为此结构）
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct FlightData {
-    pub callsign: String,
+    pub callsign: String,   // 模拟器报告的机尾号/ATC ID（播报用呼号由 callsign 模块解析）
+    pub atc_airline: String,        // 模拟器中设置的航空公司（MSFS ATC_AIRLINE）
+    pub atc_flight_number: String,  // 模拟器中设置的航班号（MSFS ATC_FLIGHT_NUMBER）
+    pub aircraft_type: String,      // ICAO 机型代码，如 "B738"（X-Plane acf_ICAO / MSFS ATC_MODEL）
     pub altitude: f64,      // 英尺
     pub speed: f64,         // 节
     pub heading: f64,       // 度
     pub vertical_speed: f64, // 英尺/分钟
     pub latitude: f64,      // 度
     pub longitude: f64,     // 度
+    pub on_ground: bool,    // 是否在地面
+    pub altitude_agl: f64,  // 离地高度（英尺）
+    pub ground_speed: f64,  // 地速（节）
+    pub gear_ratio: f64,    // 起落架放出比例（0 收起 ~ 1 放下）
+    pub flap_ratio: f64,    // 襟翼手柄位置（0 ~ 1）
+    pub com1_active: f64,   // COM1 使用频率（MHz）
+    pub com1_standby: f64,  // COM1 备用频率（MHz）
+    pub com2_active: f64,   // COM2 使用频率（MHz）
+    pub com2_standby: f64,  // COM2 备用频率（MHz）
+    pub nav1_active: f64,   // NAV1 使用频率（MHz）
+    pub nav1_standby: f64,  // NAV1 备用频率（MHz）
+    pub nav2_active: f64,   // NAV2 使用频率（MHz）
+    pub nav2_standby: f64,  // NAV2 备用频率（MHz）
+    pub transponder_code: u16, // 应答机编码（如 7000）
+    pub transponder_mode: u8,  // 应答机模式（0 关 / 1 待命 / 2 开 / 3 测试）
+    pub parking_brake: f64, // 停机刹车（0 松开 ~ 1 刹紧）
 }
 
 impl Default for FlightData {
     fn default() -> Self {
         FlightData {
             callsign: String::new(),
+            atc_airline: String::new(),
+            atc_flight_number: String::new(),
+            aircraft_type: String::new(),
             altitude: 0.0,
             speed: 0.0,
             heading: 0.0,
             vertical_speed: 0.0,
             latitude: 0.0,
             longitude: 0.0,
+            on_ground: true,
+            altitude_agl: 0.0,
+            ground_speed: 0.0,
+            gear_ratio: 1.0,
+            flap_ratio: 0.0,
+            com1_active: 0.0,
+            com1_standby: 0.0,
+            com2_active: 0.0,
+            com2_standby: 0.0,
+            nav1_active: 0.0,
+            nav1_standby: 0.0,
+            nav2_active: 0.0,
+            nav2_standby: 0.0,
+            transponder_code: 0,
+            transponder_mode: 0,
+            parking_brake: 0.0,
+        }
+    }
+}
+
+/// RREF 订阅的 DataRef 列表：(ID, DataRef 路径)
+///
+/// RREF 响应只有单精度浮点数，经纬度精度不足，改由 RPOS 数据包（双精度）提供。
+const RREF_DATAREFS: [(i32, &str); 34] = [
+    (1, "sim/flightmodel/position/indicated_airspeed"),            // 指示空速（节）
+    (2, "sim/flightmodel/position/elevation"),                     // 海拔高度（米）
+    (3, "sim/flightmodel/position/psi"),                           // 航向
+    (4, "sim/flightmodel/position/vh_ind_fpm"),                    // 垂直速度
+    (7, "sim/flightmodel/failures/onground_any"),                  // 是否在地面（0/1）
+    (8, "sim/flightmodel/position/y_agl"),                         // 离地高度（米）
+    (9, "sim/flightmodel/position/groundspeed"),                   // 地速（米/秒）
+    (10, "sim/flightmodel2/gear/deploy_ratio[0]"),                 // 起落架放出比例
+    (11, "sim/cockpit2/controls/flap_handle_deploy_ratio"),        // 襟翼手柄位置
+    (12, "sim/cockpit2/radios/actuators/com1_frequency_khz"),      // COM1 使用频率（kHz）
+    (13, "sim/cockpit2/radios/actuators/com1_standby_frequency_khz"), // COM1 备用频率（kHz）
+    (14, "sim/cockpit2/radios/actuators/com2_frequency_khz"),      // COM2 使用频率（kHz）
+    (15, "sim/cockpit2/radios/actuators/com2_standby_frequency_khz"), // COM2 备用频率（kHz）
+    (16, "sim/cockpit2/radios/actuators/transponder_code"),        // 应答机编码
+    (17, "sim/cockpit2/radios/actuators/transponder_mode"),        // 应答机模式
+    (18, "sim/cockpit/radios/nav1_freq_hz"),                       // NAV1 使用频率（10 kHz）
+    (19, "sim/cockpit/radios/nav1_stdby_freq_hz"),                 // NAV1 备用频率（10 kHz）
+    (20, "sim/cockpit/radios/nav2_freq_hz"),                       // NAV2 使用频率（10 kHz）
+    (21, "sim/cockpit/radios/nav2_stdby_freq_hz"),                 // NAV2 备用频率（10 kHz）
+    (22, "sim/cockpit2/controls/parking_brake_ratio"),             // 停机刹车
+    // 机尾号为字节数组，每个字符单独订阅（ID 从 TAILNUM_BASE_ID 开始）
+    (30, "sim/aircraft/view/acf_tailnum[0]"),
+    (31, "sim/aircraft/view/acf_tailnum[1]"),
+    (32, "sim/aircraft/view/acf_tailnum[2]"),
+    (33, "sim/aircraft/view/acf_tailnum[3]"),
+    (34, "sim/aircraft/view/acf_tailnum[4]"),
+    (35, "sim/aircraft/view/acf_tailnum[5]"),
+    (36, "sim/aircraft/view/acf_tailnum[6]"),
+    (37, "sim/aircraft/view/acf_tailnum[7]"),
+    (38, "sim/aircraft/view/acf_tailnum[8]"),
+    (39, "sim/aircraft/view/acf_tailnum[9]"),
+    // ICAO 机型代码（ID 从 ICAO_TYPE_BASE_ID 开始）
+    (40, "sim/aircraft/view/acf_ICAO[0]"),
+    (41, "sim/aircraft/view/acf_ICAO[1]"),
+    (42, "sim/aircraft/view/acf_ICAO[2]"),
+    (43, "sim/aircraft/view/acf_ICAO[3]"),
+];
+
+/// 机尾号字符的 RREF ID 起始值及订阅长度
+const TAILNUM_BASE_ID: i32 = 30;
+const TAILNUM_LEN: usize = 10;
+
+/// ICAO 机型代码字符的 RREF ID 起始值及订阅长度
+const ICAO_TYPE_BASE_ID: i32 = 40;
+const ICAO_TYPE_LEN: usize = 4;
+
+/// TCAS 目标（X-Plane 11.50+ 的 AI 和联机飞机，数组下标 0 为本机）
+const TCAS_MAX_TARGETS: usize = 8;
+const TCAS_BASE_ID: i32 = 100;      // 目标 n 的字段 ID 为 TCAS_BASE_ID + n * TCAS_ID_STRIDE + 字段偏移
+const TCAS_ID_STRIDE: i32 = 20;
+const TCAS_TYPE_LEN: usize = 4;     // 订阅 ICAO 机型代码的前 4 个字符
+const TCAS_TYPE_STRIDE: usize = 8;  // icao_type 数组中每个目标占 8 字节
+
+/// 每个目标订阅的数组 DataRef（按字段偏移排列）
+const TCAS_FIELDS: [&str; 8] = [
+    "sim/cockpit2/tcas/targets/position/lat",               // 0 纬度
+    "sim/cockpit2/tcas/targets/position/lon",               // 1 经度
+    "sim/cockpit2/tcas/targets/position/ele",               // 2 海拔（米）
+    "sim/cockpit2/tcas/targets/position/psi",               // 3 航向
+    "sim/cockpit2/tcas/targets/position/V_msc",             // 4 速度（米/秒）
+    "sim/cockpit2/tcas/targets/position/vertical_speed",    // 5 垂直速度（英尺/分钟）
+    "sim/cockpit2/tcas/targets/position/weight_on_wheels",  // 6 是否在地面
+    "sim/cockpit2/tcas/targets/modeS_id",                   // 7 S 模式地址（0 表示无目标）
+];
+const TCAS_FIELD_COUNT: usize = TCAS_FIELDS.len() + TCAS_TYPE_LEN;
+
+/// 全部 RREF 订阅（ID, DataRef）
+fn rref_subscriptions() -> Vec<(i32, String)> {
+    let mut subscriptions: Vec<(i32, String)> = RREF_DATAREFS.iter()
+        .map(|(id, dataref)| (*id, dataref.to_string()))
+        .collect();
+    
+    for slot in 1..=TCAS_MAX_TARGETS {
+        let base = TCAS_BASE_ID + slot as i32 * TCAS_ID_STRIDE;
+        
+        for (offset, dataref) in TCAS_FIELDS.iter().enumerate() {
+            subscriptions.push((base + offset as i32, format!("{}[{}]", dataref, slot)));
+        }
+        for c in 0..TCAS_TYPE_LEN {
+            subscriptions.push((
+                base + (TCAS_FIELDS.len() + c) as i32,
+                format!("sim/cockpit2/tcas/targets/icao_type[{}]", slot * TCAS_TYPE_STRIDE + c),
+            ));
         }
     }
+    
+    subscriptions
 }
 
-fn parse_xplane_packet(buf: &[u8]) -> Option<FlightData> {
+/// 发送全部订阅请求（RREF + RPOS），frequency 为 0 表示取消订阅
+fn send_subscriptions(socket: &UdpSocket, addr: SocketAddr, frequency: i32) -> std::io::Result<()> {
+    // RREF 格式: RREF\0 + freq(4字节) + id(4字节) + dataref_path（共 413 字节）
+    for (id, dataref) in rref_subscriptions() {
+        let mut msg = b"RREF\0".to_vec();
+        msg.extend_from_slice(&frequency.to_le_bytes());  // 频率：每秒次数
+        msg.extend_from_slice(&id.to_le_bytes());    // ID
+        msg.extend_from_slice(dataref.as_bytes());
+        msg.push(0);  // null terminator
+        
+        // 填充到413字节
+        msg.resize(413, 0);
+        
+        socket.send_to(&msg, addr)?;
+    }
+    
+    // RPOS 格式: RPOS\0 + 频率（ASCII 字符串）+ \0
+    let mut msg = b"RPOS\0".to_vec();
+    msg.extend_from_slice(frequency.to_string().as_bytes());
+    msg.push(0);
+    socket.send_to(&msg, addr)?;
+    
+    Ok(())
+}
+
+const METERS_TO_FEET: f64 = 3.28084;
+const MPS_TO_KNOTS: f64 = 1.943844;
+
+/// 单个数据包解析出的部分飞行数据，None 表示该字段不在数据包中
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlightDataUpdate {
+    pub altitude: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub vertical_speed: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub on_ground: Option<bool>,
+    pub altitude_agl: Option<f64>,
+    pub ground_speed: Option<f64>,
+    pub gear_ratio: Option<f64>,
+    pub flap_ratio: Option<f64>,
+    pub com1_active: Option<f64>,
+    pub com1_standby: Option<f64>,
+    pub com2_active: Option<f64>,
+    pub com2_standby: Option<f64>,
+    pub nav1_active: Option<f64>,
+    pub nav1_standby: Option<f64>,
+    pub nav2_active: Option<f64>,
+    pub nav2_standby: Option<f64>,
+    pub transponder_code: Option<u16>,
+    pub transponder_mode: Option<u8>,
+    pub parking_brake: Option<f64>,
+    pub tail_number: [Option<u8>; TAILNUM_LEN],  // 机尾号的各个字符（0 表示结束）
+    pub aircraft_type: [Option<u8>; ICAO_TYPE_LEN],  // ICAO 机型代码的各个字符
+    pub tcas: Vec<TcasValue>,                    // 其他航空器的数据
+}
+
+/// TCAS 目标的单个字段值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TcasValue {
+    pub slot: usize,    // 目标下标（1 ~ TCAS_MAX_TARGETS）
+    pub field: usize,   // 字段偏移（见 TCAS_FIELDS，之后为机型代码字符）
+    pub value: f32,
+}
+
+/// 按目标下标汇总 TCAS 数据，转换为交通表中的目标
+struct TcasTargets {
+    slots: [[f32; TCAS_FIELD_COUNT]; TCAS_MAX_TARGETS + 1],
+}
+
+impl TcasTargets {
+    fn new() -> Self {
+        TcasTargets {
+            slots: [[0.0; TCAS_FIELD_COUNT]; TCAS_MAX_TARGETS + 1],
+        }
+    }
+    
+    fn apply(&mut self, values: &[TcasValue], traffic: &SharedTraffic) {
+        if values.is_empty() {
+            return;
+        }
+        
+        let mut touched = [false; TCAS_MAX_TARGETS + 1];
+        for v in values {
+            self.slots[v.slot][v.field] = v.value;
+            touched[v.slot] = true;
+        }
+        
+        let mut table = traffic.lock().unwrap();
+        for slot in (1..=TCAS_MAX_TARGETS).filter(|&slot| touched[slot]) {
+            let id = format!("xplane-{}", slot);
+            match self.target(slot, id.clone()) {
+                Some(target) => table.update(target),
+                None => table.remove(&id),
+            }
+        }
+    }
+    
+    fn target(&self, slot: usize, id: String) -> Option<TrafficTarget> {
+        let f = &self.slots[slot];
+        let mode_s = f[7] as u32;
+        if mode_s == 0 || (f[0] == 0.0 && f[1] == 0.0) {
+            return None;
+        }
+        
+        let aircraft_type: String = f[TCAS_FIELDS.len()..]
+            .iter()
+            .map(|&c| c as u8)
+            .take_while(|&c| c != 0)
+            .map(char::from)
+            .collect();
+        
+        Some(TrafficTarget {
+            id,
+            callsign: format!("{:06X}", mode_s),
+            aircraft_type: aircraft_type.trim().to_string(),
+            latitude: f[0] as f64,
+            longitude: f[1] as f64,
+            altitude: f[2] as f64 * METERS_TO_FEET,
+            heading: f[3] as f64,
+            ground_speed: f[4] as f64 * MPS_TO_KNOTS,
+            vertical_speed: f[5] as f64,
+            on_ground: f[6] >= 0.5,
+        })
+    }
+}
+
+impl FlightDataUpdate {
+    /// 将数据包中包含的字段合并到当前数据
+    pub fn apply_to(&self, data: &mut FlightData) {
+        fn set<T: Copy>(target: &mut T, value: Option<T>) {
+            if let Some(v) = value {
+                *target = v;
+            }
+        }
+        
+        set(&mut data.altitude, self.altitude);
+        set(&mut data.speed, self.speed);
+        set(&mut data.heading, self.heading);
+        set(&mut data.vertical_speed, self.vertical_speed);
+        set(&mut data.latitude, self.latitude);
+        set(&mut data.longitude, self.longitude);
+        set(&mut data.on_ground, self.on_ground);
+        set(&mut data.altitude_agl, self.altitude_agl);
+        set(&mut data.ground_speed, self.ground_speed);
+        set(&mut data.gear_ratio, self.gear_ratio);
+        set(&mut data.flap_ratio, self.flap_ratio);
+        set(&mut data.com1_active, self.com1_active);
+        set(&mut data.com1_standby, self.com1_standby);
+        set(&mut data.com2_active, self.com2_active);
+        set(&mut data.com2_standby, self.com2_standby);
+        set(&mut data.nav1_active, self.nav1_active);
+        set(&mut data.nav1_standby, self.nav1_standby);
+        set(&mut data.nav2_active, self.nav2_active);
+        set(&mut data.nav2_standby, self.nav2_standby);
+        set(&mut data.transponder_code, self.transponder_code);
+        set(&mut data.transponder_mode, self.transponder_mode);
+        set(&mut data.parking_brake, self.parking_brake);
+        
+        merge_chars(&mut data.callsign, &self.tail_number);
+        merge_chars(&mut data.aircraft_type, &self.aircraft_type);
+    }
+}
+
+/// 用收到的字符覆盖字符串的对应位置（字节数组 DataRef 按字符订阅，0 表示结束）
+fn merge_chars<const N: usize>(text: &mut String, received: &[Option<u8>; N]) {
+    if received.iter().all(Option::is_none) {
+        return;
+    }
+    
+    let mut bytes = [0u8; N];
+    for (byte, current) in bytes.iter_mut().zip(text.bytes()) {
+        *byte = current;
+    }
+    for (byte, received) in bytes.iter_mut().zip(received) {
+        if let Some(value) = received {
+            *byte = *value;
+        }
+    }
+    
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(N);
+    *text = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+}
+
+fn parse_xplane_packet(buf: &[u8]) -> Option<FlightDataUpdate> {
     if buf.len() < 5 {
         return None;
     }
@@ -200,6 +712,11 @@ fn parse_xplane_packet(buf: &[u8]) -> Option<FlightData> {
         return parse_rref_data(&buf[5..]);
     }
     
+    // 处理 RPOS 响应（双精度位置）
+    if header == b"RPOS" {
+        return parse_rpos_data(&buf[5..]);
+    }
+    
     // 处理 DATA 包
     if header == b"DATA" {
         return parse_data_packet(&buf[5..]);
@@ -208,67 +725,461 @@ fn parse_xplane_packet(buf: &[u8]) -> Option<FlightData> {
     None
 }
 
-fn parse_rref_data(buf: &[u8]) -> Option<FlightData> {
+fn parse_rref_data(buf: &[u8]) -> Option<FlightDataUpdate> {
     if buf.len() < 8 {
         return None;
     }
     
-    let mut data = FlightData::default();
-    let mut offset = 0;
+    let mut data = FlightDataUpdate::default();
     
-    while offset + 8 <= buf.len() {
-        let id = i32::from_le_bytes([buf[offset], buf[offset+1], buf[offset+2], buf[offset+3]]);
-        let value = f32::from_le_bytes([buf[offset+4], buf[offset+5], buf[offset+6], buf[offset+7]]);
+    for chunk in buf.chunks_exact(8) {
+        let id = i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let value = f32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
         
         match id {
-            1 => data.speed = value as f64,           // 空速
-            2 => data.altitude = value as f64,        // 高度
-            3 => data.heading = value as f64,         // 航向
-            4 => data.vertical_speed = value as f64,  // 垂直速度
-            5 => data.latitude = value as f64,        // 纬度
-            6 => data.longitude = value as f64,       // 经度
+            1 => data.speed = Some(value as f64),           // 空速
+            2 => data.altitude = Some(value as f64 * METERS_TO_FEET), // 高度（米转英尺）
+            3 => data.heading = Some(value as f64),         // 航向
+            4 => data.vertical_speed = Some(value as f64),  // 垂直速度
+            7 => data.on_ground = Some(value >= 0.5),       // 地面状态
+            8 => data.altitude_agl = Some(value as f64 * METERS_TO_FEET), // 离地高度（米转英尺）
+            9 => data.ground_speed = Some(value as f64 * MPS_TO_KNOTS),   // 地速（米/秒转节）
+            10 => data.gear_ratio = Some(value as f64),     // 起落架
+            11 => data.flap_ratio = Some(value as f64),     // 襟翼
+            12 => data.com1_active = Some(value as f64 / 1000.0),  // kHz 转 MHz
+            13 => data.com1_standby = Some(value as f64 / 1000.0),
+            14 => data.com2_active = Some(value as f64 / 1000.0),
+            15 => data.com2_standby = Some(value as f64 / 1000.0),
+            16 => data.transponder_code = Some(value as u16),
+            17 => data.transponder_mode = Some(value as u8),
+            18 => data.nav1_active = Some(value as f64 / 100.0),   // 10 kHz 转 MHz
+            19 => data.nav1_standby = Some(value as f64 / 100.0),
+            20 => data.nav2_active = Some(value as f64 / 100.0),
+            21 => data.nav2_standby = Some(value as f64 / 100.0),
+            22 => data.parking_brake = Some(value as f64),
+            id if (TAILNUM_BASE_ID..TAILNUM_BASE_ID + TAILNUM_LEN as i32).contains(&id) => {
+                data.tail_number[(id - TAILNUM_BASE_ID) as usize] = Some(value as u8);
+            }
+            id if (ICAO_TYPE_BASE_ID..ICAO_TYPE_BASE_ID + ICAO_TYPE_LEN as i32).contains(&id) => {
+                data.aircraft_type[(id - ICAO_TYPE_BASE_ID) as usize] = Some(value as u8);
+            }
+            id if id >= TCAS_BASE_ID + TCAS_ID_STRIDE => {
+                let slot = ((id - TCAS_BASE_ID) / TCAS_ID_STRIDE) as usize;
+                let field = ((id - TCAS_BASE_ID) % TCAS_ID_STRIDE) as usize;
+                if slot <= TCAS_MAX_TARGETS && field < TCAS_FIELD_COUNT {
+                    data.tcas.push(TcasValue { slot, field, value });
+                }
+            }
             _ => {}
         }
+    }
+    
+    Some(data)
+}
+
+/// 解析 RPOS 数据包
+/// 格式: lon(f64) lat(f64) ele_m(f64) y_agl_m(f32) theta psi phi vx vy vz p q r（均为 f32）
+fn parse_rpos_data(buf: &[u8]) -> Option<FlightDataUpdate> {
+    if buf.len() < 28 {
+        return None;
+    }
+    
+    let f64_at = |offset: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&buf[offset..offset + 8]);
+        f64::from_le_bytes(bytes)
+    };
+    let f32_at = |offset: usize| {
+        f32::from_le_bytes([buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]]) as f64
+    };
+    
+    Some(FlightDataUpdate {
+        longitude: Some(f64_at(0)),
+        latitude: Some(f64_at(8)),
+        altitude: Some(f64_at(16) * METERS_TO_FEET),
+        altitude_agl: Some(f32_at(24) * METERS_TO_FEET),
+        ..FlightDataUpdate::default()
+    })
+}
+
+/// DATA 包中的字段
+#[derive(Debug, Clone, Copy)]
+enum DataField {
+    Speed,          // 指示空速（节）
+    GroundSpeed,    // 地速（节）
+    VerticalSpeed,  // 垂直速度（英尺/分钟）
+    Heading,        // 磁航向（度）
+    Latitude,
+    Longitude,
+    Altitude,       // 海拔高度（英尺）
+    AltitudeAgl,    // 离地高度（英尺）
+    FlapRatio,
+    GearRatio,
+    ParkingBrake,
+    Com1Active,     // 频率单位为 10 kHz（如 11850）
+    Com1Standby,
+    Com2Active,
+    Com2Standby,
+    Nav1Active,
+    Nav1Standby,
+    Nav2Active,
+    Nav2Standby,
+    TransponderMode,
+    TransponderCode,
+}
+
+/// DATA 包解码表：(数据组编号, 组内位置, 字段)
+///
+/// 数据组编号对应 X-Plane「Data Output」界面中的行号。
+const DATA_GROUPS: &[(i32, usize, DataField)] = &[
+    // 3: Speeds
+    (3, 0, DataField::Speed),
+    (3, 3, DataField::GroundSpeed),
+    // 4: Mach, VVI, g-load
+    (4, 2, DataField::VerticalSpeed),
+    // 13: Trim, flap, slat, speedbrakes
+    (13, 3, DataField::FlapRatio),
+    // 14: Gear, brakes
+    (14, 0, DataField::GearRatio),
+    (14, 1, DataField::ParkingBrake),
+    // 17: Pitch, roll, headings
+    (17, 3, DataField::Heading),
+    // 20: Latitude, longitude, altitude
+    (20, 0, DataField::Latitude),
+    (20, 1, DataField::Longitude),
+    (20, 2, DataField::Altitude),
+    (20, 3, DataField::AltitudeAgl),
+    // 96: COM 1/2 frequency
+    (96, 0, DataField::Com1Active),
+    (96, 1, DataField::Com1Standby),
+    (96, 3, DataField::Com2Active),
+    (96, 4, DataField::Com2Standby),
+    // 97: NAV 1/2 frequency
+    (97, 0, DataField::Nav1Active),
+    (97, 1, DataField::Nav1Standby),
+    (97, 4, DataField::Nav2Active),
+    (97, 5, DataField::Nav2Standby),
+    // 104: Transponder
+    (104, 0, DataField::TransponderMode),
+    (104, 1, DataField::TransponderCode),
+];
+
+/// X-Plane 用 -999 表示该位置没有数据
+const DATA_NO_VALUE: f32 = -999.0;
+
+/// 离地高度低于该值且几乎没有垂直速度时视为在地面（DATA 包不含地面状态）
+const DATA_ON_GROUND_AGL: f64 = 15.0;
+
+impl DataField {
+    fn apply(self, data: &mut FlightDataUpdate, value: f32) {
+        let v = value as f64;
+        match self {
+            DataField::Speed => data.speed = Some(v),
+            DataField::GroundSpeed => data.ground_speed = Some(v),
+            DataField::VerticalSpeed => data.vertical_speed = Some(v),
+            DataField::Heading => data.heading = Some(v),
+            DataField::Latitude => data.latitude = Some(v),
+            DataField::Longitude => data.longitude = Some(v),
+            DataField::Altitude => data.altitude = Some(v),
+            DataField::AltitudeAgl => data.altitude_agl = Some(v),
+            DataField::FlapRatio => data.flap_ratio = Some(v),
+            DataField::GearRatio => data.gear_ratio = Some(v),
+            DataField::ParkingBrake => data.parking_brake = Some(v),
+            DataField::Com1Active => data.com1_active = Some(v / 100.0),
+            DataField::Com1Standby => data.com1_standby = Some(v / 100.0),
+            DataField::Com2Active => data.com2_active = Some(v / 100.0),
+            DataField::Com2Standby => data.com2_standby = Some(v / 100.0),
+            DataField::Nav1Active => data.nav1_active = Some(v / 100.0),
+            DataField::Nav1Standby => data.nav1_standby = Some(v / 100.0),
+            DataField::Nav2Active => data.nav2_active = Some(v / 100.0),
+            DataField::Nav2Standby => data.nav2_standby = Some(v / 100.0),
+            DataField::TransponderMode => data.transponder_mode = Some(value as u8),
+            DataField::TransponderCode => data.transponder_code = Some(value as u16),
+        }
+    }
+}
+
+/// 解析传统 DATA 包（「Data Output」界面勾选的数据组）
+/// 每组 36 字节：index(i32) + 8 个 f32
+fn parse_data_packet(buf: &[u8]) -> Option<FlightDataUpdate> {
+    if buf.len() < 36 {
+        return None;
+    }
+    
+    let mut data = FlightDataUpdate::default();
+    
+    for group in buf.chunks_exact(36) {
+        let index = i32::from_le_bytes([group[0], group[1], group[2], group[3]]);
         
-        offset += 8;
+        for (_, slot, field) in DATA_GROUPS.iter().filter(|(i, _, _)| *i == index) {
+            let offset = 4 + slot * 4;
+            let value = f32::from_le_bytes([group[offset], group[offset + 1], group[offset + 2], group[offset + 3]]);
+            
+            if value != DATA_NO_VALUE {
+                field.apply(&mut data, value);
+            }
+        }
+    }
+    
+    // 根据离地高度估算地面状态
+    if let Some(agl) = data.altitude_agl {
+        let level = data.vertical_speed.map(|vs| vs.abs() < 100.0).unwrap_or(true);
+        data.on_ground = Some(agl < DATA_ON_GROUND_AGL && level);
     }
     
     Some(data)
 }
 
-fn parse_data_packet(buf: &[u8]) -> Option<FlightData> {
-    let mut data = FlightData::default();
-    let mut offset = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // RREF 响应：海平面（高度 0）、平飞（垂直速度 0）、在地面
+    const RREF_LEVEL_AT_SEA_LEVEL: &[u8] = &[
+        0x52, 0x52, 0x45, 0x46, 0x2c, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x80, 0x3f,
+    ];
+    
+    // RPOS 响应：双精度经纬度，海拔 -12.5 米（低于海平面的机场）
+    const RPOS_NEGATIVE_ELEVATION: &[u8] = &[
+        0x52, 0x50, 0x4f, 0x53, 0x34, 0x0d, 0xd6, 0x80, 0x78, 0x6a, 0x25, 0x5d,
+        0x40, 0x9c, 0x41, 0x45, 0x7c, 0x41, 0x0a, 0x44, 0x40, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x29, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    
+    fn rref_packet(values: &[(i32, f32)]) -> Vec<u8> {
+        let mut packet = b"RREF,".to_vec();
+        for (id, value) in values {
+            packet.extend_from_slice(&id.to_le_bytes());
+            packet.extend_from_slice(&value.to_le_bytes());
+        }
+        packet
+    }
     
-    while offset + 36 <= buf.len() {
-        let index = buf[offset] as usize;
-        offset += 4;
+    #[test]
+    fn test_zero_values_are_applied() {
+        let mut current = FlightData {
+            altitude: 3000.0,
+            vertical_speed: -700.0,
+            on_ground: false,
+            ..FlightData::default()
+        };
         
-        let values: Vec<f32> = (0..8)
-            .map(|i| {
-                let bytes = &buf[offset + i * 4..offset + (i + 1) * 4];
-                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
-            })
-            .collect();
+        parse_xplane_packet(RREF_LEVEL_AT_SEA_LEVEL).unwrap().apply_to(&mut current);
         
-        match index {
-            3 => {
-                // Speeds - 空速（节）
-                data.speed = values[0] as f64;
-            }
-            17 => {
-                // Pitch, Roll, Heading - 航向（度）
-                data.heading = values[2] as f64;
-            }
-            20 => {
-                // Position - 高度（英尺）
-                data.altitude = values[2] as f64;
+        assert_eq!(current.altitude, 0.0);
+        assert_eq!(current.vertical_speed, 0.0);
+        assert!(current.on_ground);
+    }
+    
+    #[test]
+    fn test_partial_packet_keeps_other_fields() {
+        let mut current = FlightData {
+            speed: 250.0,
+            heading: 90.0,
+            ..FlightData::default()
+        };
+        
+        let update = parse_xplane_packet(&rref_packet(&[(3, 0.0)])).unwrap();
+        assert_eq!(update, FlightDataUpdate { heading: Some(0.0), ..FlightDataUpdate::default() });
+        
+        update.apply_to(&mut current);
+        assert_eq!(current.heading, 0.0);
+        assert_eq!(current.speed, 250.0);
+    }
+    
+    #[test]
+    fn test_rref_unit_conversion() {
+        let update = parse_xplane_packet(&rref_packet(&[
+            (2, 1000.0),     // 米
+            (9, 100.0),      // 米/秒
+            (12, 118500.0),  // kHz
+            (16, 7700.0),
+        ])).unwrap();
+        
+        assert!((update.altitude.unwrap() - 3280.84).abs() < 0.01);
+        assert!((update.ground_speed.unwrap() - 194.3844).abs() < 0.001);
+        assert_eq!(update.com1_active, Some(118.5));
+        assert_eq!(update.transponder_code, Some(7700));
+    }
+    
+    #[test]
+    fn test_rpos_double_precision_position() {
+        let update = parse_xplane_packet(RPOS_NEGATIVE_ELEVATION).unwrap();
+        
+        // 双精度保留到 1e-10 度，单精度只能精确到约 1e-5 度
+        assert!((update.latitude.unwrap() - 40.08012345678).abs() < 1e-10);
+        assert!((update.longitude.unwrap() - 116.58462345678).abs() < 1e-10);
+        assert!((update.altitude.unwrap() - (-12.5 * METERS_TO_FEET)).abs() < 1e-9);
+        assert_eq!(update.altitude_agl, Some(0.0));
+        assert_eq!(update.speed, None);
+    }
+    
+    #[test]
+    fn test_equator_and_meridian_position() {
+        let mut packet = b"RPOS4".to_vec();
+        for value in [0.0f64, 0.0, 100.0] {
+            packet.extend_from_slice(&value.to_le_bytes());
+        }
+        packet.extend_from_slice(&[0u8; 40]);
+        
+        let mut current = FlightData { latitude: 1.0, longitude: 1.0, ..FlightData::default() };
+        parse_xplane_packet(&packet).unwrap().apply_to(&mut current);
+        
+        assert_eq!(current.latitude, 0.0);
+        assert_eq!(current.longitude, 0.0);
+    }
+    
+    fn data_packet(groups: &[(i32, [f32; 8])]) -> Vec<u8> {
+        let mut packet = b"DATA*".to_vec();
+        for (index, values) in groups {
+            packet.extend_from_slice(&index.to_le_bytes());
+            for value in values {
+                packet.extend_from_slice(&value.to_le_bytes());
             }
-            _ => {}
         }
+        packet
+    }
+    
+    #[test]
+    fn test_data_packet_decoding() {
+        let packet = data_packet(&[
+            (3, [142.0, 141.0, 150.0, 148.0, -999.0, 163.0, 172.0, 170.0]),
+            (4, [0.23, 0.0, -750.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+            (17, [-2.5, 0.5, 181.0, 178.0, 0.0, 0.0, 0.0, 0.0]),
+            (20, [40.0801, 116.5846, 1650.0, 1530.0, 0.0, 1650.0, 40.0, 116.0]),
+            (96, [11850.0, 12160.0, 0.0, 12475.0, 12700.0, 0.0, 0.0, 0.0]),
+            (104, [2.0, 4521.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        ]);
         
-        offset += 32;
+        let update = parse_xplane_packet(&packet).unwrap();
+        
+        assert_eq!(update.speed, Some(142.0));
+        assert_eq!(update.ground_speed, Some(148.0));
+        assert_eq!(update.vertical_speed, Some(-750.0));
+        assert_eq!(update.heading, Some(178.0));
+        assert!((update.latitude.unwrap() - 40.0801).abs() < 1e-4);
+        assert_eq!(update.altitude, Some(1650.0));
+        assert_eq!(update.altitude_agl, Some(1530.0));
+        assert_eq!(update.on_ground, Some(false));
+        assert_eq!(update.com1_active, Some(118.5));
+        assert_eq!(update.com2_standby, Some(127.0));
+        assert_eq!(update.transponder_mode, Some(2));
+        assert_eq!(update.transponder_code, Some(4521));
+        assert_eq!(update.gear_ratio, None);
     }
     
-    Some(data)
+    #[test]
+    fn test_data_packet_skips_missing_values() {
+        let packet = data_packet(&[
+            (14, [1.0, -999.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            (20, [0.0, 0.0, 13.0, 8.0, 1.0, 13.0, 0.0, 0.0]),
+        ]);
+        
+        let update = parse_xplane_packet(&packet).unwrap();
+        
+        assert_eq!(update.gear_ratio, Some(1.0));
+        assert_eq!(update.parking_brake, None);
+        assert_eq!(update.latitude, Some(0.0));
+        assert_eq!(update.on_ground, Some(true));
+    }
+    
+    #[test]
+    fn test_rejects_truncated_packets() {
+        assert_eq!(parse_xplane_packet(b"RRE"), None);
+        assert_eq!(parse_xplane_packet(b"RREF,\x01\x00"), None);
+        assert_eq!(parse_xplane_packet(&RPOS_NEGATIVE_ELEVATION[..20]), None);
+        assert_eq!(parse_xplane_packet(b"XXXX,\x00\x00\x00\x00\x00\x00\x00\x00"), None);
+    }
+    
+    #[test]
+    fn test_tail_number_is_assembled_from_rref_bytes() {
+        let mut current = FlightData::default();
+        let mut values: Vec<(i32, f32)> = b"N172SP".iter()
+            .enumerate()
+            .map(|(i, &c)| (TAILNUM_BASE_ID + i as i32, c as f32))
+            .collect();
+        values.push((TAILNUM_BASE_ID + 6, 0.0));
+        
+        parse_xplane_packet(&rref_packet(&values)).unwrap().apply_to(&mut current);
+        assert_eq!(current.callsign, "N172SP");
+        
+        // 只更新部分字符时保留其余字符
+        parse_xplane_packet(&rref_packet(&[(TAILNUM_BASE_ID + 5, b'X' as f32)])).unwrap().apply_to(&mut current);
+        assert_eq!(current.callsign, "N172SX");
+        
+        // 机型代码不足 4 个字符时以 0 结束
+        let values: Vec<(i32, f32)> = [b'R', b'4', b'4', 0].iter()
+            .enumerate()
+            .map(|(i, &c)| (ICAO_TYPE_BASE_ID + i as i32, c as f32))
+            .collect();
+        parse_xplane_packet(&rref_packet(&values)).unwrap().apply_to(&mut current);
+        assert_eq!(current.aircraft_type, "R44");
+    }
+    
+    #[test]
+    fn test_tcas_targets_are_added_to_traffic_table() {
+        let traffic: SharedTraffic = Arc::new(Mutex::new(crate::modules::traffic::TrafficTable::new()));
+        let base = TCAS_BASE_ID + 2 * TCAS_ID_STRIDE;
+        let packet = rref_packet(&[
+            (base, 40.08),
+            (base + 1, 116.58),
+            (base + 2, 1000.0),
+            (base + 3, 180.0),
+            (base + 6, 0.0),
+            (base + 7, 7_864_320.0),    // 0x780000
+            (base + 8, b'A' as f32),
+            (base + 9, b'3' as f32),
+            (base + 10, b'2' as f32),
+            (base + 11, b'0' as f32),
+        ]);
+        
+        let mut tcas = TcasTargets::new();
+        tcas.apply(&parse_xplane_packet(&packet).unwrap().tcas, &traffic);
+        
+        let targets = traffic.lock().unwrap().targets();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].id, "xplane-2");
+        assert_eq!(targets[0].aircraft_type, "A320");
+        assert_eq!(targets[0].callsign, "780000");
+        assert!((targets[0].altitude - 3280.84).abs() < 0.1);
+        
+        // S 模式地址变为 0 表示目标已消失
+        tcas.apply(&parse_xplane_packet(&rref_packet(&[(base + 7, 0.0)])).unwrap().tcas, &traffic);
+        assert!(traffic.lock().unwrap().targets().is_empty());
+    }
+    
+    #[test]
+    fn test_config_validation_is_offline() {
+        // 主机名只检查格式，连接时才解析，保存的设置不会因暂时无法解析而被重置
+        let remote = XPlaneConfig { sim_host: "xplane-pc.lan".to_string(), ..XPlaneConfig::default() };
+        assert!(remote.validate().is_ok());
+        assert!(XPlaneConfig { sim_host: "x plane".to_string(), ..XPlaneConfig::default() }.validate().is_err());
+        
+        let local = XPlaneConfig { listen_port: 49000, ..XPlaneConfig::default() };
+        assert!(local.validate().is_err(), "本机端口冲突");
+        assert!(XPlaneConfig { sim_host: "localhost".to_string(), ..local }.validate().is_err());
+        assert_eq!(XPlaneConfig::default().resolve().unwrap(), "127.0.0.1:49000".parse().unwrap());
+    }
+    
+    #[test]
+    fn test_reconnect_on_same_port() {
+        // 由系统分配两个不同的空闲端口，避免与本机其他程序冲突
+        let probes = [UdpSocket::bind(("127.0.0.1", 0)).unwrap(), UdpSocket::bind(("127.0.0.1", 0)).unwrap()];
+        let [listen_port, sim_port] = probes.map(|probe| probe.local_addr().unwrap().port());
+        let config = XPlaneConfig { sim_port, listen_port, ..XPlaneConfig::default() };
+        assert!(config.validate().is_ok());
+        let recorder: SharedRecorder = Arc::new(Mutex::new(None));
+        let traffic: SharedTraffic = Arc::new(Mutex::new(crate::modules::traffic::TrafficTable::new()));
+        
+        let mut first = SimulatorConnection::new(&config, recorder.clone(), traffic.clone()).unwrap();
+        first.disconnect();
+        
+        // disconnect 返回后端口应已释放，可立即重新绑定
+        assert!(SimulatorConnection::new(&config, recorder, traffic).is_ok());
+    }
 }
@@ -0,0 +1,556 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use super::atc_database::{ATCDatabase, Taxiway};
+
+/// 换用另一条滑行道的附加代价（米），使路线尽量少换滑行道
+const TURN_PENALTY_M: f64 = 150.0;
+
+/// 沿跑道滑行的代价倍数（只在没有其他路线时沿跑道滑行）
+const RUNWAY_COST_FACTOR: f64 = 3.0;
+
+/// 等待点到跑道入口的距离按沿跑道滑行计价，使等待点尽量靠近指定跑道端的入口
+const HOLDING_POINT_FACTOR: f64 = RUNWAY_COST_FACTOR;
+
+/// 起点、停机位与滑行网络的最大距离（米）
+const MAX_SNAP_DISTANCE_M: f64 = 500.0;
+
+/// 起点与停机位的最大距离（米），用于标注起点停机位
+const PARKING_RADIUS_M: f64 = 60.0;
+
+/// 机场滑行网络（来自 apt.dat 1201/1202/1204 或 Little Navmap taxi_path）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaxiNetwork {
+    pub nodes: Vec<TaxiNode>,
+    pub segments: Vec<TaxiSegment>,
+    pub parking: Vec<TaxiParking>,
+    #[serde(default)]
+    pub runway_ends: Vec<TaxiRunwayEnd>,
+}
+
+/// 滑行网络节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiNode {
+    pub id: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 滑行网络的一段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiSegment {
+    pub from: u32,
+    pub to: u32,
+    pub name: String,               // 滑行道名称，跑道段为跑道名称
+    pub runway: bool,
+    pub oneway: bool,               // 只能从 from 滑向 to
+    pub max_size: Option<char>,     // 允许的最大翼展等级（ICAO 代码字母 A~F），None 表示不限
+    pub runway_zone: Vec<String>,   // 所在跑道活动区对应的跑道端
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// 停机位位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiParking {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 跑道入口位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiRunwayEnd {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 滑行起点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxiStart {
+    Position { latitude: f64, longitude: f64 },
+    Gate(String),
+}
+
+/// 滑行终点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxiTarget {
+    Runway(String),   // 跑道端的等待点，如 "36R"
+    Gate(String),
+}
+
+/// 路线限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteOptions {
+    /// 本机翼展等级（ICAO 代码字母），超出限制的滑行道不可用
+    #[serde(default)]
+    pub size: Option<char>,
+    /// 关闭的滑行道
+    #[serde(default)]
+    pub closed: Vec<String>,
+}
+
+impl RouteOptions {
+    pub fn is_closed(&self, name: &str) -> bool {
+        self.closed.iter().any(|closed| closed.trim().eq_ignore_ascii_case(name))
+    }
+
+    fn allows(&self, segment: &TaxiSegment) -> bool {
+        if segment.closed || (!segment.name.is_empty() && self.is_closed(&segment.name)) {
+            return false;
+        }
+        match (self.size, segment.max_size) {
+            (Some(size), Some(max_size)) => size.to_ascii_uppercase() <= max_size,
+            _ => true,
+        }
+    }
+}
+
+/// 规划结果：按顺序经过的网络段和总长度（米）
+#[derive(Debug, Clone)]
+pub struct NetworkRoute<'a> {
+    pub segments: Vec<&'a TaxiSegment>,
+    pub distance: f64,
+}
+
+/// 搜索状态：所在节点和到达该节点所经过的段（用于计算换道代价）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    node: u32,
+    via: Option<usize>,
+}
+
+/// 优先队列元素（代价小的先出队）
+struct Queued {
+    cost: f64,
+    state: State,
+    arrived: bool,  // 在终点停下（代价含等待点到跑道入口的距离）
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl TaxiNetwork {
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// 停机位位置
+    pub fn parking_position(&self, name: &str) -> Option<(f64, f64)> {
+        self.parking.iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+            .map(|p| (p.latitude, p.longitude))
+    }
+
+    /// 附近的停机位名称
+    pub fn nearest_parking(&self, lat: f64, lon: f64) -> Option<&str> {
+        self.parking.iter()
+            .map(|p| (p, distance_m(lat, lon, p.latitude, p.longitude)))
+            .filter(|(_, d)| *d <= PARKING_RADIUS_M)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| p.name.as_str())
+    }
+
+    /// 跑道入口位置（只匹配指定的一端，不含对端）
+    pub fn runway_end_position(&self, end: &str) -> Option<(f64, f64)> {
+        let end = normalize_runway_end(end);
+        self.runway_ends.iter()
+            .find(|e| normalize_runway_end(&e.name) == end)
+            .map(|e| (e.latitude, e.longitude))
+    }
+
+    /// 从指定位置滑行到跑道端等待点或停机位的最短路线
+    ///
+    /// 代价为滑行距离加换道代价；遵守单向段、翼展限制和关闭的滑行道。
+    /// 活动区不区分跑道方向，已知入口位置时排除离对端入口更近的等待点，
+    /// 并把等待点到指定入口的距离计入代价，优先停在该端入口处
+    pub fn find_route(&self, lat: f64, lon: f64, target: &TaxiTarget, options: &RouteOptions) -> Option<NetworkRoute<'_>> {
+        let positions: HashMap<u32, (f64, f64)> = self.nodes.iter()
+            .map(|n| (n.id, (n.latitude, n.longitude)))
+            .collect();
+
+        // 邻接表：节点 → （段序号，相邻节点）
+        let mut adjacency: HashMap<u32, Vec<(usize, u32)>> = HashMap::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            if !options.allows(segment) {
+                continue;
+            }
+            adjacency.entry(segment.from).or_default().push((index, segment.to));
+            if !segment.oneway {
+                adjacency.entry(segment.to).or_default().push((index, segment.from));
+            }
+        }
+
+        let start = self.nearest_node(&positions, lat, lon)?;
+        // 终点 → 停下后的附加代价
+        let targets: HashMap<u32, f64> = match target {
+            TaxiTarget::Runway(end) => {
+                let threshold = self.runway_end_position(end);
+                let reciprocal = reciprocal_end(&normalize_runway_end(end))
+                    .and_then(|r| self.runway_end_position(&r));
+                self.segments.iter()
+                    .filter(|s| s.runway_zone.iter().any(|zone| same_runway_end(zone, end))
+                        || (s.runway && runway_ends(&s.name).iter().any(|e| same_runway_end(e, end))))
+                    .flat_map(|s| [s.from, s.to])
+                    .filter_map(|id| {
+                        let (n_lat, n_lon) = positions.get(&id)?;
+                        let Some((t_lat, t_lon)) = threshold else { return Some((id, 0.0)) };
+                        let to_threshold = distance_m(*n_lat, *n_lon, t_lat, t_lon);
+                        if reciprocal.is_some_and(|(r_lat, r_lon)| distance_m(*n_lat, *n_lon, r_lat, r_lon) < to_threshold) {
+                            return None;
+                        }
+                        Some((id, to_threshold * HOLDING_POINT_FACTOR))
+                    })
+                    .collect()
+            }
+            TaxiTarget::Gate(name) => {
+                let (p_lat, p_lon) = self.parking_position(name)?;
+                self.nearest_node(&positions, p_lat, p_lon).map(|id| (id, 0.0)).into_iter().collect()
+            }
+        };
+        if targets.is_empty() {
+            return None;
+        }
+
+        let mut best: HashMap<State, f64> = HashMap::new();
+        let mut previous: HashMap<State, State> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        let origin = State { node: start, via: None };
+        best.insert(origin, 0.0);
+        queue.push(Queued { cost: 0.0, state: origin, arrived: false });
+
+        while let Some(Queued { cost, state, arrived }) = queue.pop() {
+            if arrived {
+                return Some(self.build_route(state, &previous, &positions));
+            }
+            if best.get(&state).is_some_and(|b| cost > *b) {
+                continue;
+            }
+            // 在等待点停下，不再穿过活动区
+            if let Some(extra) = targets.get(&state.node) {
+                queue.push(Queued { cost: cost + extra, state, arrived: true });
+                continue;
+            }
+
+            for (index, next) in adjacency.get(&state.node).map(Vec::as_slice).unwrap_or_default() {
+                let segment = &self.segments[*index];
+                let (Some(a), Some(b)) = (positions.get(&state.node), positions.get(next)) else { continue };
+
+                let mut step = distance_m(a.0, a.1, b.0, b.1);
+                if segment.runway {
+                    step *= RUNWAY_COST_FACTOR;
+                }
+                let previous_name = state.via.map(|i| self.segments[i].name.as_str());
+                if previous_name.is_some_and(|name| name != segment.name) {
+                    step += TURN_PENALTY_M;
+                }
+
+                let next_state = State { node: *next, via: Some(*index) };
+                let next_cost = cost + step;
+                if best.get(&next_state).is_none_or(|b| next_cost < *b) {
+                    best.insert(next_state, next_cost);
+                    previous.insert(next_state, state);
+                    queue.push(Queued { cost: next_cost, state: next_state, arrived: false });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// 离指定位置最近的网络节点（不考虑限制，受限时应无路可走而不是换一个起点）
+    fn nearest_node(&self, positions: &HashMap<u32, (f64, f64)>, lat: f64, lon: f64) -> Option<u32> {
+        self.segments.iter()
+            .flat_map(|s| [s.from, s.to])
+            .collect::<HashSet<u32>>()
+            .into_iter()
+            .filter_map(|id| {
+                let (n_lat, n_lon) = positions.get(&id)?;
+                Some((id, distance_m(lat, lon, *n_lat, *n_lon)))
+            })
+            .filter(|(_, d)| *d <= MAX_SNAP_DISTANCE_M)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(id, _)| id)
+    }
+
+    fn build_route(&self, end: State, previous: &HashMap<State, State>, positions: &HashMap<u32, (f64, f64)>) -> NetworkRoute<'_> {
+        let mut segments = Vec::new();
+        let mut distance = 0.0;
+        let mut state = end;
+        while let (Some(index), Some(prev)) = (state.via, previous.get(&state)) {
+            if let (Some(a), Some(b)) = (positions.get(&prev.node), positions.get(&state.node)) {
+                distance += distance_m(a.0, a.1, b.0, b.1);
+            }
+            segments.push(&self.segments[index]);
+            state = *prev;
+        }
+        segments.reverse();
+        NetworkRoute { segments, distance }
+    }
+}
+
+/// 没有滑行网络时按滑行道连接关系搜索：经过滑行道最少的路线
+///
+/// 返回依次经过的滑行道名称，`start` 为起点可进入的滑行道，`targets` 为可到达终点的滑行道
+pub fn find_named_route(taxiways: &[Taxiway], start: &[String], targets: &[&str], options: &RouteOptions) -> Option<Vec<String>> {
+    let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+    for taxiway in taxiways.iter().filter(|t| !options.is_closed(&t.name)) {
+        for other in taxiway.connects_to.iter().filter(|o| !options.is_closed(o)) {
+            neighbours.entry(&taxiway.name).or_default().push(other);
+            neighbours.entry(other).or_default().push(&taxiway.name);
+        }
+    }
+
+    let mut previous: HashMap<&str, Option<&str>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for name in start.iter().filter(|n| !options.is_closed(n)) {
+        if previous.insert(name, None).is_none() {
+            queue.push_back(name.as_str());
+        }
+    }
+
+    while let Some(name) = queue.pop_front() {
+        if targets.contains(&name) {
+            let mut route = vec![name.to_string()];
+            let mut current = name;
+            while let Some(Some(prev)) = previous.get(current) {
+                route.push(prev.to_string());
+                current = prev;
+            }
+            route.reverse();
+            return Some(route);
+        }
+        for next in neighbours.get(name).map(Vec::as_slice).unwrap_or_default() {
+            if !previous.contains_key(next) {
+                previous.insert(next, Some(name));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// 跑道名称中的跑道端（"18L/36R" → ["18L", "36R"]）
+pub fn runway_ends(name: &str) -> Vec<&str> {
+    name.split(['/', ',', ' ']).filter(|end| !end.is_empty()).collect()
+}
+
+/// 规范化跑道端写法（"RWY36R"、"RW36R"、"36r" → "36R"，"9" → "09"）
+pub fn normalize_runway_end(end: &str) -> String {
+    let end = end.trim().to_uppercase();
+    let end = end.strip_prefix("RWY").or_else(|| end.strip_prefix("RW")).unwrap_or(&end).trim();
+    let digits: String = end.chars().take_while(|c| c.is_ascii_digit()).collect();
+    match digits.parse::<u8>() {
+        Ok(number) => format!("{:02}{}", number, &end[digits.len()..]),
+        Err(_) => end.to_string(),
+    }
+}
+
+/// 是否为同一条跑道的一端或其对端（"16L" 与 "34R"）
+pub fn same_runway_end(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_runway_end(a), normalize_runway_end(b));
+    a == b || reciprocal_end(&a).is_some_and(|r| r == b)
+}
+
+/// 对端跑道号（"16L" → "34R"）
+fn reciprocal_end(end: &str) -> Option<String> {
+    let digits: String = end.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let number: u8 = digits.parse().ok().filter(|n| (1..=36).contains(n))?;
+    let side = match &end[digits.len()..] {
+        "L" => "R",
+        "R" => "L",
+        other => other,
+    };
+    Some(format!("{:02}{}", (number + 17) % 36 + 1, side))
+}
+
+/// 两点距离（米）
+fn distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    ATCDatabase::calculate_distance(lat1, lon1, lat2, lon2) * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::atc_database::TaxiwayType;
+
+    fn segment(from: u32, to: u32, name: &str) -> TaxiSegment {
+        TaxiSegment {
+            from,
+            to,
+            name: name.to_string(),
+            runway: false,
+            oneway: false,
+            max_size: None,
+            runway_zone: vec![],
+            closed: false,
+        }
+    }
+
+    /// 停机坪 → A / B 两条滑行道 → 18 号跑道入口等待点，C → 36 号跑道入口等待点
+    ///
+    /// ```text
+    ///  4(等待点)──[zone]── 5(18 入口)
+    ///  |A          6(等待点)  |
+    ///  |           |B         |跑道
+    ///  1───────────2──C──3──[zone]── 7(36 入口)
+    ///  |apron
+    ///  0(停机位)
+    /// ```
+    fn sample_network() -> TaxiNetwork {
+        let node = |id, latitude, longitude| TaxiNode { id, latitude, longitude };
+        let runway_end = |name: &str, latitude, longitude| TaxiRunwayEnd { name: name.to_string(), latitude, longitude };
+        let mut zone = segment(4, 5, "A");
+        zone.runway_zone = vec!["36".to_string()];
+        let mut b_north = segment(2, 6, "B");
+        b_north.max_size = Some('C');
+        let mut b_zone = segment(6, 5, "B");
+        b_zone.runway_zone = vec!["36".to_string()];
+        let mut c_zone = segment(3, 7, "C");
+        c_zone.runway_zone = vec!["18".to_string()];
+
+        TaxiNetwork {
+            nodes: vec![
+                node(0, 36.5000, 103.6000),
+                node(1, 36.5010, 103.6000),
+                node(2, 36.5010, 103.6020),
+                node(4, 36.5100, 103.6015),
+                node(5, 36.5100, 103.6030),
+                node(6, 36.5095, 103.6020),
+                node(3, 36.5010, 103.6025),
+                node(7, 36.5000, 103.6030),
+            ],
+            segments: vec![
+                segment(0, 1, "apron"),
+                segment(1, 4, "A"),
+                zone,
+                segment(1, 2, "L"),
+                b_north,
+                b_zone,
+                segment(2, 3, "C"),
+                c_zone,
+            ],
+            parking: vec![TaxiParking { name: "Stand 5".to_string(), latitude: 36.4999, longitude: 103.6000 }],
+            runway_ends: vec![runway_end("18", 36.5100, 103.6030), runway_end("36", 36.5000, 103.6030)],
+        }
+    }
+
+    fn names(route: &NetworkRoute) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for segment in &route.segments {
+            if names.last() != Some(&segment.name) {
+                names.push(segment.name.clone());
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn test_route_to_holding_point() {
+        let network = sample_network();
+        let target = TaxiTarget::Runway("RWY18".to_string());
+        let route = network.find_route(36.4999, 103.6000, &target, &RouteOptions::default()).unwrap();
+        assert_eq!(names(&route), vec!["apron", "A"]);
+        // 停在活动区外的等待点，不进入跑道
+        assert!(route.segments.iter().all(|s| s.runway_zone.is_empty()));
+        assert!((route.distance - 1121.0).abs() < 5.0, "{}", route.distance);
+
+        // 对端跑道号停在另一端入口的等待点，而不是 18 号跑道的等待点
+        let reciprocal = network.find_route(36.4999, 103.6000, &TaxiTarget::Runway("36".to_string()), &RouteOptions::default()).unwrap();
+        assert_eq!(names(&reciprocal), vec!["apron", "L", "C"]);
+        assert_ne!(reciprocal.segments.last().unwrap().to, route.segments.last().unwrap().to);
+
+        // 不知道入口位置时停在最近的等待点
+        let mut network = network;
+        network.runway_ends.clear();
+        let nearest = network.find_route(36.4999, 103.6000, &target, &RouteOptions::default()).unwrap();
+        assert_eq!(names(&nearest), vec!["apron", "L", "C"]);
+        assert_eq!(network.nearest_parking(36.5, 103.6), Some("Stand 5"));
+    }
+
+    #[test]
+    fn test_route_respects_limits() {
+        let mut network = sample_network();
+        let target = TaxiTarget::Runway("18".to_string());
+
+        // A 关闭时改走 L、B
+        let closed = RouteOptions { size: None, closed: vec!["a".to_string()] };
+        let route = network.find_route(36.4999, 103.6000, &target, &closed).unwrap();
+        assert_eq!(names(&route), vec!["apron", "L", "B"]);
+
+        // B 只允许 C 类以下，E 类飞机无路可走
+        let heavy = RouteOptions { size: Some('E'), closed: vec!["A".to_string()] };
+        assert!(network.find_route(36.4999, 103.6000, &target, &heavy).is_none());
+
+        // 单向段不能逆行
+        network.segments[1] = TaxiSegment { oneway: true, ..segment(4, 1, "A") };
+        let route = network.find_route(36.4999, 103.6000, &target, &RouteOptions::default()).unwrap();
+        assert_eq!(names(&route), vec!["apron", "L", "B"]);
+    }
+
+    #[test]
+    fn test_route_to_gate() {
+        let network = sample_network();
+        let route = network.find_route(36.5100, 103.5999, &TaxiTarget::Gate("stand 5".to_string()), &RouteOptions::default()).unwrap();
+        assert_eq!(names(&route), vec!["A", "apron"]);
+        assert!(network.find_route(36.51, 103.6, &TaxiTarget::Gate("Stand 9".to_string()), &RouteOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_named_route() {
+        let taxiway = |name: &str, connects_to: &[&str]| Taxiway {
+            name: name.to_string(),
+            taxiway_type: TaxiwayType::Main,
+            connects_to: connects_to.iter().map(|c| c.to_string()).collect(),
+        };
+        let taxiways = vec![
+            taxiway("A", &["A3", "B"]),
+            taxiway("B", &["B2", "01/19"]),
+            taxiway("C", &["A3", "01/19"]),
+        ];
+        let start = vec!["A3".to_string()];
+
+        let route = find_named_route(&taxiways, &start, &["01/19"], &RouteOptions::default()).unwrap();
+        assert_eq!(route, vec!["A3", "C", "01/19"]);
+        let closed = RouteOptions { size: None, closed: vec!["C".to_string()] };
+        assert_eq!(find_named_route(&taxiways, &start, &["01/19"], &closed).unwrap(), vec!["A3", "A", "B", "01/19"]);
+
+        // 预设路线可反向使用；关闭后没有滑行网络，也无法按连接关系到达跑道
+        let mut db = ATCDatabase::with_store(None);
+        let zbaa = db.get_airport("ZBAA").unwrap();
+        let target = TaxiTarget::Runway("36R".to_string());
+        let route = zbaa.plan_taxi_route(&TaxiStart::Gate("B12".to_string()), &target, &RouteOptions::default()).unwrap();
+        assert_eq!((route.from.as_str(), route.to.as_str()), ("T2-B12", "RWY36R"));
+        assert_eq!(route.route, vec!["B2", "B", "R5"]);
+        let closed = RouteOptions { size: None, closed: vec!["R5".to_string()] };
+        assert!(zbaa.plan_taxi_route(&TaxiStart::Gate("B12".to_string()), &target, &closed).is_none());
+
+        assert_eq!(normalize_runway_end("rwy9"), "09");
+        assert!(same_runway_end("16L", "34R"));
+        assert!(!same_runway_end("16L", "34L"));
+        // 超出 01~36 的跑道号没有对端
+        assert!(!same_runway_end("RWY250", "15"));
+        assert!(sample_network().find_route(36.4999, 103.6000, &TaxiTarget::Runway("RWY250".to_string()), &RouteOptions::default()).is_none());
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use super::flight_phase::FlightPhase;
+use super::simulator::FlightData;
+
+/// 接收线程与 Tauri 命令共享的交通表
+pub type SharedTraffic = Arc<Mutex<TrafficTable>>;
+
+/// 超过该时间未更新的目标视为已离开
+const TRAFFIC_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 交通通报范围：水平 10 海里、垂直 3000 英尺以内的空中目标
+const ADVISORY_RANGE_NM: f64 = 10.0;
+const ADVISORY_ALTITUDE_FT: f64 = 3000.0;
+
+/// 进近排序范围：前方 15 海里、航向相差 45° 以内
+const SEQUENCE_RANGE_NM: f64 = 15.0;
+const SEQUENCE_SECTOR_DEG: f64 = 45.0;
+
+/// 跑道占用判断：前方 3 海里、正前方 ±10° 以内、航向与本机相差 20° 以内的地面目标
+const RUNWAY_RANGE_NM: f64 = 3.0;
+const RUNWAY_SECTOR_DEG: f64 = 10.0;
+const RUNWAY_ALIGNMENT_DEG: f64 = 20.0;
+
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// 其他航空器（模拟器 AI 或联机飞机）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrafficTarget {
+    pub id: String,             // 数据源内唯一标识（如 "xplane-3"、"msfs-12"）
+    pub callsign: String,
+    pub aircraft_type: String,  // ICAO 机型代码（如 "A320"），未知时为空
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,          // 英尺
+    pub heading: f64,           // 度
+    pub ground_speed: f64,      // 节
+    pub vertical_speed: f64,    // 英尺/分钟
+    pub on_ground: bool,
+}
+
+impl Default for TrafficTarget {
+    fn default() -> Self {
+        TrafficTarget {
+            id: String::new(),
+            callsign: String::new(),
+            aircraft_type: String::new(),
+            latitude: 0.0,
+            longitude: 0.0,
+            altitude: 0.0,
+            heading: 0.0,
+            ground_speed: 0.0,
+            vertical_speed: 0.0,
+            on_ground: false,
+        }
+    }
+}
+
+impl TrafficTarget {
+    /// 通报中使用的描述：优先机型，其次呼号
+    pub fn describe(&self) -> &str {
+        if !self.aircraft_type.is_empty() {
+            &self.aircraft_type
+        } else if !self.callsign.is_empty() {
+            &self.callsign
+        } else {
+            "traffic"
+        }
+    }
+}
+
+/// 目标相对本机的位置
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficInfo {
+    pub target: TrafficTarget,
+    pub distance_nm: f64,
+    pub bearing: f64,               // 从本机看目标的真方位
+    pub clock: u8,                  // 钟点方位（1~12）
+    pub relative_altitude: f64,     // 目标高度 - 本机高度（英尺）
+}
+
+impl TrafficInfo {
+    fn new(own: &FlightData, target: &TrafficTarget) -> Self {
+        let distance_nm = distance_nm(own.latitude, own.longitude, target.latitude, target.longitude);
+        let bearing = bearing(own.latitude, own.longitude, target.latitude, target.longitude);
+
+        let relative = normalize(bearing - own.heading);
+        let clock = match ((relative / 30.0).round() as i32).rem_euclid(12) {
+            0 => 12,
+            c => c as u8,
+        };
+
+        TrafficInfo {
+            target: target.clone(),
+            distance_nm,
+            bearing,
+            clock,
+            relative_altitude: target.altitude - own.altitude,
+        }
+    }
+
+    /// 相对本机航向的方位（-180 ~ 180）
+    fn relative_bearing(&self, own: &FlightData) -> f64 {
+        let relative = normalize(self.bearing - own.heading);
+        if relative > 180.0 { relative - 360.0 } else { relative }
+    }
+}
+
+/// 当前交通态势
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrafficPicture {
+    pub advisories: Vec<TrafficInfo>,       // 需要通报的空中交通（按距离排序）
+    pub sequence: Option<u32>,              // 进近顺序（1 表示第一个）
+    pub following: Option<TrafficInfo>,     // 前机
+    pub runway_occupied: Option<TrafficInfo>, // 占用前方跑道的目标
+}
+
+impl TrafficPicture {
+    pub fn is_empty(&self) -> bool {
+        self.advisories.is_empty() && self.following.is_none() && self.runway_occupied.is_none()
+    }
+
+    /// 生成 LLM 上下文
+    pub fn get_atc_context(&self, language: &str) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let mut lines = Vec::new();
+
+        if language == "zh" {
+            lines.push("## 交通情况".to_string());
+            for info in &self.advisories {
+                lines.push(format!(
+                    "- 活动通报：{} 点钟方向 {:.0} 海里，{}，高度 {:.0} 英尺（{}）",
+                    info.clock, info.distance_nm, info.target.describe(), info.target.altitude,
+                    relative_altitude_zh(info.relative_altitude)
+                ));
+            }
+            if let (Some(number), Some(info)) = (self.sequence, &self.following) {
+                lines.push(format!("- 进近顺序：第 {} 个，跟随前方 {:.0} 海里的 {}", number, info.distance_nm, info.target.describe()));
+            }
+            if let Some(info) = &self.runway_occupied {
+                lines.push(format!("- 跑道占用：{} 位于前方 {:.1} 海里的跑道上，不得发布起飞或着陆许可", info.target.describe(), info.distance_nm));
+            }
+        } else {
+            lines.push("## Traffic".to_string());
+            for info in &self.advisories {
+                lines.push(format!(
+                    "- Traffic advisory: {} o'clock, {:.0} miles, {}, altitude {:.0} feet ({})",
+                    info.clock, info.distance_nm, info.target.describe(), info.target.altitude,
+                    relative_altitude_en(info.relative_altitude)
+                ));
+            }
+            if let (Some(number), Some(info)) = (self.sequence, &self.following) {
+                lines.push(format!("- Sequence: number {}, following the {} {:.0} miles ahead", number, info.target.describe(), info.distance_nm));
+            }
+            if let Some(info) = &self.runway_occupied {
+                lines.push(format!("- Runway occupied: {} on the runway {:.1} miles ahead, do NOT issue takeoff or landing clearance", info.target.describe(), info.distance_nm));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn relative_altitude_zh(diff: f64) -> String {
+    if diff.abs() < 500.0 {
+        "同高度".to_string()
+    } else if diff > 0.0 {
+        format!("高于本机 {:.0} 英尺", diff)
+    } else {
+        format!("低于本机 {:.0} 英尺", -diff)
+    }
+}
+
+fn relative_altitude_en(diff: f64) -> String {
+    if diff.abs() < 500.0 {
+        "same altitude".to_string()
+    } else if diff > 0.0 {
+        format!("{:.0} feet above", diff)
+    } else {
+        format!("{:.0} feet below", -diff)
+    }
+}
+
+/// 交通表 - 保存所有数据源报告的其他航空器
+#[derive(Debug, Default)]
+pub struct TrafficTable {
+    targets: HashMap<String, (TrafficTarget, Instant)>,
+}
+
+impl TrafficTable {
+    pub fn new() -> Self {
+        TrafficTable::default()
+    }
+
+    /// 添加或更新一个目标
+    pub fn update(&mut self, target: TrafficTarget) {
+        self.targets.insert(target.id.clone(), (target, Instant::now()));
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.targets.remove(id);
+    }
+
+    /// 用完整快照替换某个数据源（id 前缀）的全部目标
+    pub fn replace(&mut self, prefix: &str, targets: Vec<TrafficTarget>) {
+        self.targets.retain(|id, _| !id.starts_with(prefix));
+        for target in targets {
+            self.update(target);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+
+    /// 仍然有效的目标
+    pub fn targets(&self) -> Vec<TrafficTarget> {
+        let mut targets: Vec<TrafficTarget> = self.targets.values()
+            .filter(|(_, updated)| updated.elapsed() < TRAFFIC_TIMEOUT)
+            .map(|(target, _)| target.clone())
+            .collect();
+        targets.sort_by(|a, b| a.id.cmp(&b.id));
+        targets
+    }
+
+    /// 所有目标相对本机的位置（按距离排序）
+    pub fn nearby(&self, own: &FlightData) -> Vec<TrafficInfo> {
+        let mut nearby: Vec<TrafficInfo> = self.targets()
+            .iter()
+            .map(|target| TrafficInfo::new(own, target))
+            .collect();
+        nearby.sort_by(|a, b| a.distance_nm.total_cmp(&b.distance_nm));
+        nearby
+    }
+
+    /// 分析交通态势：活动通报、进近排序、跑道占用
+    ///
+    /// 地面上只在进跑道、起飞滑跑阶段判断跑道占用，停机位和滑行道上不判断
+    pub fn picture(&self, own: &FlightData, phase: FlightPhase) -> TrafficPicture {
+        let nearby = self.nearby(own);
+        let mut picture = TrafficPicture::default();
+
+        if !own.on_ground {
+            picture.advisories = nearby.iter()
+                .filter(|info| !info.target.on_ground)
+                .filter(|info| info.distance_nm <= ADVISORY_RANGE_NM)
+                .filter(|info| info.relative_altitude.abs() <= ADVISORY_ALTITUDE_FT)
+                .cloned()
+                .collect();
+
+            // 下降中：前方同向、高度不高于本机的飞机都排在本机之前
+            if own.vertical_speed < -300.0 {
+                let ahead: Vec<&TrafficInfo> = nearby.iter()
+                    .filter(|info| !info.target.on_ground)
+                    .filter(|info| info.distance_nm <= SEQUENCE_RANGE_NM)
+                    .filter(|info| info.relative_bearing(own).abs() <= SEQUENCE_SECTOR_DEG)
+                    .filter(|info| heading_difference(info.target.heading, own.heading) <= SEQUENCE_SECTOR_DEG)
+                    .filter(|info| info.relative_altitude <= 500.0)
+                    .collect();
+
+                if let Some(nearest) = ahead.first() {
+                    picture.sequence = Some(ahead.len() as u32 + 1);
+                    picture.following = Some((*nearest).clone());
+                }
+            }
+        }
+
+        // 起飞前或五边：正前方与本机同向的地面目标占用跑道
+        let on_final = !own.on_ground && own.altitude_agl < 1500.0 && own.vertical_speed < -300.0;
+        let lined_up = own.on_ground && phase == FlightPhase::LineUp;
+        if on_final || lined_up {
+            picture.runway_occupied = nearby.iter()
+                .filter(|info| info.target.on_ground)
+                .filter(|info| info.distance_nm <= RUNWAY_RANGE_NM)
+                .filter(|info| info.relative_bearing(own).abs() <= RUNWAY_SECTOR_DEG)
+                .find(|info| heading_difference(info.target.heading, own.heading) <= RUNWAY_ALIGNMENT_DEG)
+                .cloned();
+        }
+
+        picture
+    }
+}
+
+/// 两点间大圆距离（海里）
+pub fn distance_nm(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).to_radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * a.sqrt().asin()
+}
+
+/// 从点 1 看点 2 的真方位（0 ~ 360）
+pub fn bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlon = (lon2 - lon1).to_radians();
+
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    normalize(y.atan2(x).to_degrees())
+}
+
+fn normalize(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
+
+/// 两个航向的夹角（0 ~ 180）
+fn heading_difference(a: f64, b: f64) -> f64 {
+    let diff = normalize(a - b);
+    diff.min(360.0 - diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn own(altitude: f64, heading: f64, vertical_speed: f64, on_ground: bool) -> FlightData {
+        FlightData {
+            latitude: 40.0,
+            longitude: 116.0,
+            altitude,
+            altitude_agl: altitude,
+            heading,
+            vertical_speed,
+            on_ground,
+            ..FlightData::default()
+        }
+    }
+
+    /// 在本机北方 distance_nm 处的目标
+    fn target_north(id: &str, distance_nm: f64, altitude: f64, heading: f64, on_ground: bool) -> TrafficTarget {
+        TrafficTarget {
+            id: id.to_string(),
+            aircraft_type: "A320".to_string(),
+            latitude: 40.0 + distance_nm / 60.0,
+            longitude: 116.0,
+            altitude,
+            heading,
+            on_ground,
+            ..TrafficTarget::default()
+        }
+    }
+
+    #[test]
+    fn test_traffic_advisory_clock_position() {
+        let mut table = TrafficTable::new();
+        table.update(target_north("xplane-1", 5.0, 6000.0, 180.0, false));
+        table.update(target_north("xplane-2", 40.0, 6000.0, 180.0, false));  // 太远
+
+        // 本机航向 090，北方目标位于 9 点钟方向
+        let picture = table.picture(&own(5000.0, 90.0, 0.0, false), FlightPhase::Cruise);
+        assert_eq!(picture.advisories.len(), 1);
+        assert_eq!(picture.advisories[0].clock, 9);
+        assert!((picture.advisories[0].distance_nm - 5.0).abs() < 0.01);
+        assert!(picture.get_atc_context("en").contains("9 o'clock, 5 miles, A320"));
+    }
+
+    #[test]
+    fn test_sequence_following_traffic_ahead() {
+        let mut table = TrafficTable::new();
+        table.update(target_north("msfs-1", 4.0, 2500.0, 0.0, false));
+        table.update(target_north("msfs-2", 9.0, 2000.0, 5.0, false));
+        table.update(target_north("msfs-3", 6.0, 3000.0, 180.0, false));  // 反向，不参与排序
+
+        let picture = table.picture(&own(3000.0, 0.0, -700.0, false), FlightPhase::Cruise);
+        assert_eq!(picture.sequence, Some(3));
+        assert_eq!(picture.following.unwrap().target.id, "msfs-1");
+    }
+
+    #[test]
+    fn test_runway_occupied() {
+        let mut table = TrafficTable::new();
+        table.update(target_north("xplane-1", 1.0, 100.0, 2.0, true));
+
+        assert!(table.picture(&own(800.0, 0.0, -700.0, false), FlightPhase::Approach).runway_occupied.is_some());
+        // 地面目标在侧方（如滑行道上）
+        assert!(table.picture(&own(800.0, 90.0, -700.0, false), FlightPhase::Approach).runway_occupied.is_none());
+        // 巡航中不判断跑道
+        assert!(table.picture(&own(8000.0, 0.0, 0.0, false), FlightPhase::Cruise).runway_occupied.is_none());
+        // 在跑道上对正时前方地面目标占用跑道
+        assert!(table.picture(&own(100.0, 0.0, 0.0, true), FlightPhase::LineUp).runway_occupied.is_some());
+    }
+
+    #[test]
+    fn test_apron_traffic_is_not_runway_occupied() {
+        let mut table = TrafficTable::new();
+        table.update(target_north("xplane-1", 0.1, 100.0, 0.0, true));
+
+        // 停机位或滑行道上，前方同向的地面飞机不算占用跑道
+        for phase in [FlightPhase::PreFlight, FlightPhase::Taxi, FlightPhase::TaxiIn, FlightPhase::Parked] {
+            assert!(table.picture(&own(100.0, 0.0, 0.0, true), phase).runway_occupied.is_none(), "{:?}", phase);
+        }
+    }
+
+    #[test]
+    fn test_replace_snapshot() {
+        let mut table = TrafficTable::new();
+        table.update(target_north("xplane-1", 1.0, 0.0, 0.0, true));
+        table.replace("msfs-", vec![target_north("msfs-1", 1.0, 0.0, 0.0, true)]);
+        table.replace("msfs-", Vec::new());
+
+        let ids: Vec<String> = table.targets().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["xplane-1".to_string()]);
+    }
+}
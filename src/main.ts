@@ -2,6 +2,7 @@ import "./style.css";
 import { renderModelManager } from "./modelManager";
 
 const { invoke } = window.__TAURI__.tauri;
+const { listen } = window.__TAURI__.event;
 
 // UI Elements
 const connectionStatus = document.getElementById("connection-status")!;
@@ -11,6 +12,7 @@ const callsign = document.getElementById("callsign")!;
 const altitude = document.getElementById("altitude")!;
 const speed = document.getElementById("speed")!;
 const heading = document.getElementById("heading")!;
+const phase = document.getElementById("phase")!;
 const messagesContainer = document.getElementById("messages")!;
 const pttButton = document.getElementById("ptt-button")!;
 const connectButton = document.getElementById("connect-button")!;
@@ -179,6 +181,22 @@ languageSelect.addEventListener("change", (e) => {
   addMessage("atc", currentLanguage === "zh" ? "已切换到中文模式" : "Switched to English mode");
 });
 
+interface PhaseChange {
+  from: string;
+  to: string;
+  display_name: string;
+  timestamp: number;
+  latitude: number;
+  longitude: number;
+  altitude: number;
+}
+
+// 飞行阶段切换（后端推送）
+listen<PhaseChange>("phase-changed", (event) => {
+  phase.textContent = event.payload.display_name;
+  console.log(`Flight phase: ${event.payload.from} -> ${event.payload.to} at ${Math.round(event.payload.altitude)} ft`);
+});
+
 // Update flight info periodically
 setInterval(async () => {
   if (!isConnected) return;
@@ -186,18 +204,58 @@ setInterval(async () => {
   try {
     const flightData = await invoke("get_flight_data") as {
       callsign: string;
+      callsign_en: string;
+      callsign_zh: string;
       altitude: number;
       speed: number;
       heading: number;
     };
     
-    callsign.textContent = flightData.callsign;
+    callsign.textContent = flightData.callsign || "-";
+    callsign.title = `${flightData.callsign_en} / ${flightData.callsign_zh}`;
     altitude.textContent = `${Math.round(flightData.altitude)} ft`;
     speed.textContent = `${Math.round(flightData.speed)} kts`;
     heading.textContent = `${Math.round(flightData.heading)}°`;
   } catch (error) {
     console.error("Failed to get flight data:", error);
   }
+  
+  try {
+    const status = await invoke("get_connection_status") as {
+      health: "waiting" | "receiving" | "stale" | "incompatible" | "disconnected";
+      last_data_age_secs: number | null;
+      error: string | null;
+      bridge: {
+        state: "running" | "restarting" | "failed" | "stopped";
+        retry_in_secs?: number;
+        reason?: string;
+        restart_count: number;
+        recent_stderr: string[];
+      } | null;
+    };
+    
+    if (status.bridge?.state === "failed") {
+      connectionStatus.textContent = `MSFS 桥接已停止：${status.bridge.reason}`;
+      connectionStatus.style.color = "#ef4444";
+    } else if (status.bridge?.state === "restarting") {
+      connectionStatus.textContent = `MSFS 桥接已退出，${status.bridge.retry_in_secs} 秒后重启...`;
+      connectionStatus.style.color = "#f97316";
+    } else if (status.health === "receiving") {
+      connectionStatus.textContent = "已连接";
+      connectionStatus.style.color = "#4ade80";
+    } else if (status.health === "waiting") {
+      connectionStatus.textContent = "等待数据...";
+      connectionStatus.style.color = "#facc15";
+    } else if (status.health === "stale") {
+      connectionStatus.textContent = "数据中断，正在重新订阅...";
+      connectionStatus.style.color = "#f97316";
+    } else if (status.health === "incompatible") {
+      connectionStatus.textContent = status.error ?? "数据源版本不兼容";
+      connectionStatus.style.color = "#ef4444";
+    }
+  } catch (error) {
+    console.error("Failed to get connection status:", error);
+  }
 }, 1000);
 
 // Initial message